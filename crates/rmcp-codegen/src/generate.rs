@@ -0,0 +1,334 @@
+//! Generation of the client from a snapshot
+use std::collections::HashSet;
+
+use rmcp::model::{Prompt, ResourceTemplate, Tool};
+use serde_json::{Value, json};
+
+use crate::{
+    ServerSnapshot,
+    naming::{pascal_case, snake_case, unique},
+    render::{doc_comment, string_literal},
+    schema::TypeGenerator,
+};
+
+/// Generates the Rust source of a typed client from a [`ServerSnapshot`].
+///
+/// The generated source is meant to be included in a module of its own, and uses the
+/// `rmcp`, `serde` and `serde_json` crates:
+///
+/// ```rust,ignore
+/// #[allow(dead_code)]
+/// mod weather {
+///     include!(concat!(env!("OUT_DIR"), "/weather.rs"));
+/// }
+/// ```
+///
+/// It contains a client struct wrapping a `Peer<RoleClient>`, with:
+/// - a method per tool, taking the struct of its input schema and returning the type of
+///   its output schema, or the `CallToolResult` without one;
+/// - a `{name}_prompt` method per prompt, taking the struct of its arguments;
+/// - a `read_{name}` method per resource template, taking its variables.
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    client_name: Option<String>,
+}
+
+/// A part of a URI template
+enum TemplatePart<'a> {
+    Literal(&'a str),
+    /// `{name}`, or `{+name}` keeping the reserved characters
+    Variable {
+        name: &'a str,
+        reserved: bool,
+    },
+}
+
+/// Parse the simple and reserved expansions of a URI template, `None` for the templates
+/// using other operators
+fn parse_uri_template(template: &str) -> Option<Vec<TemplatePart<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(TemplatePart::Literal(&rest[..start]));
+        }
+        let end = start + rest[start..].find('}')?;
+        let expression = &rest[start + 1..end];
+        let (name, reserved) = match expression.strip_prefix('+') {
+            Some(name) => (name, true),
+            None => (expression, false),
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            return None;
+        }
+        parts.push(TemplatePart::Variable { name, reserved });
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Literal(rest));
+    }
+    Some(parts)
+}
+
+const EXPAND_URI_TEMPLATE_VALUE: &str = r#"
+/// Percent-encode a value expanded in a URI template, keeping the reserved characters if `reserved`
+fn expand_uri_template_value(value: &str, reserved: bool) -> String {
+    const RESERVED: &[u8] = b":/?#[]@!$&'()*+,;=";
+    let mut expanded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || (reserved && RESERVED.contains(&byte))
+        {
+            expanded.push(byte as char);
+        } else {
+            expanded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    expanded
+}
+"#;
+
+impl Generator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the client struct, by default the name of the server followed by
+    /// `Client`.
+    pub fn with_client_name(mut self, client_name: impl Into<String>) -> Self {
+        self.client_name = Some(client_name.into());
+        self
+    }
+
+    pub fn generate(&self, snapshot: &ServerSnapshot) -> String {
+        let mut types = TypeGenerator::new();
+        let server_name = snapshot
+            .server_info
+            .as_ref()
+            .map(|info| info.name.as_str())
+            .unwrap_or("server");
+        let client_name = types.reserve(
+            self.client_name
+                .as_deref()
+                .unwrap_or(&format!("{server_name}Client")),
+        );
+        let mut method_names: HashSet<String> = ["new", "peer"].map(String::from).into();
+        let mut methods = Vec::new();
+        for tool in &snapshot.tools {
+            let name = unique(&mut method_names, snake_case(&tool.name));
+            methods.push(self.tool_method(&mut types, &name, tool));
+        }
+        for prompt in &snapshot.prompts {
+            let name = unique(
+                &mut method_names,
+                format!("{}_prompt", snake_case(&prompt.name)),
+            );
+            methods.push(self.prompt_method(&mut types, &name, prompt));
+        }
+        let mut expands_uri_templates = false;
+        for template in &snapshot.resource_templates {
+            let name = unique(
+                &mut method_names,
+                format!("read_{}", snake_case(&template.name)),
+            );
+            let (method, expands) = self.resource_template_method(&name, template);
+            methods.push(method);
+            expands_uri_templates |= expands;
+        }
+
+        let mut source = String::new();
+        let server = match &snapshot.server_info {
+            Some(info) => format!("`{} {}`", info.name, info.version),
+            None => "the server".to_owned(),
+        };
+        source.push_str(&format!(
+            "// Generated by rmcp-codegen from {server}, do not edit by hand.\n\n"
+        ));
+        source.push_str(&doc_comment(
+            &format!("A typed client of the tools, prompts and resource templates of {server}"),
+            "",
+        ));
+        source.push_str(&format!(
+            "#[derive(Debug, Clone)]\n\
+             pub struct {client_name} {{\n    \
+                 peer: rmcp::Peer<rmcp::RoleClient>,\n\
+             }}\n\n\
+             impl {client_name} {{\n    \
+                 /// Create a client calling the server through the peer\n    \
+                 pub fn new(peer: rmcp::Peer<rmcp::RoleClient>) -> Self {{\n        \
+                     Self {{ peer }}\n    \
+                 }}\n\n    \
+                 /// The peer the server is called through\n    \
+                 pub fn peer(&self) -> &rmcp::Peer<rmcp::RoleClient> {{\n        \
+                     &self.peer\n    \
+                 }}\n"
+        ));
+        for method in methods {
+            source.push('\n');
+            source.push_str(&method);
+        }
+        source.push_str("}\n");
+        for definition in types.into_definitions() {
+            source.push('\n');
+            source.push_str(&definition);
+        }
+        if expands_uri_templates {
+            source.push_str(EXPAND_URI_TEMPLATE_VALUE);
+        }
+        source
+    }
+
+    fn tool_method(&self, types: &mut TypeGenerator, name: &str, tool: &Tool) -> String {
+        let type_name = pascal_case(&tool.name);
+        let params = types.struct_type(&format!("{type_name}Params"), &tool.input_schema);
+        let output = tool.output_schema.as_ref().map(|schema| {
+            types.type_of(
+                &Value::Object(schema.as_ref().clone()),
+                &format!("{type_name}Output"),
+                schema,
+            )
+        });
+        let mut method = String::new();
+        if let Some(description) = &tool.description {
+            method.push_str(&doc_comment(description, "    "));
+        }
+        let (param, arguments) = match &params {
+            Some(params) => (format!(", params: {params}"), "params"),
+            None => (String::new(), "()"),
+        };
+        let (output, call) = match &output {
+            Some(output) => (output.as_str(), "call_tool_typed"),
+            None => ("rmcp::model::CallToolResult", "call_tool_with_arguments"),
+        };
+        method.push_str(&format!(
+            "    pub async fn {name}(&self{param}) -> Result<{output}, rmcp::service::TypedToolCallError> {{\n        \
+                 self.peer.{call}({}, {arguments}).await\n    \
+             }}\n",
+            string_literal(&tool.name)
+        ));
+        method
+    }
+
+    fn prompt_method(&self, types: &mut TypeGenerator, name: &str, prompt: &Prompt) -> String {
+        // the arguments of a prompt are strings, described by a schema of their own
+        let arguments = prompt.arguments.as_deref().unwrap_or_default();
+        let properties: serde_json::Map<String, Value> = arguments
+            .iter()
+            .map(|argument| {
+                let mut schema = json!({ "type": "string" });
+                if let Some(description) = &argument.description {
+                    schema["description"] = description.clone().into();
+                }
+                (argument.name.clone(), schema)
+            })
+            .collect();
+        let required: Vec<_> = arguments
+            .iter()
+            .filter(|argument| argument.required == Some(true))
+            .map(|argument| argument.name.clone())
+            .collect();
+        let schema = json!({ "type": "object", "properties": properties, "required": required });
+        let Value::Object(schema) = schema else {
+            unreachable!("an object")
+        };
+        let params = types.struct_type(
+            &format!("{}PromptArguments", pascal_case(&prompt.name)),
+            &schema,
+        );
+
+        let mut method = String::new();
+        if let Some(description) = &prompt.description {
+            method.push_str(&doc_comment(description, "    "));
+        }
+        let (param, arguments) = match &params {
+            Some(params) => (
+                format!(", arguments: {params}"),
+                "match serde_json::to_value(arguments) {\n            \
+                     Ok(serde_json::Value::Object(arguments)) => Some(arguments),\n            \
+                     _ => None,\n        \
+                 }",
+            ),
+            None => (String::new(), "None"),
+        };
+        method.push_str(&format!(
+            "    pub async fn {name}(&self{param}) -> Result<rmcp::model::GetPromptResult, rmcp::ServiceError> {{\n        \
+                 let arguments = {arguments};\n        \
+                 self.peer\n            \
+                     .get_prompt(rmcp::model::GetPromptRequestParam {{\n                \
+                         name: {}.to_owned(),\n                \
+                         arguments,\n            \
+                     }})\n            \
+                     .await\n    \
+             }}\n",
+            string_literal(&prompt.name)
+        ));
+        method
+    }
+
+    /// The method, and whether it expands its URI template
+    fn resource_template_method(&self, name: &str, template: &ResourceTemplate) -> (String, bool) {
+        let mut method = String::new();
+        let Some(parts) = parse_uri_template(&template.uri_template) else {
+            // a template this generator can't expand, the caller does
+            let doc = format!(
+                "{}\n\nThe URI expands the template `{}`.",
+                template.description.as_deref().unwrap_or_default(),
+                template.uri_template
+            );
+            method.push_str(&doc_comment(&doc, "    "));
+            method.push_str(&format!(
+                "    pub async fn {name}(&self, uri: impl Into<String>) -> Result<rmcp::model::ReadResourceResult, rmcp::ServiceError> {{\n        \
+                     self.peer\n            \
+                         .read_resource(rmcp::model::ReadResourceRequestParam {{ uri: uri.into() }})\n            \
+                         .await\n    \
+                 }}\n"
+            ));
+            return (method, false);
+        };
+        if let Some(description) = &template.description {
+            method.push_str(&doc_comment(description, "    "));
+        }
+        let mut params = Vec::new();
+        let mut param_names = HashSet::from(["self".to_owned()]);
+        let mut format = String::new();
+        let mut values = Vec::new();
+        for part in parts {
+            match part {
+                TemplatePart::Literal(literal) => {
+                    format.push_str(&literal.replace('{', "{{").replace('}', "}}"))
+                }
+                TemplatePart::Variable { name, reserved } => {
+                    let param = unique(&mut param_names, snake_case(name));
+                    format.push_str("{}");
+                    values.push(format!("expand_uri_template_value({param}, {reserved})"));
+                    params.push(format!(", {param}: &str"));
+                }
+            }
+        }
+        let expands = !values.is_empty();
+        let uri = if expands {
+            format!(
+                "format!({}, {})",
+                string_literal(&format),
+                values.join(", ")
+            )
+        } else {
+            format!("{}.to_owned()", string_literal(&template.uri_template))
+        };
+        method.push_str(&format!(
+            "    pub async fn {name}(&self{}) -> Result<rmcp::model::ReadResourceResult, rmcp::ServiceError> {{\n        \
+                 let uri = {uri};\n        \
+                 self.peer\n            \
+                     .read_resource(rmcp::model::ReadResourceRequestParam {{ uri }})\n            \
+                     .await\n    \
+             }}\n",
+            params.concat()
+        ));
+        (method, expands)
+    }
+}
@@ -0,0 +1,56 @@
+//! Generate typed Rust clients of Model Context Protocol servers.
+//!
+//! A [`ServerSnapshot`] of the tools, prompts and resource templates of a server is fetched
+//! through any rmcp client transport, or loaded from a JSON file saved before. The
+//! [`Generator`] then converts it into the source of a client wrapping a
+//! `Peer<RoleClient>`, with the input and output schemas of the tools converted into
+//! Rust types.
+//!
+//! In a build script, generating from a snapshot checked in the repository:
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), rmcp_codegen::CodegenError> {
+//! use rmcp_codegen::{Generator, ServerSnapshot};
+//!
+//! let snapshot = ServerSnapshot::load("weather.json")?;
+//! let source = Generator::new()
+//!     .with_client_name("WeatherClient")
+//!     .generate(&snapshot);
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! std::fs::write(format!("{out_dir}/weather.rs"), source)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `rmcp-codegen` binary does the same from the command line, and saves snapshots:
+//!
+//! ```sh
+//! rmcp-codegen snapshot --output weather.json -- npx -y weather-server
+//! rmcp-codegen generate --snapshot weather.json --output src/weather.rs
+//! ```
+mod generate;
+mod naming;
+mod render;
+mod schema;
+mod snapshot;
+
+pub use generate::Generator;
+pub use snapshot::ServerSnapshot;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodegenError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("fail to connect to the server: {0}")]
+    Initialize(Box<rmcp::service::ClientInitializeError>),
+    #[error("fail to list the server features: {0}")]
+    Service(#[from] rmcp::ServiceError),
+}
+
+impl From<rmcp::service::ClientInitializeError> for CodegenError {
+    fn from(error: rmcp::service::ClientInitializeError) -> Self {
+        CodegenError::Initialize(Box::new(error))
+    }
+}
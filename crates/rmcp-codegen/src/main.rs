@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, bail};
+use clap::{Args, Parser, Subcommand};
+use rmcp::transport::{StreamableHttpClientTransport, TokioChildProcess};
+use rmcp_codegen::{Generator, ServerSnapshot};
+
+/// Generate typed Rust clients of Model Context Protocol servers
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Save the tools, prompts and resource templates of a server as JSON
+    Snapshot {
+        #[command(flatten)]
+        source: Source,
+        /// The file to write, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Generate the source of a client of a server
+    Generate {
+        #[command(flatten)]
+        source: Source,
+        /// The name of the client struct, by default the name of the server followed by `Client`
+        #[arg(long)]
+        client_name: Option<String>,
+        /// The file to write, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Where the server is
+#[derive(Args)]
+struct Source {
+    /// A saved snapshot of the server
+    #[arg(long, conflicts_with_all = ["url", "command"])]
+    snapshot: Option<PathBuf>,
+    /// The url of a streamable http server
+    #[arg(long, conflicts_with = "command")]
+    url: Option<String>,
+    /// The command running a stdio server
+    #[arg(last = true)]
+    command: Vec<String>,
+}
+
+impl Source {
+    async fn snapshot(self) -> anyhow::Result<ServerSnapshot> {
+        let snapshot = if let Some(path) = self.snapshot {
+            ServerSnapshot::load(&path).with_context(|| format!("loading {}", path.display()))?
+        } else if let Some(url) = self.url {
+            ServerSnapshot::fetch_from(StreamableHttpClientTransport::from_uri(url)).await?
+        } else if let Some((program, args)) = self.command.split_first() {
+            let mut command = tokio::process::Command::new(program);
+            command.args(args);
+            ServerSnapshot::fetch_from(TokioChildProcess::new(command)?).await?
+        } else {
+            bail!("expected a --snapshot, an --url, or a command after --");
+        };
+        Ok(snapshot)
+    }
+}
+
+fn write(output: Option<PathBuf>, content: &str) -> anyhow::Result<()> {
+    match output {
+        Some(path) => {
+            std::fs::write(&path, content).with_context(|| format!("writing {}", path.display()))
+        }
+        None => {
+            print!("{content}");
+            Ok(())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Snapshot { source, output } => {
+            let snapshot = source.snapshot().await?;
+            write(output, &(serde_json::to_string_pretty(&snapshot)? + "\n"))
+        }
+        Command::Generate {
+            source,
+            client_name,
+            output,
+        } => {
+            let snapshot = source.snapshot().await?;
+            let mut generator = Generator::new();
+            if let Some(client_name) = client_name {
+                generator = generator.with_client_name(client_name);
+            }
+            write(output, &generator.generate(&snapshot))
+        }
+    }
+}
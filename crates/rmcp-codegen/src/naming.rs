@@ -0,0 +1,103 @@
+//! Rust identifiers for the names of tools, prompts, properties and definitions
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn escape_keyword(mut ident: String) -> String {
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+/// `get_weather`, `get-weather` and `getWeather` are all `GetWeather`
+pub(crate) fn pascal_case(name: &str) -> String {
+    let mut ident: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, 'T');
+    }
+    escape_keyword(ident)
+}
+
+/// `getWeather`, `get-weather` and `GetWeather` are all `get_weather`
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut ident = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+        } else if c.is_ascii_uppercase() {
+            if previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                && !ident.ends_with('_')
+            {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+        } else {
+            ident.push(c);
+        }
+        previous = Some(c);
+    }
+    let mut ident = ident.trim_end_matches('_').to_owned();
+    if ident.is_empty() {
+        ident.push_str("value");
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    escape_keyword(ident)
+}
+
+/// The first of `name`, `name2`, `name3`... not taken yet, which is then taken
+pub(crate) fn unique(taken: &mut std::collections::HashSet<String>, name: String) -> String {
+    let mut candidate = name.clone();
+    let mut index = 1;
+    while taken.contains(&candidate) {
+        index += 1;
+        candidate = format!("{name}{index}");
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cases() {
+        for name in ["get_weather", "get-weather", "getWeather", "GetWeather"] {
+            assert_eq!(pascal_case(name), "GetWeather");
+            assert_eq!(snake_case(name), "get_weather");
+        }
+        assert_eq!(snake_case("type"), "type_");
+        assert_eq!(snake_case("3d"), "_3d");
+        assert_eq!(snake_case("--"), "value");
+        assert_eq!(pascal_case("3d"), "T3d");
+        assert_eq!(pascal_case("self"), "Self_");
+    }
+
+    #[test]
+    fn test_unique() {
+        let mut taken = std::collections::HashSet::new();
+        assert_eq!(unique(&mut taken, "name".to_owned()), "name");
+        assert_eq!(unique(&mut taken, "name".to_owned()), "name2");
+        assert_eq!(unique(&mut taken, "name".to_owned()), "name3");
+    }
+}
@@ -0,0 +1,21 @@
+//! Rendering of Rust source text
+
+/// A `///` doc comment, one line per line of the text
+pub(crate) fn doc_comment(text: &str, indent: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() {
+                format!("{indent}///\n")
+            } else {
+                format!("{indent}/// {line}\n")
+            }
+        })
+        .collect()
+}
+
+/// A string literal of the value
+pub(crate) fn string_literal(value: &str) -> String {
+    format!("{value:?}")
+}
@@ -0,0 +1,384 @@
+//! Conversion of JSON schemas into Rust types
+//!
+//! Objects with properties become structs, and string enumerations become enums. The
+//! definitions of `$defs` are converted once, under their own name. The schemas without
+//! a Rust counterpart, like unions of several types, become a `serde_json::Value`.
+use std::collections::{HashMap, HashSet};
+
+use rmcp::model::JsonObject;
+use serde_json::Value;
+
+use crate::{
+    naming::{pascal_case, snake_case, unique},
+    render::{doc_comment, string_literal},
+};
+
+const VALUE: &str = "serde_json::Value";
+
+/// The names a generated type can't take, as they are used unqualified
+const PRELUDE: &[&str] = &[
+    "Box", "Err", "None", "Ok", "Option", "Result", "Some", "String", "Vec",
+];
+
+pub(crate) struct TypeGenerator {
+    /// The rendered named types, in the order they were named
+    definitions: Vec<String>,
+    names: HashSet<String>,
+    /// The types of the converted `$defs`, by their name and schema
+    refs: HashMap<(String, String), String>,
+    /// The `$defs` being converted, by their name and schema, to the name reserved for them
+    pending: HashMap<(String, String), String>,
+    /// The name reserved for the next named type, which is a `$defs` entry
+    next_name: Option<String>,
+}
+
+impl TypeGenerator {
+    pub(crate) fn new() -> Self {
+        Self {
+            definitions: Vec::new(),
+            names: PRELUDE.iter().map(|name| name.to_string()).collect(),
+            refs: HashMap::new(),
+            pending: HashMap::new(),
+            next_name: None,
+        }
+    }
+
+    /// Reserve a type name, made unique
+    pub(crate) fn reserve(&mut self, name: &str) -> String {
+        unique(&mut self.names, pascal_case(name))
+    }
+
+    pub(crate) fn into_definitions(self) -> Vec<String> {
+        self.definitions
+    }
+
+    /// The struct of an object schema with properties, `None` for the other schemas
+    pub(crate) fn struct_type(&mut self, name: &str, schema: &JsonObject) -> Option<String> {
+        let has_properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .is_some_and(|properties| !properties.is_empty());
+        has_properties.then(|| {
+            let name = self.reserve(name);
+            self.define_struct(name, schema, schema)
+        })
+    }
+
+    /// The Rust type of a schema, named after `name` if it needs a type of its own
+    pub(crate) fn type_of(&mut self, schema: &Value, name: &str, root: &JsonObject) -> String {
+        let Value::Object(schema) = schema else {
+            return VALUE.to_owned();
+        };
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            return self.reference(reference, root);
+        }
+        let ty = self.non_null_type(schema, name, root);
+        if schema.get("nullable") == Some(&Value::Bool(true)) {
+            optional(ty)
+        } else {
+            ty
+        }
+    }
+
+    fn name_for(&mut self, name: &str) -> String {
+        self.next_name.take().unwrap_or_else(|| self.reserve(name))
+    }
+
+    fn non_null_type(&mut self, schema: &JsonObject, name: &str, root: &JsonObject) -> String {
+        for key in ["anyOf", "oneOf"] {
+            if let Some(Value::Array(variants)) = schema.get(key) {
+                let (nulls, others): (Vec<_>, Vec<_>) =
+                    variants.iter().partition(|variant| is_null(variant));
+                return match others.as_slice() {
+                    [variant] if nulls.is_empty() => self.type_of(variant, name, root),
+                    [variant] => optional(self.type_of(variant, name, root)),
+                    _ => VALUE.to_owned(),
+                };
+            }
+        }
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            return match all.as_slice() {
+                [schema] => self.type_of(schema, name, root),
+                _ => VALUE.to_owned(),
+            };
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            let values: Option<Vec<&str>> = values.iter().map(Value::as_str).collect();
+            return match values {
+                Some(values) => self.define_enum(name, schema, &values),
+                None => VALUE.to_owned(),
+            };
+        }
+        if schema.get("const").is_some_and(Value::is_string) {
+            return "String".to_owned();
+        }
+        match schema.get("type") {
+            Some(Value::String(ty)) => self.typed(ty, schema, name, root),
+            Some(Value::Array(types)) => {
+                let others: Vec<_> = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|ty| *ty != "null")
+                    .collect();
+                match others.as_slice() {
+                    [ty] if others.len() == types.len() => self.typed(ty, schema, name, root),
+                    [ty] => optional(self.typed(ty, schema, name, root)),
+                    _ => VALUE.to_owned(),
+                }
+            }
+            None if schema.contains_key("properties") => self.typed("object", schema, name, root),
+            _ => VALUE.to_owned(),
+        }
+    }
+
+    fn typed(&mut self, ty: &str, schema: &JsonObject, name: &str, root: &JsonObject) -> String {
+        let format = schema.get("format").and_then(Value::as_str);
+        match ty {
+            "string" => "String".to_owned(),
+            "boolean" => "bool".to_owned(),
+            "null" => "()".to_owned(),
+            "integer" => match format {
+                Some("int8") => "i8",
+                Some("int16") => "i16",
+                Some("int32") => "i32",
+                Some("uint8") => "u8",
+                Some("uint16") => "u16",
+                Some("uint32") => "u32",
+                Some("uint" | "uint64") => "u64",
+                _ => "i64",
+            }
+            .to_owned(),
+            "number" => match format {
+                Some("float") => "f32",
+                _ => "f64",
+            }
+            .to_owned(),
+            "array" => match schema.get("items") {
+                Some(items @ Value::Object(_)) => {
+                    format!("Vec<{}>", self.type_of(items, &format!("{name}Item"), root))
+                }
+                _ => format!("Vec<{VALUE}>"),
+            },
+            "object" => {
+                if schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .is_some_and(|properties| !properties.is_empty())
+                {
+                    let name = self.name_for(name);
+                    return self.define_struct(name, schema, root);
+                }
+                match schema.get("additionalProperties") {
+                    Some(values @ Value::Object(_)) => format!(
+                        "std::collections::BTreeMap<String, {}>",
+                        self.type_of(values, &format!("{name}Value"), root)
+                    ),
+                    _ => format!("serde_json::Map<String, {VALUE}>"),
+                }
+            }
+            _ => VALUE.to_owned(),
+        }
+    }
+
+    fn reference(&mut self, reference: &str, root: &JsonObject) -> String {
+        let definition = [("#/$defs/", "$defs"), ("#/definitions/", "definitions")]
+            .into_iter()
+            .find_map(|(prefix, key)| {
+                let name = reference.strip_prefix(prefix)?;
+                let definition = root.get(key)?.get(name)?;
+                Some((name, definition))
+            });
+        let Some((definition_name, definition)) = definition else {
+            return VALUE.to_owned();
+        };
+        let key = (definition_name.to_owned(), definition.to_string());
+        if let Some(ty) = self.refs.get(&key) {
+            return ty.clone();
+        }
+        // a recursive reference
+        if let Some(name) = self.pending.get(&key) {
+            return format!("Box<{name}>");
+        }
+        let name = self.reserve(definition_name);
+        self.pending.insert(key.clone(), name.clone());
+        let previous_name = self.next_name.replace(name.clone());
+        let ty = self.type_of(definition, definition_name, root);
+        // the definition has no named type of its own
+        if self.next_name.take().is_some() {
+            self.names.remove(&name);
+        }
+        self.next_name = previous_name;
+        self.pending.remove(&key);
+        self.refs.insert(key, ty.clone());
+        ty
+    }
+
+    fn define_struct(&mut self, name: String, schema: &JsonObject, root: &JsonObject) -> String {
+        // reserve the place of the struct, before the types of its fields
+        let index = self.definitions.len();
+        self.definitions.push(String::new());
+        let required: HashSet<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        let mut fields = String::new();
+        let mut field_names = HashSet::new();
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (property, property_schema) in properties.into_iter().flatten() {
+            let field = unique(&mut field_names, snake_case(property));
+            let mut ty = self.type_of(
+                property_schema,
+                &format!("{name}{}", pascal_case(property)),
+                root,
+            );
+            if let Some(description) = property_schema.get("description").and_then(Value::as_str) {
+                fields.push_str(&doc_comment(description, "    "));
+            }
+            if field != *property {
+                fields.push_str(&format!(
+                    "    #[serde(rename = {})]\n",
+                    string_literal(property)
+                ));
+            }
+            if !required.contains(property.as_str()) {
+                ty = optional(ty);
+                fields
+                    .push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+            }
+            fields.push_str(&format!("    pub {field}: {ty},\n"));
+        }
+        let mut definition = String::new();
+        if let Some(description) = description(schema) {
+            definition.push_str(&doc_comment(description, ""));
+        }
+        definition
+            .push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+        definition.push_str(&format!("pub struct {name} {{\n{fields}}}\n"));
+        self.definitions[index] = definition;
+        name
+    }
+
+    fn define_enum(&mut self, name: &str, schema: &JsonObject, values: &[&str]) -> String {
+        let name = self.name_for(name);
+        let mut variants = String::new();
+        let mut variant_names = HashSet::new();
+        for value in values {
+            let variant = unique(&mut variant_names, pascal_case(value));
+            if variant != *value {
+                variants.push_str(&format!(
+                    "    #[serde(rename = {})]\n",
+                    string_literal(value)
+                ));
+            }
+            variants.push_str(&format!("    {variant},\n"));
+        }
+        let mut definition = String::new();
+        if let Some(description) = description(schema) {
+            definition.push_str(&doc_comment(description, ""));
+        }
+        definition.push_str(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n",
+        );
+        definition.push_str(&format!("pub enum {name} {{\n{variants}}}\n"));
+        self.definitions.push(definition);
+        name
+    }
+}
+
+/// `{"type": "null"}`, or `{"const": null}` as generated by schemars
+fn is_null(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("null")
+        || schema.get("const") == Some(&Value::Null)
+}
+
+fn optional(ty: String) -> String {
+    if ty.starts_with("Option<") {
+        ty
+    } else {
+        format!("Option<{ty}>")
+    }
+}
+
+fn description(schema: &JsonObject) -> Option<&str> {
+    schema.get("description").and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: Value) -> JsonObject {
+        let Value::Object(object) = value else {
+            panic!("not an object")
+        };
+        object
+    }
+
+    #[test]
+    fn test_types() {
+        let root = object(json!({
+            "type": "object",
+            "properties": {
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "scores": { "type": "object", "additionalProperties": { "type": "number" } },
+                "extra": { "type": "object" },
+                "limit": { "type": ["integer", "null"], "format": "uint32" },
+                "either": { "anyOf": [{ "type": "string" }, { "type": "integer" }] },
+            },
+            "required": ["tags", "scores", "extra", "limit", "either"],
+        }));
+        let mut types = TypeGenerator::new();
+        assert_eq!(
+            types.struct_type("params", &root).as_deref(),
+            Some("Params")
+        );
+        let definitions = types.into_definitions();
+        assert_eq!(definitions.len(), 1);
+        let definition = &definitions[0];
+        assert!(definition.contains("pub tags: Vec<String>,"));
+        assert!(definition.contains("pub scores: std::collections::BTreeMap<String, f64>,"));
+        assert!(definition.contains("pub extra: serde_json::Map<String, serde_json::Value>,"));
+        assert!(definition.contains("pub limit: Option<u32>,"));
+        assert!(definition.contains("pub either: serde_json::Value,"));
+    }
+
+    #[test]
+    fn test_recursive_reference() {
+        let root = object(json!({
+            "type": "object",
+            "properties": { "root": { "$ref": "#/$defs/Node" } },
+            "required": ["root"],
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/Node" } },
+                        "parent": { "$ref": "#/$defs/Node" },
+                    },
+                    "required": ["children"],
+                },
+            },
+        }));
+        let mut types = TypeGenerator::new();
+        types.struct_type("tree", &root);
+        let definitions = types.into_definitions();
+        assert_eq!(definitions.len(), 2);
+        assert!(definitions[0].contains("pub root: Node,"));
+        assert!(definitions[1].contains("pub struct Node {"));
+        assert!(definitions[1].contains("pub children: Vec<Box<Node>>,"));
+        assert!(definitions[1].contains("pub parent: Option<Box<Node>>,"));
+    }
+
+    #[test]
+    fn test_no_properties() {
+        let mut types = TypeGenerator::new();
+        let schema = object(json!({ "type": "object", "properties": {} }));
+        assert_eq!(types.struct_type("params", &schema), None);
+        assert!(types.into_definitions().is_empty());
+    }
+}
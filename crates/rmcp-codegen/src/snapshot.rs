@@ -0,0 +1,82 @@
+//! The tools, prompts and resource templates of a server, saved as JSON
+use std::path::Path;
+
+use rmcp::{
+    Peer, RoleClient, ServiceExt,
+    model::{Implementation, Prompt, ResourceTemplate, Tool},
+    transport::IntoTransport,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::CodegenError;
+
+/// What a client is generated from.
+///
+/// It is fetched from a live server with [`ServerSnapshot::fetch`], and can be saved to
+/// a file with [`ServerSnapshot::save`], so the client can be generated again offline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerSnapshot {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_info: Option<Implementation>,
+    #[serde(default)]
+    pub tools: Vec<Tool>,
+    #[serde(default)]
+    pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub resource_templates: Vec<ResourceTemplate>,
+}
+
+impl ServerSnapshot {
+    /// List all the tools, prompts and resource templates of the server.
+    ///
+    /// The features the server doesn't declare in its capabilities are skipped. They are
+    /// sorted by name, so the snapshots of a server don't depend on the order it lists
+    /// them in.
+    pub async fn fetch(peer: &Peer<RoleClient>) -> Result<Self, CodegenError> {
+        let server_info = peer.peer_info();
+        let capabilities = server_info.map(|info| &info.capabilities);
+        let mut snapshot = Self {
+            server_info: server_info.map(|info| info.server_info.clone()),
+            ..Default::default()
+        };
+        if capabilities.is_none_or(|capabilities| capabilities.tools.is_some()) {
+            snapshot.tools = peer.list_all_tools().await?;
+        }
+        if capabilities.is_none_or(|capabilities| capabilities.prompts.is_some()) {
+            snapshot.prompts = peer.list_all_prompts().await?;
+        }
+        if capabilities.is_none_or(|capabilities| capabilities.resources.is_some()) {
+            snapshot.resource_templates = peer.list_all_resource_templates().await?;
+        }
+        snapshot.tools.sort_by(|a, b| a.name.cmp(&b.name));
+        snapshot.prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        snapshot
+            .resource_templates
+            .sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(snapshot)
+    }
+
+    /// Connect to a server through any client transport, and fetch its snapshot.
+    pub async fn fetch_from<T, E, A>(transport: T) -> Result<Self, CodegenError>
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let client = ().serve(transport).await?;
+        let snapshot = Self::fetch(client.peer()).await;
+        let _ = client.cancel().await;
+        snapshot
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CodegenError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CodegenError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
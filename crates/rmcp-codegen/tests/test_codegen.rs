@@ -0,0 +1,293 @@
+//cargo test -p rmcp-codegen --test test_codegen
+use rmcp::{
+    ErrorData, Json, RoleServer, ServerHandler, ServiceExt,
+    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    model::{
+        AnnotateAble, GetPromptRequestParam, GetPromptResult, Implementation, ListPromptsResult,
+        ListResourceTemplatesResult, PaginatedRequestParam, Prompt, PromptArgument, PromptMessage,
+        PromptMessageRole, RawResourceTemplate, ReadResourceRequestParam, ReadResourceResult,
+        ResourceContents, ServerCapabilities, ServerInfo,
+    },
+    schemars,
+    service::RequestContext,
+    tool, tool_handler, tool_router,
+};
+use rmcp_codegen::{Generator, ServerSnapshot};
+use serde::{Deserialize, Serialize};
+
+const SNAPSHOT: &str = "tests/test_codegen/snapshot.json";
+const CLIENT: &str = "tests/test_codegen/client.rs";
+
+#[allow(dead_code)]
+mod generated {
+    include!("test_codegen/client.rs");
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ForecastRequest {
+    /// The city of the forecast
+    city: String,
+    /// Celsius by default
+    unit: Option<Unit>,
+    /// The number of days
+    days: u8,
+}
+
+/// Where a forecast is
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct Location {
+    name: String,
+    r#type: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct Forecast {
+    location: Location,
+    unit: Unit,
+    temperatures: Vec<f64>,
+    days_ahead: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct Weather {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Weather {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Get the forecast of a city
+    #[tool(name = "get-forecast")]
+    async fn get_forecast(
+        &self,
+        Parameters(request): Parameters<ForecastRequest>,
+    ) -> Json<Forecast> {
+        let unit = request.unit.unwrap_or(Unit::Celsius);
+        Json(Forecast {
+            location: Location {
+                name: request.city,
+                r#type: "city".to_owned(),
+            },
+            unit,
+            temperatures: vec![20.5; request.days as usize],
+            days_ahead: None,
+        })
+    }
+
+    /// Check the server is up
+    #[tool]
+    async fn ping(&self) -> String {
+        "pong".to_owned()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Weather {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .build(),
+            server_info: Implementation {
+                name: "weather".to_owned(),
+                version: "1.0.0".to_owned(),
+                ..Implementation::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let arguments = vec![
+            PromptArgument {
+                name: "city".to_owned(),
+                title: None,
+                description: Some("The city to talk about".to_owned()),
+                required: Some(true),
+            },
+            PromptArgument {
+                name: "tone".to_owned(),
+                title: None,
+                description: None,
+                required: None,
+            },
+        ];
+        Ok(ListPromptsResult {
+            prompts: vec![Prompt::new(
+                "weather-report",
+                Some("Write a weather report"),
+                Some(arguments),
+            )],
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let arguments = request.arguments.unwrap_or_default();
+        let text = format!(
+            "Write a {} weather report of {}",
+            arguments
+                .get("tone")
+                .and_then(|tone| tone.as_str())
+                .unwrap_or("short"),
+            arguments
+                .get("city")
+                .and_then(|city| city.as_str())
+                .unwrap_or_default(),
+        );
+        Ok(GetPromptResult {
+            description: None,
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let template = |uri_template: &str, name: &str| {
+            RawResourceTemplate {
+                uri_template: uri_template.to_owned(),
+                name: name.to_owned(),
+                title: None,
+                description: Some("The history of a city".to_owned()),
+                mime_type: None,
+            }
+            .no_annotation()
+        };
+        Ok(ListResourceTemplatesResult {
+            resource_templates: vec![
+                template("weather://{city}/history/{+date}", "history"),
+                template("weather://{city}/search{?query}", "search"),
+            ],
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text("sunny", request.uri)],
+        })
+    }
+}
+
+fn serve() -> tokio::io::DuplexStream {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Weather::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    client_transport
+}
+
+/// Compare to the expected file, updating it instead if `UPDATE_CODEGEN` is set
+fn compare(actual: &str, expected_file: &str) {
+    // UPDATE_CODEGEN=1 cargo test -p rmcp-codegen --test test_codegen
+    if std::env::var("UPDATE_CODEGEN").is_ok() {
+        std::fs::write(expected_file, actual).expect("Failed to update expected file");
+        return;
+    }
+    let expected = std::fs::read_to_string(expected_file).expect("Failed to read expected file");
+    assert_eq!(
+        actual, expected,
+        "{expected_file} differs, set UPDATE_CODEGEN=1 to update it"
+    );
+}
+
+#[tokio::test]
+async fn test_snapshot() -> anyhow::Result<()> {
+    let snapshot = ServerSnapshot::fetch_from(serve()).await?;
+    assert_eq!(snapshot.tools.len(), 2);
+    assert_eq!(snapshot.prompts.len(), 1);
+    assert_eq!(snapshot.resource_templates.len(), 2);
+    compare(&(serde_json::to_string_pretty(&snapshot)? + "\n"), SNAPSHOT);
+    assert_eq!(ServerSnapshot::load(SNAPSHOT)?, snapshot);
+    Ok(())
+}
+
+#[test]
+fn test_generate() -> anyhow::Result<()> {
+    let source = Generator::new().generate(&ServerSnapshot::load(SNAPSHOT)?);
+    compare(&source, CLIENT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generated_client() -> anyhow::Result<()> {
+    use generated::*;
+
+    let client = ().serve(serve()).await?;
+    let weather = WeatherClient::new(client.peer().clone());
+
+    let forecast = weather
+        .get_forecast(GetForecastParams {
+            city: "Paris".to_owned(),
+            unit: Some(Unit::Fahrenheit),
+            days: 2,
+        })
+        .await?;
+    assert_eq!(forecast.location.name, "Paris");
+    assert_eq!(forecast.location.type_, "city");
+    assert_eq!(forecast.unit, Unit::Fahrenheit);
+    assert_eq!(forecast.temperatures, [20.5, 20.5]);
+    assert_eq!(forecast.days_ahead, None);
+
+    let pong = weather.ping().await?;
+    assert_eq!(pong.content[0].as_text().unwrap().text, "pong");
+
+    let prompt = weather
+        .weather_report_prompt(WeatherReportPromptArguments {
+            city: "Paris".to_owned(),
+            tone: None,
+        })
+        .await?;
+    let text = match &prompt.messages[0].content {
+        rmcp::model::PromptMessageContent::Text { text } => text.clone(),
+        content => panic!("unexpected content {content:?}"),
+    };
+    assert_eq!(text, "Write a short weather report of Paris");
+
+    let history = weather.read_history("New York", "2025/06").await?;
+    let ResourceContents::TextResourceContents { uri, .. } = &history.contents[0] else {
+        panic!("unexpected contents");
+    };
+    assert_eq!(uri, "weather://New%20York/history/2025/06");
+
+    let search = weather
+        .read_search("weather://Paris/search?query=rain")
+        .await?;
+    assert_eq!(search.contents.len(), 1);
+
+    client.cancel().await?;
+    Ok(())
+}
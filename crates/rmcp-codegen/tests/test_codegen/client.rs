@@ -0,0 +1,122 @@
+// Generated by rmcp-codegen from `weather 1.0.0`, do not edit by hand.
+
+/// A typed client of the tools, prompts and resource templates of `weather 1.0.0`
+#[derive(Debug, Clone)]
+pub struct WeatherClient {
+    peer: rmcp::Peer<rmcp::RoleClient>,
+}
+
+impl WeatherClient {
+    /// Create a client calling the server through the peer
+    pub fn new(peer: rmcp::Peer<rmcp::RoleClient>) -> Self {
+        Self { peer }
+    }
+
+    /// The peer the server is called through
+    pub fn peer(&self) -> &rmcp::Peer<rmcp::RoleClient> {
+        &self.peer
+    }
+
+    /// Get the forecast of a city
+    pub async fn get_forecast(&self, params: GetForecastParams) -> Result<GetForecastOutput, rmcp::service::TypedToolCallError> {
+        self.peer.call_tool_typed("get-forecast", params).await
+    }
+
+    /// Check the server is up
+    pub async fn ping(&self) -> Result<rmcp::model::CallToolResult, rmcp::service::TypedToolCallError> {
+        self.peer.call_tool_with_arguments("ping", ()).await
+    }
+
+    /// Write a weather report
+    pub async fn weather_report_prompt(&self, arguments: WeatherReportPromptArguments) -> Result<rmcp::model::GetPromptResult, rmcp::ServiceError> {
+        let arguments = match serde_json::to_value(arguments) {
+            Ok(serde_json::Value::Object(arguments)) => Some(arguments),
+            _ => None,
+        };
+        self.peer
+            .get_prompt(rmcp::model::GetPromptRequestParam {
+                name: "weather-report".to_owned(),
+                arguments,
+            })
+            .await
+    }
+
+    /// The history of a city
+    pub async fn read_history(&self, city: &str, date: &str) -> Result<rmcp::model::ReadResourceResult, rmcp::ServiceError> {
+        let uri = format!("weather://{}/history/{}", expand_uri_template_value(city, false), expand_uri_template_value(date, true));
+        self.peer
+            .read_resource(rmcp::model::ReadResourceRequestParam { uri })
+            .await
+    }
+
+    /// The history of a city
+    ///
+    /// The URI expands the template `weather://{city}/search{?query}`.
+    pub async fn read_search(&self, uri: impl Into<String>) -> Result<rmcp::model::ReadResourceResult, rmcp::ServiceError> {
+        self.peer
+            .read_resource(rmcp::model::ReadResourceRequestParam { uri: uri.into() })
+            .await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GetForecastParams {
+    /// The city of the forecast
+    pub city: String,
+    /// The number of days
+    pub days: u8,
+    /// Celsius by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<Unit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Unit {
+    #[serde(rename = "celsius")]
+    Celsius,
+    #[serde(rename = "fahrenheit")]
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GetForecastOutput {
+    #[serde(rename = "daysAhead")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_ahead: Option<u8>,
+    pub location: Location,
+    pub temperatures: Vec<f64>,
+    pub unit: Unit,
+}
+
+/// Where a forecast is
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Location {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WeatherReportPromptArguments {
+    /// The city to talk about
+    pub city: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+}
+
+/// Percent-encode a value expanded in a URI template, keeping the reserved characters if `reserved`
+fn expand_uri_template_value(value: &str, reserved: bool) -> String {
+    const RESERVED: &[u8] = b":/?#[]@!$&'()*+,;=";
+    let mut expanded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || (reserved && RESERVED.contains(&byte))
+        {
+            expanded.push(byte as char);
+        } else {
+            expanded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    expanded
+}
@@ -1,7 +1,9 @@
 //! Common utilities shared between different macro implementations
 
 use quote::quote;
-use syn::{Attribute, Expr, FnArg, ImplItemFn, Signature, Type};
+use syn::{
+    Attribute, Expr, FnArg, Ident, ImplItemFn, LitStr, Meta, Pat, PatIdent, Signature, Type,
+};
 
 /// Parse a None expression
 pub fn none_expr() -> syn::Result<Expr> {
@@ -51,9 +53,8 @@ pub fn extract_doc_line(
     }
 }
 
-/// Find Parameters<T> type in function signature
-/// Returns the full Parameters<T> type if found
-pub fn find_parameters_type_in_sig(sig: &Signature) -> Option<Box<Type>> {
+/// Find the type of an argument in function signature by the last segment of its path
+pub fn find_type_in_sig(sig: &Signature, name: &str) -> Option<Box<Type>> {
     sig.inputs.iter().find_map(|input| {
         if let FnArg::Typed(pat_type) = input {
             if let Type::Path(type_path) = &*pat_type.ty {
@@ -61,7 +62,7 @@ pub fn find_parameters_type_in_sig(sig: &Signature) -> Option<Box<Type>> {
                     .path
                     .segments
                     .last()
-                    .is_some_and(|type_name| type_name.ident == "Parameters")
+                    .is_some_and(|type_name| type_name.ident == name)
                 {
                     return Some(pat_type.ty.clone());
                 }
@@ -71,7 +72,87 @@ pub fn find_parameters_type_in_sig(sig: &Signature) -> Option<Box<Type>> {
     })
 }
 
+/// Find Parameters<T> type in function signature
+/// Returns the full Parameters<T> type if found
+pub fn find_parameters_type_in_sig(sig: &Signature) -> Option<Box<Type>> {
+    find_type_in_sig(sig, "Parameters")
+}
+
 /// Find Parameters<T> type in ImplItemFn
 pub fn find_parameters_type_impl(fn_item: &ImplItemFn) -> Option<Box<Type>> {
     find_parameters_type_in_sig(&fn_item.sig)
 }
+
+/// An argument of a tool function deserialized from its own field of the call arguments
+pub struct FlattenedArg {
+    pub ident: Ident,
+    pub ty: Box<Type>,
+    /// From `#[arg(description = "...")]`
+    pub description: Option<LitStr>,
+    /// From `#[arg(default = <expr>)]`
+    pub default: Option<Expr>,
+}
+
+/// Parse an input of a tool function as a flattened argument
+///
+/// Only the inputs with an `#[arg]` attribute are flattened arguments, the others are the
+/// receiver, the extractors and a reference to the handler taken by a free function, for
+/// which `None` is returned.
+pub fn flattened_arg(input: &FnArg) -> syn::Result<Option<FlattenedArg>> {
+    let FnArg::Typed(pat_type) = input else {
+        return Ok(None);
+    };
+    let arg_attrs: Vec<_> = pat_type
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("arg"))
+        .collect();
+    if arg_attrs.is_empty() {
+        return Ok(None);
+    }
+    let Pat::Ident(PatIdent {
+        ident,
+        by_ref: None,
+        subpat: None,
+        ..
+    }) = &*pat_type.pat
+    else {
+        return Err(syn::Error::new_spanned(
+            &pat_type.pat,
+            "a tool argument must be bound to an identifier",
+        ));
+    };
+    let mut description = None;
+    let mut default = None;
+    // a bare `#[arg]` has nothing to parse
+    for attr in arg_attrs
+        .into_iter()
+        .filter(|attr| !matches!(attr.meta, Meta::Path(_)))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("description") {
+                description = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                default = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `description` or `default`"))
+            }
+        })?;
+    }
+    Ok(Some(FlattenedArg {
+        ident: ident.clone(),
+        ty: pat_type.ty.clone(),
+        description,
+        default,
+    }))
+}
+
+/// Whether a tool function takes flattened arguments, and is called through its
+/// generated `__{name}_tool_call` function
+pub fn has_flattened_args(sig: &Signature) -> bool {
+    sig.inputs
+        .iter()
+        .any(|input| matches!(flattened_arg(input), Ok(Some(_))))
+}
@@ -2,6 +2,7 @@
 use proc_macro::TokenStream;
 
 mod common;
+mod mcp_error;
 mod prompt;
 mod prompt_handler;
 mod prompt_router;
@@ -20,7 +21,7 @@ mod tool_router;
 /// | :-                | :-                         | :-    |
 /// | `name`            | `String`                   | The name of the tool. If not provided, it defaults to the function name. |
 /// | `description`     | `String`                   | A description of the tool. The document of this function will be used. |
-/// | `input_schema`    | `Expr`                     | A JSON Schema object defining the expected parameters for the tool. If not provide, if will use the json schema of its argument with type `Parameters<T>` |
+/// | `input_schema`    | `Expr`                     | A JSON Schema object defining the expected parameters for the tool. If not provide, if will use the json schema of its argument with type `Parameters<T>`, or of its flattened arguments |
 /// | `annotations`     | `ToolAnnotationsAttribute` | Additional tool information. Defaults to `None`. |
 ///
 /// ## Example
@@ -31,6 +32,27 @@ mod tool_router;
 ///     // handling tool request
 /// }
 /// ```
+///
+/// ## Flattened arguments
+///
+/// Instead of a `Parameters<T>`, the tool can take its parameters as plain arguments marked
+/// with `#[arg]`, each deserialized from the field of the same name. An argument can be
+/// documented in the schema with `#[arg(description = "...")]`, and made optional with
+/// `#[arg(default = ...)]`. The other inputs are extractors, like `Peer`, `RequestContext`,
+/// `Extension<T>`, `State<T>`, `ToolName` or your own `FromContextPart` types.
+///
+/// ```rust,ignore
+/// #[tool(description = "Greet someone")]
+/// pub async fn greet(
+///     &self,
+///     #[arg(description = "Who to greet")] name: String,
+///     #[arg(default = "Hello")] greeting: String,
+///     #[arg] punctuation: Option<char>,
+///     peer: Peer<RoleServer>,
+/// ) -> String {
+///     format!("{greeting}, {name}!")
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn tool(attr: TokenStream, input: TokenStream) -> TokenStream {
     tool::tool(attr.into(), input.into())
@@ -51,6 +73,7 @@ pub fn tool(attr: TokenStream, input: TokenStream) -> TokenStream {
 /// | :-        | :-            | :-    |
 /// | `router`  | `Ident`       | The name of the router function to be generated. Defaults to `tool_router`. |
 /// | `vis`     | `Visibility`  | The visibility of the generated router function. Defaults to empty. |
+/// | `client`  | `Ident`       | The name of a typed client struct to generate, calling the tools through a `Peer<RoleClient>`. Defaults to none. |
 ///
 /// ## Example
 ///
@@ -102,6 +125,27 @@ pub fn tool(attr: TokenStream, input: TokenStream) -> TokenStream {
 ///     }
 /// }
 /// ```
+///
+/// ## Typed client
+///
+/// With `client`, a struct with the given name and visibility is generated alongside the
+/// router, with one async method per tool. A method takes the `T` of the `Parameters<T>` of
+/// the tool, which must also implement `Serialize`, or its flattened arguments. It returns
+/// the `T` of a `Json<T>` result, or the `CallToolResult` for other results. Renaming a tool
+/// or changing its parameters then fails at compile time on the client side too.
+///
+/// ```rust,ignore
+/// #[tool_router(client = CalculatorClient, vis = "pub")]
+/// impl Calculator {
+///     #[tool]
+///     async fn sum(&self, Parameters(request): Parameters<SumRequest>) -> Json<SumResult> {
+///         // ...
+///     }
+/// }
+///
+/// let calculator = CalculatorClient::new(client.peer().clone());
+/// let result: SumResult = calculator.sum(SumRequest { a: 1, b: 2 }).await?;
+/// ```
 #[proc_macro_attribute]
 pub fn tool_router(attr: TokenStream, input: TokenStream) -> TokenStream {
     tool_router::tool_router(attr.into(), input.into())
@@ -234,13 +278,14 @@ pub fn prompt_router(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 /// # prompt_handler
 ///
-/// This macro generates handler methods for `get_prompt` and `list_prompts` in the implementation block, using an existing `PromptRouter` instance.
+/// This macro generates handler methods for `get_prompt`, `list_prompts` and `complete` in the implementation block, using an existing `PromptRouter` instance.
 ///
 /// ## Usage
 ///
-/// | field     | type   | usage |
-/// | :-        | :-     | :-    |
-/// | `router`  | `Expr` | The expression to access the `PromptRouter` instance. Defaults to `self.prompt_router`. |
+/// | field               | type   | usage |
+/// | :-                  | :-     | :-    |
+/// | `router`            | `Expr` | The expression to access the `PromptRouter` instance. Defaults to `self.prompt_router`. |
+/// | `completion_router` | `Expr` | The expression to access a `CompletionRouter` completing the arguments the prompts have no completer for, such as the variables of resource templates. Without it, a `complete` method of the block is kept. |
 ///
 /// ## Example
 /// ```rust,ignore
@@ -257,9 +302,61 @@ pub fn prompt_router(attr: TokenStream, input: TokenStream) -> TokenStream {
 ///    // ...implement other handler methods
 /// }
 /// ```
+///
+/// or completing resource templates too:
+/// ```rust,ignore
+/// #[prompt_handler(completion_router = self.completion_router)]
+/// impl ServerHandler for MyPromptHandler {
+///    // ...implement other handler methods
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn prompt_handler(attr: TokenStream, input: TokenStream) -> TokenStream {
     prompt_handler::prompt_handler(attr.into(), input.into())
         .unwrap_or_else(|err| err.to_compile_error())
         .into()
 }
+
+/// # McpError
+///
+/// This derive macro implements `rmcp::handler::server::tool::IntoToolError` for an error type, so
+/// that a tool returning `Result<T, E>` turns the error into a JSON-RPC error, or into a tool
+/// result with `is_error` which the model can see. The message is the `Display` of the error.
+///
+/// ## Usage
+///
+/// The `#[mcp(...)]` attribute is taken on each variant, and on the type for the default of
+/// the variants. Without any, the error is an internal error.
+///
+/// | field         | type   | usage |
+/// | :-            | :-     | :-    |
+/// | `code`        | `Expr` | The JSON-RPC error code, a constant of `ErrorCode` like `INVALID_PARAMS`, or an integer. |
+/// | `tool_error`  | `bool` | Turn the error into a tool result with `is_error` instead. |
+/// | `data`        | `Expr` | A `Serialize` value for the `data` of the error, or the structured content of a tool error. Named fields are bound by their names, the others as `_0`, `_1`... |
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// #[derive(Debug, thiserror::Error, McpError)]
+/// enum DbError {
+///     #[error("no row {id}")]
+///     #[mcp(code = INVALID_PARAMS, data = serde_json::json!({ "id": id }))]
+///     NotFound { id: u64 },
+///     #[error("query timed out after {0}s")]
+///     #[mcp(tool_error)]
+///     Timeout(u64),
+///     #[error("connection lost")]
+///     ConnectionLost,
+/// }
+///
+/// #[tool]
+/// async fn get_row(&self, id: u64) -> Result<Json<Row>, DbError> {
+///     // ...
+/// }
+/// ```
+#[proc_macro_derive(McpError, attributes(mcp))]
+pub fn mcp_error(input: TokenStream) -> TokenStream {
+    mcp_error::mcp_error(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
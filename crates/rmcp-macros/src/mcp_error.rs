@@ -0,0 +1,196 @@
+use darling::{FromMeta, ast::NestedMeta};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Ident, spanned::Spanned};
+
+#[derive(FromMeta, Default, Debug)]
+#[darling(default)]
+pub struct McpErrorAttribute {
+    /// The JSON-RPC error code of a protocol error
+    pub code: Option<Expr>,
+    /// Turn the error into a tool result with `is_error` rather than a protocol error
+    pub tool_error: bool,
+    /// The structured data of the error, any `Serialize` value
+    pub data: Option<Expr>,
+}
+
+impl McpErrorAttribute {
+    /// Parse the `#[mcp(...)]` attribute among `attrs`, if any
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Option<Self>> {
+        let mut mcp_attrs = attrs.iter().filter(|attr| attr.path().is_ident("mcp"));
+        let Some(attr) = mcp_attrs.next() else {
+            return Ok(None);
+        };
+        if let Some(duplicate) = mcp_attrs.next() {
+            return Err(syn::Error::new_spanned(
+                duplicate,
+                "duplicate `#[mcp]` attribute",
+            ));
+        }
+        let list = attr.meta.require_list()?;
+        let attribute = Self::from_list(&NestedMeta::parse_meta_list(list.tokens.clone())?)?;
+        if attribute.tool_error && attribute.code.is_some() {
+            return Err(syn::Error::new_spanned(attr, "a tool error has no `code`"));
+        }
+        Ok(Some(attribute))
+    }
+
+    /// The attribute of a variant, with the defaults of the enum
+    fn or_defaults(self, defaults: &McpErrorAttribute) -> Self {
+        let tool_error = self.tool_error || (self.code.is_none() && defaults.tool_error);
+        Self {
+            code: if tool_error {
+                None
+            } else {
+                self.code.or_else(|| defaults.code.clone())
+            },
+            tool_error,
+            data: self.data.or_else(|| defaults.data.clone()),
+        }
+    }
+}
+
+/// The error code, where a bare constant name is one of `ErrorCode` and an integer is wrapped
+/// in an `ErrorCode`
+fn error_code(code: Option<&Expr>) -> TokenStream {
+    match code {
+        None => quote! { rmcp::model::ErrorCode::INTERNAL_ERROR },
+        Some(Expr::Path(path)) if path.qself.is_none() && path.path.get_ident().is_some() => {
+            quote! { rmcp::model::ErrorCode::#path }
+        }
+        Some(code @ (Expr::Lit(_) | Expr::Unary(_))) => quote! { rmcp::model::ErrorCode(#code) },
+        Some(code) => quote! { #code },
+    }
+}
+
+/// The pattern matching a variant, binding named fields by their names and the others as
+/// `_0`, `_1`...
+fn variant_pattern(path: TokenStream, fields: &Fields) -> TokenStream {
+    match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote! { #path { #(#names),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let names = (0..fields.unnamed.len()).map(|index| format_ident!("_{index}"));
+            quote! { #path ( #(#names),* ) }
+        }
+        Fields::Unit => path,
+    }
+}
+
+fn variant_body(attribute: &McpErrorAttribute) -> TokenStream {
+    let data = match &attribute.data {
+        Some(data) => quote! {
+            Some(rmcp::serde_json::to_value(&#data).map_err(|error| {
+                rmcp::ErrorData::internal_error(
+                    format!("failed to serialize error data: {error}"),
+                    None,
+                )
+            })?)
+        },
+        None => quote! { None },
+    };
+    if attribute.tool_error {
+        quote! {
+            let data: Option<rmcp::serde_json::Value> = #data;
+            Ok(rmcp::model::CallToolResult {
+                content: vec![rmcp::model::Content::text(message)],
+                structured_content: data,
+                is_error: Some(true),
+                meta: None,
+            })
+        }
+    } else {
+        let code = error_code(attribute.code.as_ref());
+        quote! {
+            Err(rmcp::ErrorData::new(#code, message, #data))
+        }
+    }
+}
+
+pub fn mcp_error(input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let defaults = McpErrorAttribute::from_attrs(&input.attrs)?.unwrap_or_default();
+    let arms = match &input.data {
+        Data::Struct(data) => {
+            let pattern = variant_pattern(quote! { Self }, &data.fields);
+            let body = variant_body(&defaults);
+            vec![quote! { #pattern => { #body } }]
+        }
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let attribute = McpErrorAttribute::from_attrs(&variant.attrs)?
+                    .unwrap_or_default()
+                    .or_defaults(&defaults);
+                let variant_ident: &Ident = &variant.ident;
+                let pattern = variant_pattern(quote! { Self::#variant_ident }, &variant.fields);
+                let body = variant_body(&attribute);
+                Ok(quote! { #pattern => { #body } })
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "`McpError` can't be derived for a union",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics rmcp::handler::server::tool::IntoToolError for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn into_tool_error(
+                self,
+            ) -> ::std::result::Result<rmcp::model::CallToolResult, rmcp::ErrorData> {
+                let message = ::std::string::ToString::to_string(&self);
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mcp_error_codes() -> syn::Result<()> {
+        let input = quote! {
+            #[mcp(code = INVALID_REQUEST)]
+            enum DbError {
+                #[mcp(code = INVALID_PARAMS, data = serde_json::json!({ "id": id }))]
+                NotFound { id: u64 },
+                #[mcp(code = -32000)]
+                Busy(u32),
+                #[mcp(tool_error, data = _0)]
+                Timeout(u64),
+                Closed,
+            }
+        };
+        let result = mcp_error(input)?.to_string();
+        assert!(result.contains("rmcp :: handler :: server :: tool :: IntoToolError for DbError"));
+        assert!(result.contains("Self :: NotFound { id }"));
+        assert!(result.contains("rmcp :: model :: ErrorCode :: INVALID_PARAMS"));
+        assert!(result.contains("rmcp :: model :: ErrorCode (- 32000)"));
+        assert!(result.contains("Self :: Timeout (_0)"));
+        assert!(result.contains("is_error : Some (true)"));
+        // the variants without attribute take the one of the enum
+        assert!(result.contains("rmcp :: model :: ErrorCode :: INVALID_REQUEST"));
+
+        let input = quote! {
+            enum DbError {
+                #[mcp(tool_error, code = INVALID_PARAMS)]
+                NotFound,
+            }
+        };
+        assert!(mcp_error(input).is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use darling::{FromMeta, ast::NestedMeta};
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
@@ -18,6 +20,47 @@ pub struct PromptAttribute {
     pub arguments: Option<Expr>,
     /// Optional icons for the prompt
     pub icons: Option<Expr>,
+    /// The completers of the arguments, by argument name
+    pub complete: Option<BTreeMap<String, Expr>>,
+}
+
+impl PromptAttribute {
+    /// Parse the `#[prompt(...)]` attribute of a function
+    pub fn from_attribute(attr: &syn::Attribute) -> syn::Result<Self> {
+        match &attr.meta {
+            syn::Meta::List(list) => Ok(Self::from_list(&NestedMeta::parse_meta_list(
+                list.tokens.clone(),
+            )?)?),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+/// The function returning the completers of the arguments of a prompt, a method of the
+/// server is called with the query while another expression is converted into a completer
+fn completions_fn(fn_ident: Ident, complete: &BTreeMap<String, Expr>) -> syn::Result<ImplItemFn> {
+    let completions = complete.iter().map(|(argument, completer)| {
+        let completer = match completer {
+            Expr::Path(path) => quote! {
+                rmcp::handler::server::completion::Completer::from_fn(
+                    |server: &Self, query| Box::pin(async move {
+                        rmcp::handler::server::completion::IntoCompletionValues::into_completion_values(
+                            #path(server, query).await,
+                        )
+                    }),
+                )
+            },
+            completer => quote! {
+                rmcp::handler::server::completion::Completer::from(#completer)
+            },
+        };
+        quote! { (#argument, #completer) }
+    });
+    syn::parse2::<ImplItemFn>(quote! {
+        pub fn #fn_ident() -> Vec<(&'static str, rmcp::handler::server::completion::Completer<Self>)> {
+            vec![#(#completions),*]
+        }
+    })
 }
 
 pub struct ResolvedPromptAttribute {
@@ -116,6 +159,11 @@ pub fn prompt(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream>
         icons: attribute.icons,
     };
     let prompt_attr_fn = resolved_prompt_attr.into_fn(prompt_attr_fn_ident.clone())?;
+    let completions_fn = attribute
+        .complete
+        .as_ref()
+        .map(|complete| completions_fn(format_ident!("{}_prompt_completions", fn_ident), complete))
+        .transpose()?;
 
     // Modify the input function for async support (same as tool macro)
     if fn_item.sig.asyncness.is_some() {
@@ -153,6 +201,7 @@ pub fn prompt(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream>
 
     Ok(quote! {
         #prompt_attr_fn
+        #completions_fn
         #fn_item
     })
 }
@@ -223,4 +272,25 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_prompt_completions() -> syn::Result<()> {
+        let attr = quote! {
+            complete(table = ["users", "orders"], column = Self::complete_column)
+        };
+        let input = quote! {
+            async fn query(&self, Parameters(args): Parameters<QueryArgs>) -> Vec<PromptMessage> {
+                vec![]
+            }
+        };
+        let result = prompt(attr, input)?.to_string();
+        assert!(result.contains("fn query_prompt_completions"));
+        assert!(result.contains("Completer :: from ([\"users\" , \"orders\"])"));
+        assert!(result.contains("Self :: complete_column (server , query) . await"));
+
+        // no completers, no function
+        let result = prompt(quote! {}, quote! { fn query(&self) {} })?.to_string();
+        assert!(!result.contains("prompt_completions"));
+        Ok(())
+    }
 }
@@ -7,6 +7,7 @@ use syn::{Expr, ImplItem, ItemImpl, parse_quote};
 #[darling(default)]
 pub struct PromptHandlerAttribute {
     pub router: Option<Expr>,
+    pub completion_router: Option<Expr>,
 }
 
 pub fn prompt_handler(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
@@ -55,9 +56,43 @@ pub fn prompt_handler(attr: TokenStream, input: TokenStream) -> syn::Result<Toke
         }
     };
 
+    // Add complete implementation, for the completers of the prompt arguments, then the
+    // ones of the completion router if any
+    let complete_impl: ImplItem = match &attribute.completion_router {
+        Some(completion_router) => parse_quote! {
+            async fn complete(
+                &self,
+                request: rmcp::model::CompleteRequestParam,
+                _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+            ) -> Result<rmcp::model::CompleteResult, rmcp::ErrorData> {
+                let has_prompt_completer = request
+                    .r#ref
+                    .as_prompt_name()
+                    .and_then(|prompt| #router_expr.completer(prompt, &request.argument.name))
+                    .is_some();
+                if has_prompt_completer {
+                    #router_expr.complete(self, request).await
+                } else {
+                    #completion_router.complete(self, request).await
+                }
+            }
+        },
+        None => parse_quote! {
+            async fn complete(
+                &self,
+                request: rmcp::model::CompleteRequestParam,
+                _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+            ) -> Result<rmcp::model::CompleteResult, rmcp::ErrorData> {
+                #router_expr.complete(self, request).await
+            }
+        },
+    };
+
     // Check if methods already exist and replace them if they do
     let mut has_get_prompt = false;
     let mut has_list_prompts = false;
+    // a `complete` of the server is kept, it may complete more than the prompts
+    let mut has_complete = false;
 
     for item in &mut impl_block.items {
         if let ImplItem::Fn(fn_item) = item {
@@ -70,6 +105,12 @@ pub fn prompt_handler(attr: TokenStream, input: TokenStream) -> syn::Result<Toke
                     *item = list_prompts_impl.clone();
                     has_list_prompts = true;
                 }
+                // replaced only when a completion router is given
+                "complete" if attribute.completion_router.is_some() => {
+                    *item = complete_impl.clone();
+                    has_complete = true;
+                }
+                "complete" => has_complete = true,
                 _ => {}
             }
         }
@@ -82,6 +123,9 @@ pub fn prompt_handler(attr: TokenStream, input: TokenStream) -> syn::Result<Toke
     if !has_list_prompts {
         impl_block.items.push(list_prompts_impl);
     }
+    if !has_complete {
+        impl_block.items.push(complete_impl);
+    }
 
     Ok(quote! {
         #impl_block
@@ -108,6 +152,7 @@ mod test {
         assert!(result_str.contains("PromptContext") && result_str.contains("new"));
         assert!(result_str.contains("async fn list_prompts"));
         assert!(result_str.contains("ListPromptsResult"));
+        assert!(result_str.contains("async fn complete"));
 
         Ok(())
     }
@@ -138,4 +183,30 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_prompt_handler_with_completion_router() -> syn::Result<()> {
+        let attr = quote! { completion_router = self.completion_router };
+        let input = quote! {
+            impl ServerHandler for MyPromptHandler {
+                async fn complete(
+                    &self,
+                    request: CompleteRequestParam,
+                    _context: RequestContext<RoleServer>,
+                ) -> Result<CompleteResult, ErrorData> {
+                    Ok(CompleteResult::default())
+                }
+            }
+        };
+
+        let result = prompt_handler(attr, input)?;
+        let result_str = result.to_string();
+
+        // The `complete` of the block is replaced by one falling back to the completion router
+        assert_eq!(result_str.matches("async fn complete").count(), 1);
+        assert!(result_str.contains("self . completion_router . complete (self , request)"));
+        assert!(!result_str.contains("CompleteResult :: default"));
+
+        Ok(())
+    }
 }
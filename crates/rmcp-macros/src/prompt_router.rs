@@ -3,6 +3,8 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{ImplItem, ItemImpl, Visibility, parse_quote};
 
+use crate::prompt::PromptAttribute;
+
 #[derive(FromMeta, Debug, Default)]
 #[darling(default)]
 pub struct PromptRouterAttribute {
@@ -31,7 +33,7 @@ pub fn prompt_router(attr: TokenStream, input: TokenStream) -> syn::Result<Token
 
     for item in &mut impl_block.items {
         if let ImplItem::Fn(fn_item) = item {
-            let has_prompt_attr = fn_item.attrs.iter().any(|attr| {
+            let prompt_attr = fn_item.attrs.iter().find(|attr| {
                 attr.path()
                     .segments
                     .last()
@@ -39,7 +41,10 @@ pub fn prompt_router(attr: TokenStream, input: TokenStream) -> syn::Result<Token
                     .unwrap_or(false)
             });
 
-            if has_prompt_attr {
+            if let Some(prompt_attr) = prompt_attr {
+                // errors are reported by the `#[prompt]` macro itself
+                let has_completions = PromptAttribute::from_attribute(prompt_attr)
+                    .is_ok_and(|attribute| attribute.complete.is_some());
                 let fn_ident = &fn_item.sig.ident;
                 let attr_fn_ident = format_ident!("{}_prompt_attr", fn_ident);
 
@@ -56,9 +61,22 @@ pub fn prompt_router(attr: TokenStream, input: TokenStream) -> syn::Result<Token
                 }
 
                 // Use the exact same pattern as tool_router
-                prompt_route_fn_calls.push(quote! {
-                    .with_route((Self::#attr_fn_ident(), Self::#fn_ident))
-                });
+                if has_completions {
+                    let completions_fn_ident = format_ident!("{}_prompt_completions", fn_ident);
+                    prompt_route_fn_calls.push(quote! {
+                        .with_route(
+                            rmcp::handler::server::router::prompt::PromptRoute::new(
+                                Self::#attr_fn_ident(),
+                                Self::#fn_ident,
+                            )
+                            .with_completions(Self::#completions_fn_ident()),
+                        )
+                    });
+                } else {
+                    prompt_route_fn_calls.push(quote! {
+                        .with_route((Self::#attr_fn_ident(), Self::#fn_ident))
+                    });
+                }
             }
         }
     }
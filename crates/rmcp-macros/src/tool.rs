@@ -1,9 +1,9 @@
 use darling::{FromMeta, ast::NestedMeta};
 use proc_macro2::{Span, TokenStream};
 use quote::{ToTokens, format_ident, quote};
-use syn::{Expr, Ident, ImplItemFn, LitStr, ReturnType, parse_quote};
+use syn::{Expr, FnArg, Ident, ImplItemFn, LitStr, ReturnType, ext::IdentExt, parse_quote};
 
-use crate::common::{extract_doc_line, none_expr};
+use crate::common::{FlattenedArg, extract_doc_line, flattened_arg, none_expr};
 
 /// Check if a type is Json<T> and extract the inner type T
 fn extract_json_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
@@ -21,15 +21,12 @@ fn extract_json_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
     None
 }
 
-/// Extract schema expression from a function's return type
+/// Extract the type `T` from a function's return type
 /// Handles patterns like Json<T> and Result<Json<T>, E>
-fn extract_schema_from_return_type(ret_type: &syn::Type) -> Option<Expr> {
+pub fn extract_json_output_type(ret_type: &syn::Type) -> Option<&syn::Type> {
     // First, try direct Json<T>
     if let Some(inner_type) = extract_json_inner_type(ret_type) {
-        return syn::parse2::<Expr>(quote! {
-            rmcp::handler::server::tool::cached_schema_for_type::<#inner_type>()
-        })
-        .ok();
+        return Some(inner_type);
     }
 
     // Then, try Result<Json<T>, E>
@@ -54,13 +51,187 @@ fn extract_schema_from_return_type(ret_type: &syn::Type) -> Option<Expr> {
         _ => return None,
     };
 
-    let inner_type = extract_json_inner_type(ok_type)?;
+    extract_json_inner_type(ok_type)
+}
 
+/// Extract schema expression from a function's return type
+fn extract_schema_from_return_type(ret_type: &syn::Type) -> Option<Expr> {
+    let inner_type = extract_json_output_type(ret_type)?;
     syn::parse2::<Expr>(quote! {
         rmcp::handler::server::tool::cached_schema_for_type::<#inner_type>()
     })
     .ok()
 }
+
+/// The hidden function holding the struct the flattened arguments of a tool are
+/// deserialized to, named after the tool function
+///
+/// It returns the functions giving the input schema of the tool and parsing the
+/// arguments of a call into a tuple, so that the struct is emitted once for both.
+fn flattened_args_fn(fn_ident: &Ident, args: &[FlattenedArg]) -> syn::Result<ImplItemFn> {
+    let struct_name: String = fn_ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars))
+                .into_iter()
+                .flatten()
+        })
+        .collect();
+    let struct_ident = format_ident!("{}Arguments", struct_name);
+    let mut fields = vec![];
+    let mut default_fns = vec![];
+    for FlattenedArg {
+        ident,
+        ty,
+        description,
+        default,
+    } in args
+    {
+        let description = description
+            .as_ref()
+            .map(|description| quote! { #[schemars(description = #description)] });
+        let default = default.as_ref().map(|default| {
+            let default_fn_ident = format_ident!("__rmcp_default_{}", ident.unraw());
+            let default_fn_name = default_fn_ident.to_string();
+            // let string literals default a `String`
+            let value = match default {
+                Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(_),
+                    ..
+                }) => quote! { ::std::convert::Into::into(#default) },
+                _ => quote! { #default },
+            };
+            default_fns.push(quote! {
+                fn #default_fn_ident() -> #ty {
+                    #value
+                }
+            });
+            quote! { #[serde(default = #default_fn_name)] }
+        });
+        fields.push(quote! {
+            #description
+            #default
+            #ident: #ty
+        });
+    }
+    let args_fn_ident = format_ident!("__{}_tool_arguments", fn_ident.unraw());
+    let idents: Vec<_> = args.iter().map(|arg| &arg.ident).collect();
+    let tys: Vec<_> = args.iter().map(|arg| &arg.ty).collect();
+    let doc_comment = format!("Generated arguments of the tool {fn_ident}");
+    syn::parse2::<ImplItemFn>(quote! {
+        #[doc = #doc_comment]
+        #[doc(hidden)]
+        pub fn #args_fn_ident() -> (
+            fn() -> ::std::sync::Arc<rmcp::model::JsonObject>,
+            fn(
+                rmcp::model::JsonObject,
+            ) -> ::std::result::Result<(#(#tys,)*), rmcp::serde_json::Error>,
+        ) {
+            #[derive(rmcp::serde::Deserialize, rmcp::schemars::JsonSchema)]
+            #[serde(crate = "rmcp::serde")]
+            #[schemars(crate = "rmcp::schemars")]
+            struct #struct_ident {
+                #(#fields,)*
+            }
+            #(#default_fns)*
+            fn schema() -> ::std::sync::Arc<rmcp::model::JsonObject> {
+                rmcp::handler::server::common::cached_schema_for_type::<#struct_ident>()
+            }
+            fn parse(
+                arguments: rmcp::model::JsonObject,
+            ) -> ::std::result::Result<(#(#tys,)*), rmcp::serde_json::Error> {
+                let #struct_ident { #(#idents),* } =
+                    rmcp::serde_json::from_value(rmcp::serde_json::Value::Object(arguments))?;
+                Ok((#(#idents,)*))
+            }
+            (schema, parse)
+        }
+    })
+}
+
+/// The function the router calls for a tool with flattened arguments
+///
+/// It takes the arguments of the call as a `JsonObject` and the extractors of the tool
+/// function, and calls the tool function with the deserialized arguments.
+fn flattened_call_fn(fn_item: &ImplItemFn) -> syn::Result<ImplItemFn> {
+    let fn_ident = &fn_item.sig.ident;
+    let call_fn_ident = format_ident!("__{}_tool_call", fn_ident.unraw());
+    let args_fn_ident = format_ident!("__{}_tool_arguments", fn_ident.unraw());
+    let mut extractors = vec![];
+    let mut call_args = vec![];
+    let mut locals = vec![];
+    for (index, input) in fn_item.sig.inputs.iter().enumerate() {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        if let Some(arg) = flattened_arg(input)? {
+            let local = format_ident!("__rmcp_arg_{}", arg.ident.unraw());
+            locals.push(local.clone());
+            call_args.push(local);
+        } else {
+            let local = format_ident!("__rmcp_extractor_{}", index);
+            let ty = &pat_type.ty;
+            extractors.push(quote! { #local: #ty });
+            call_args.push(local);
+        }
+    }
+    let (receiver, callee, lt) = match fn_item.sig.receiver() {
+        Some(_) => (quote! { &self, }, quote! { self.#fn_ident }, quote! { '_ }),
+        None => (quote! {}, quote! { Self::#fn_ident }, quote! { 'static }),
+    };
+    let result = if fn_item.sig.asyncness.is_some() {
+        quote! {
+            Box::pin(async move {
+                rmcp::handler::server::tool::IntoCallToolResult::into_call_tool_result(
+                    __rmcp_result.await,
+                )
+            })
+        }
+    } else {
+        quote! {
+            Box::pin(::std::future::ready(
+                rmcp::handler::server::tool::IntoCallToolResult::into_call_tool_result(
+                    __rmcp_result,
+                ),
+            ))
+        }
+    };
+    let doc_comment = format!("Generated tool call function for {fn_ident}");
+    syn::parse2::<ImplItemFn>(quote! {
+        #[doc = #doc_comment]
+        #[doc(hidden)]
+        pub fn #call_fn_ident(
+            #receiver
+            __rmcp_arguments: rmcp::model::JsonObject,
+            #(#extractors,)*
+        ) -> ::std::pin::Pin<
+            Box<
+                dyn ::std::future::Future<
+                        Output = ::std::result::Result<rmcp::model::CallToolResult, rmcp::ErrorData>,
+                    > + Send
+                    + #lt,
+            >,
+        > {
+            let (#(#locals,)*) = match (Self::#args_fn_ident().1)(__rmcp_arguments) {
+                Ok(arguments) => arguments,
+                Err(e) => {
+                    return Box::pin(::std::future::ready(Err(rmcp::ErrorData::invalid_params(
+                        format!("failed to deserialize parameters: {e}"),
+                        None,
+                    ))));
+                }
+            };
+            let __rmcp_result = #callee(#(#call_args),*);
+            #result
+        }
+    })
+}
+
 #[derive(FromMeta, Default, Debug)]
 #[darling(default)]
 pub struct ToolAttribute {
@@ -185,11 +356,49 @@ pub fn tool(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
         ToolAttribute::from_list(&attr_args)?
     };
     let mut fn_item = syn::parse2::<ImplItemFn>(input.clone())?;
+    let flattened_args = fn_item
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| flattened_arg(input).transpose())
+        .collect::<syn::Result<Vec<_>>>()?;
+    let flattened_fns = if flattened_args.is_empty() {
+        None
+    } else {
+        if let Some(params_ty) = crate::common::find_parameters_type_impl(&fn_item) {
+            return Err(syn::Error::new_spanned(
+                params_ty,
+                "a tool with flattened arguments can't also take `Parameters`",
+            ));
+        }
+        if let Some(json_object_ty) = crate::common::find_type_in_sig(&fn_item.sig, "JsonObject") {
+            return Err(syn::Error::new_spanned(
+                json_object_ty,
+                "a tool with flattened arguments can't also take `JsonObject`",
+            ));
+        }
+        let args_fn = flattened_args_fn(&fn_item.sig.ident, &flattened_args)?;
+        let call_fn = flattened_call_fn(&fn_item)?;
+        Some(quote! {
+            #args_fn
+            #call_fn
+        })
+    };
+    for input in &mut fn_item.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type.attrs.retain(|attr| !attr.path().is_ident("arg"));
+        }
+    }
     let fn_ident = &fn_item.sig.ident;
 
     let tool_attr_fn_ident = format_ident!("{}_tool_attr", fn_ident);
     let input_schema_expr = if let Some(input_schema) = attribute.input_schema {
         input_schema
+    } else if !flattened_args.is_empty() {
+        let args_fn_ident = format_ident!("__{}_tool_arguments", fn_ident.unraw());
+        syn::parse2::<Expr>(quote! {
+            (Self::#args_fn_ident().0)()
+        })?
     } else {
         // try to find some parameters wrapper in the function
         let params_ty = crate::common::find_parameters_type_impl(&fn_item);
@@ -302,6 +511,7 @@ pub fn tool(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
     Ok(quote! {
         #tool_attr_fn
         #fn_item
+        #flattened_fns
     })
 }
 
@@ -324,6 +534,42 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_flattened_args() -> syn::Result<()> {
+        let input = quote! {
+            async fn greet(
+                &self,
+                #[arg(description = "Who to greet", default = "world")] name: String,
+                peer: Peer<RoleServer>,
+            ) -> String {
+                drop(peer);
+                name
+            }
+        };
+        let result = tool(quote! {}, input)?.to_string();
+        assert_eq!(result.matches("struct GreetArguments").count(), 1);
+        assert!(result.contains("fn __greet_tool_arguments"));
+        assert!(result.contains("fn __greet_tool_call"));
+        assert!(result.contains("__rmcp_default_name"));
+        assert!(!result.contains("# [arg"));
+
+        let input = quote! {
+            fn greet(#[arg] name: String, params: Parameters<Request>) {}
+        };
+        assert!(tool(quote! {}, input).is_err());
+
+        // without `#[arg]`, every input is an extractor
+        let input = quote! {
+            async fn greet(&self, db: TenantDb, context: RequestContext<RoleServer>) -> String {
+                db.name()
+            }
+        };
+        let result = tool(quote! {}, input)?.to_string();
+        assert!(!result.contains("GreetArguments"));
+        assert!(!result.contains("__greet_tool_call"));
+        Ok(())
+    }
+
     #[test]
     fn test_doc_comment_description() -> syn::Result<()> {
         let attr = quote! {}; // No explicit description
@@ -9,13 +9,20 @@
 use darling::{FromMeta, ast::NestedMeta};
 use proc_macro2::TokenStream;
 use quote::{ToTokens, format_ident, quote};
-use syn::{Ident, ImplItem, ItemImpl, Visibility};
+use syn::{Attribute, Ident, ImplItem, ImplItemFn, ItemImpl, Visibility, ext::IdentExt};
+
+use crate::{
+    common::{find_parameters_type_in_sig, find_type_in_sig, flattened_arg},
+    tool::{ToolAttribute, extract_json_output_type},
+};
 
 #[derive(FromMeta)]
 #[darling(default)]
 pub struct ToolRouterAttribute {
     pub router: Ident,
     pub vis: Option<Visibility>,
+    /// The name of the typed client to generate
+    pub client: Option<Ident>,
 }
 
 impl Default for ToolRouterAttribute {
@@ -23,14 +30,165 @@ impl Default for ToolRouterAttribute {
         Self {
             router: format_ident!("tool_router"),
             vis: None,
+            client: None,
         }
     }
 }
 
+fn is_tool_attr(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|seg| seg.ident == "tool")
+}
+
+/// The method of the typed client calling a tool
+fn client_method(
+    fn_item: &ImplItemFn,
+    tool_attr: &Attribute,
+    vis: &Option<Visibility>,
+) -> syn::Result<TokenStream> {
+    let attribute = match &tool_attr.meta {
+        syn::Meta::List(list) => {
+            ToolAttribute::from_list(&NestedMeta::parse_meta_list(list.tokens.clone())?)?
+        }
+        _ => ToolAttribute::default(),
+    };
+    let fn_ident = &fn_item.sig.ident;
+    let name = attribute.name.unwrap_or_else(|| fn_ident.to_string());
+    let mut docs: Vec<_> = fn_item
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .collect();
+    let default_doc: Attribute = {
+        let doc = format!("Call the `{name}` tool");
+        syn::parse_quote!(#[doc = #doc])
+    };
+    if docs.is_empty() {
+        docs.push(&default_doc);
+    }
+
+    let (params, arguments) = if let Some(params_ty) = find_parameters_type_in_sig(&fn_item.sig) {
+        let syn::Type::Path(type_path) = &*params_ty else {
+            unreachable!("found by its path")
+        };
+        let inner_ty = match &type_path.path.segments.last().map(|seg| &seg.arguments) {
+            Some(syn::PathArguments::AngleBracketed(args)) => args.args.first(),
+            _ => None,
+        }
+        .ok_or_else(|| syn::Error::new_spanned(&params_ty, "expected `Parameters<T>`"))?;
+        (quote! { params: #inner_ty }, quote! { params })
+    } else if let Some(json_object_ty) = find_type_in_sig(&fn_item.sig, "JsonObject") {
+        (quote! { arguments: #json_object_ty }, quote! { arguments })
+    } else {
+        let mut params = vec![];
+        let mut fields = vec![];
+        for input in &fn_item.sig.inputs {
+            if let Some(arg) = flattened_arg(input)? {
+                let ident = arg.ident;
+                let ty = arg.ty;
+                let field = ident.unraw().to_string();
+                params.push(quote! { #ident: #ty });
+                fields.push(quote! {
+                    arguments.insert(
+                        #field.to_owned(),
+                        rmcp::serde_json::to_value(#ident)
+                            .map_err(rmcp::service::TypedToolCallError::Arguments)?,
+                    );
+                });
+            }
+        }
+        if params.is_empty() {
+            (quote! {}, quote! { () })
+        } else {
+            (
+                quote! { #(#params),* },
+                quote! {{
+                    let mut arguments = rmcp::model::JsonObject::new();
+                    #(#fields)*
+                    arguments
+                }},
+            )
+        }
+    };
+
+    let output_ty = match &fn_item.sig.output {
+        syn::ReturnType::Type(_, ty) => extract_json_output_type(ty),
+        syn::ReturnType::Default => None,
+    };
+    let (output_ty, call) = match output_ty {
+        Some(output_ty) => (quote! { #output_ty }, quote! { call_tool_typed }),
+        None => (
+            quote! { rmcp::model::CallToolResult },
+            quote! { call_tool_with_arguments },
+        ),
+    };
+    Ok(quote! {
+        #(#docs)*
+        #vis async fn #fn_ident(
+            &self,
+            #params
+        ) -> ::std::result::Result<#output_ty, rmcp::service::TypedToolCallError> {
+            self.peer.#call(#name, #arguments).await
+        }
+    })
+}
+
+/// The typed client calling the tools of the impl block through a `Peer<RoleClient>`
+fn client_struct(
+    client: &Ident,
+    item_impl: &ItemImpl,
+    vis: &Option<Visibility>,
+) -> syn::Result<TokenStream> {
+    let mut methods = vec![];
+    for item in &item_impl.items {
+        let ImplItem::Fn(fn_item) = item else {
+            continue;
+        };
+        if let Some(tool_attr) = fn_item.attrs.iter().find(|attr| is_tool_attr(attr)) {
+            methods.push(client_method(fn_item, tool_attr, vis)?);
+        }
+    }
+    let self_ty = &item_impl.self_ty;
+    let doc = format!(
+        "A typed client of the tools of `{}`",
+        self_ty.to_token_stream().to_string().replace(' ', "")
+    );
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone)]
+        #vis struct #client {
+            peer: rmcp::Peer<rmcp::RoleClient>,
+        }
+
+        impl #client {
+            /// Create a client calling the tools through the peer
+            #vis fn new(peer: rmcp::Peer<rmcp::RoleClient>) -> Self {
+                Self { peer }
+            }
+
+            /// The peer the tools are called through
+            #vis fn peer(&self) -> &rmcp::Peer<rmcp::RoleClient> {
+                &self.peer
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
 pub fn tool_router(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
     let attr_args = NestedMeta::parse_meta_list(attr)?;
-    let ToolRouterAttribute { router, vis } = ToolRouterAttribute::from_list(&attr_args)?;
+    let ToolRouterAttribute {
+        router,
+        vis,
+        client,
+    } = ToolRouterAttribute::from_list(&attr_args)?;
     let mut item_impl = syn::parse2::<ItemImpl>(input.clone())?;
+    let client_struct = client
+        .map(|client| client_struct(&client, &item_impl, &vis))
+        .transpose()?;
     // find all function marked with `#[rmcp::tool]`
     let tool_attr_fns: Vec<_> = item_impl
         .items
@@ -40,21 +198,22 @@ pub fn tool_router(attr: TokenStream, input: TokenStream) -> syn::Result<TokenSt
                 fn_item
                     .attrs
                     .iter()
-                    .any(|attr| {
-                        attr.path()
-                            .segments
-                            .last()
-                            .is_some_and(|seg| seg.ident == "tool")
-                    })
-                    .then_some(&fn_item.sig.ident)
+                    .any(is_tool_attr)
+                    .then_some(&fn_item.sig)
             } else {
                 None
             }
         })
         .collect();
     let mut routers = vec![];
-    for handler in tool_attr_fns {
-        let tool_attr_fn_ident = format_ident!("{handler}_tool_attr");
+    for sig in tool_attr_fns {
+        let tool_attr_fn_ident = format_ident!("{}_tool_attr", sig.ident);
+        // a tool with flattened arguments is called through its generated call function
+        let handler = if crate::common::has_flattened_args(sig) {
+            format_ident!("__{}_tool_call", sig.ident.unraw())
+        } else {
+            sig.ident.clone()
+        };
         routers.push(quote! {
             .with_route((Self::#tool_attr_fn_ident(), Self::#handler))
         })
@@ -66,7 +225,10 @@ pub fn tool_router(attr: TokenStream, input: TokenStream) -> syn::Result<TokenSt
         }
     })?;
     item_impl.items.push(router_fn);
-    Ok(item_impl.into_token_stream())
+    Ok(quote! {
+        #item_impl
+        #client_struct
+    })
 }
 
 #[cfg(test)]
@@ -78,7 +240,7 @@ mod test {
             router = test_router,
         };
         let attr_args = NestedMeta::parse_meta_list(attr)?;
-        let ToolRouterAttribute { router, vis } = ToolRouterAttribute::from_list(&attr_args)?;
+        let ToolRouterAttribute { router, vis, .. } = ToolRouterAttribute::from_list(&attr_args)?;
         println!("router: {}", router);
         if let Some(vis) = vis {
             println!("visibility: {}", vis.to_token_stream());
@@ -87,4 +249,51 @@ mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_client() -> syn::Result<()> {
+        let attr = quote! {
+            client = MyClient,
+        };
+        let input = quote! {
+            impl MyHandler {
+                #[tool(name = "sum-numbers")]
+                async fn sum(&self, Parameters(request): Parameters<SumRequest>) -> Json<SumResult> {
+                    Json(request.sum())
+                }
+
+                #[tool]
+                fn greet(&self, #[arg] name: String) -> String {
+                    name
+                }
+            }
+        };
+        let result = tool_router(attr, input)?.to_string();
+        assert!(result.contains("struct MyClient"));
+        assert!(result.contains("call_tool_typed (\"sum-numbers\" , params)"));
+        assert!(result.contains("async fn greet (& self , name : String)"));
+        assert!(result.contains("__greet_tool_call"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_arg_is_left_to_tool() -> syn::Result<()> {
+        let tool_fn = quote! {
+            fn greet(&self, #[arg(unknown = 1)] name: String) -> String {
+                name
+            }
+        };
+        let input = quote! {
+            impl MyHandler {
+                #[tool]
+                #tool_fn
+            }
+        };
+        // `#[tool]` reports the error, the router doesn't refer to a call function it
+        // didn't emit
+        let result = tool_router(quote! {}, input)?.to_string();
+        assert!(!result.contains("__greet_tool_call"));
+        assert!(crate::tool::tool(quote! {}, tool_fn).is_err());
+        Ok(())
+    }
 }
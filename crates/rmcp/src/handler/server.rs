@@ -4,11 +4,19 @@ use crate::{
     service::{NotificationContext, RequestContext, RoleServer, Service, ServiceRole},
 };
 
+pub mod cancellation;
 pub mod common;
+pub mod completion;
+#[cfg(feature = "tracing-layer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing-layer")))]
+pub mod logging;
+pub mod progress;
 pub mod prompt;
-mod resource;
+pub mod resource;
 pub mod router;
+pub mod task;
 pub mod tool;
+pub mod tool_output;
 pub mod wrapper;
 impl<H: ServerHandler> Service<RoleServer> for H {
     async fn handle_request(
@@ -68,6 +76,22 @@ impl<H: ServerHandler> Service<RoleServer> for H {
                 .list_tools(request.params, context)
                 .await
                 .map(ServerResult::ListToolsResult),
+            ClientRequest::GetTaskRequest(request) => self
+                .get_task(request.params, context)
+                .await
+                .map(ServerResult::GetTaskResult),
+            ClientRequest::GetTaskResultRequest(request) => self
+                .get_task_result(request.params, context)
+                .await
+                .map(ServerResult::CallToolResult),
+            ClientRequest::CancelTaskRequest(request) => self
+                .cancel_task(request.params, context)
+                .await
+                .map(ServerResult::GetTaskResult),
+            ClientRequest::ListTasksRequest(request) => self
+                .list_tasks(request.params, context)
+                .await
+                .map(ServerResult::ListTasksResult),
         }
     }
 
@@ -196,6 +220,36 @@ pub trait ServerHandler: Sized + Send + Sync + 'static {
     ) -> impl Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
         std::future::ready(Ok(ListToolsResult::default()))
     }
+    fn get_task(
+        &self,
+        request: TaskRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<GetTaskResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::method_not_found::<GetTaskRequestMethod>()))
+    }
+    fn get_task_result(
+        &self,
+        request: TaskRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<CallToolResult, McpError>> + Send + '_ {
+        std::future::ready(Err(
+            McpError::method_not_found::<GetTaskResultRequestMethod>(),
+        ))
+    }
+    fn cancel_task(
+        &self,
+        request: TaskRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<GetTaskResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::method_not_found::<CancelTaskRequestMethod>()))
+    }
+    fn list_tasks(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<ListTasksResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::method_not_found::<ListTasksRequestMethod>()))
+    }
 
     fn on_cancelled(
         &self,
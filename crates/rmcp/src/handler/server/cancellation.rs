@@ -0,0 +1,156 @@
+//! Cancellation of the request being handled
+//!
+//! When the client sends `notifications/cancelled` for a request, the
+//! [`CancellationToken`] of its [`RequestContext`] is cancelled and, as the
+//! specification requires, whatever the handler returns afterwards is not sent
+//! back. Handlers can react to it in two ways:
+//!
+//! - cooperatively, by extracting the [`CancellationToken`] (or a [`Cancellation`])
+//!   and checking it between units of work,
+//! - by letting [`Cancellation::run`] drop the work future as soon as the request
+//!   is cancelled, optionally running a cleanup hook.
+//!
+//! ```rust
+//! # use rmcp::{handler::server::{cancellation::{Cancellation, CancelOutcome}, tool::ToolRouter}, tool, tool_router};
+//! # #[derive(Clone)]
+//! # struct Indexer { tool_router: ToolRouter<Self> }
+//! # async fn build_index() -> String { String::new() }
+//! # async fn remove_partial_index() {}
+//! # #[tool_router]
+//! # impl Indexer {
+//! #[tool(description = "Rebuild the search index")]
+//! async fn reindex(&self, cancellation: Cancellation) -> CancelOutcome<String> {
+//!     cancellation
+//!         .run_with_cleanup(build_index(), || remove_partial_index())
+//!         .await
+//! }
+//! # }
+//! ```
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+use super::{
+    common::{AsRequestContext, FromContextPart},
+    tool::IntoCallToolResult,
+};
+use crate::{
+    model::{CallToolResult, RequestId},
+    service::RequestContext,
+};
+
+/// The cancellation state of the request being handled.
+///
+/// This is an extractor, usable as a `#[tool]` or `#[prompt]` argument.
+#[derive(Debug, Clone)]
+pub struct Cancellation {
+    ct: CancellationToken,
+    request_id: RequestId,
+}
+
+/// The outcome of work run with [`Cancellation::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancelOutcome<T> {
+    /// The work completed before the request was cancelled.
+    Completed(T),
+    /// The request was cancelled and the work was dropped before completion.
+    Cancelled,
+}
+
+impl Cancellation {
+    pub fn new(ct: CancellationToken, request_id: RequestId) -> Self {
+        Self { ct, request_id }
+    }
+
+    pub fn from_request_context<R: crate::service::ServiceRole>(
+        context: &RequestContext<R>,
+    ) -> Self {
+        Self::new(context.ct.clone(), context.id.clone())
+    }
+
+    /// The id of the request this cancellation belongs to.
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+
+    /// The underlying token, cancelled when the request is cancelled.
+    pub fn token(&self) -> &CancellationToken {
+        &self.ct
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.ct.is_cancelled()
+    }
+
+    /// Wait until the request is cancelled.
+    pub async fn cancelled(&self) {
+        self.ct.cancelled().await
+    }
+
+    /// Drive `work` until it completes or the request is cancelled.
+    ///
+    /// On cancellation `work` is dropped right away, at whatever await point it
+    /// was suspended.
+    pub async fn run<F>(&self, work: F) -> CancelOutcome<F::Output>
+    where
+        F: Future,
+    {
+        self.run_with_cleanup(work, || std::future::ready(())).await
+    }
+
+    /// Like [`Cancellation::run`], but run `cleanup` after `work` has been dropped
+    /// because of a cancellation.
+    pub async fn run_with_cleanup<F, C, CF>(&self, work: F, cleanup: C) -> CancelOutcome<F::Output>
+    where
+        F: Future,
+        C: FnOnce() -> CF,
+        CF: Future<Output = ()>,
+    {
+        let outcome = tokio::select! {
+            biased;
+            _ = self.ct.cancelled() => CancelOutcome::Cancelled,
+            output = work => CancelOutcome::Completed(output),
+        };
+        if let CancelOutcome::Cancelled = outcome {
+            tracing::debug!(id = %self.request_id, "request cancelled, handler aborted");
+            cleanup().await;
+        }
+        outcome
+    }
+}
+
+impl<T> CancelOutcome<T> {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, CancelOutcome::Cancelled)
+    }
+
+    /// The output of the completed work, if any.
+    pub fn completed(self) -> Option<T> {
+        match self {
+            CancelOutcome::Completed(output) => Some(output),
+            CancelOutcome::Cancelled => None,
+        }
+    }
+}
+
+/// A cancelled outcome turns into an error, which is never sent to the client
+/// since responses of cancelled requests are dropped.
+impl<T: IntoCallToolResult> IntoCallToolResult for CancelOutcome<T> {
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::ErrorData> {
+        match self {
+            CancelOutcome::Completed(output) => output.into_call_tool_result(),
+            CancelOutcome::Cancelled => {
+                Err(crate::ErrorData::internal_error("request cancelled", None))
+            }
+        }
+    }
+}
+
+impl<C> FromContextPart<C> for Cancellation
+where
+    C: AsRequestContext,
+{
+    fn from_context_part(context: &mut C) -> Result<Self, crate::ErrorData> {
+        Ok(Self::from_request_context(context.as_request_context()))
+    }
+}
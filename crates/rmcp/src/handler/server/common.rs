@@ -56,6 +56,48 @@ pub trait FromContextPart<C>: Sized {
     fn from_context_part(context: &mut C) -> Result<Self, crate::ErrorData>;
 }
 
+/// Trait for extracting parts from a context asynchronously
+///
+/// Every [`FromContextPart`] is also an `AsyncFromContextPart`. Implement this trait
+/// for the extractors which have to wait on something, like a connection from a pool.
+/// The `M` parameter only tells the two kinds of extractors apart, leave it to its default.
+///
+/// ```rust,ignore
+/// struct TenantDb(Connection);
+///
+/// impl<C: AsRequestContext + Send> AsyncFromContextPart<C> for TenantDb {
+///     async fn from_context_part_async(context: &mut C) -> Result<Self, ErrorData> {
+///         let State(pools) = State::<Pools>::from_context_part(context)?;
+///         let tenant = tenant_of(&context.as_request_context().meta)?;
+///         Ok(TenantDb(pools.connect(tenant).await?))
+///     }
+/// }
+/// ```
+pub trait AsyncFromContextPart<C, M = private::ViaAsync>: Sized {
+    fn from_context_part_async(
+        context: &mut C,
+    ) -> impl Future<Output = Result<Self, crate::ErrorData>> + Send;
+}
+
+impl<C, T> AsyncFromContextPart<C, private::ViaSync> for T
+where
+    T: FromContextPart<C> + Send,
+{
+    fn from_context_part_async(
+        context: &mut C,
+    ) -> impl Future<Output = Result<Self, crate::ErrorData>> + Send {
+        std::future::ready(T::from_context_part(context))
+    }
+}
+
+mod private {
+    #[derive(Debug, Clone, Copy)]
+    pub enum ViaSync {}
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum ViaAsync {}
+}
+
 /// Common extractors that can be used by both tool and prompt handlers
 impl<C> FromContextPart<C> for RequestContext<RoleServer>
 where
@@ -107,6 +149,94 @@ where
     }
 }
 
+/// The shared states of a router, extracted by [`State`]
+///
+/// A router keeps its states in a single group of layers, which a request going through
+/// the router shares instead of cloning. The last layer holding a type wins.
+#[derive(Debug, Clone, Default)]
+pub struct StateMap(Vec<Arc<[Arc<crate::model::Extensions>]>>);
+
+impl StateMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn layers(&self) -> impl DoubleEndedIterator<Item = &Arc<crate::model::Extensions>> {
+        self.0.iter().flat_map(|group| group.iter())
+    }
+
+    /// Keep `layers` as the single group of the map
+    fn set_layers(&mut self, layers: Vec<Arc<crate::model::Extensions>>) {
+        self.0.clear();
+        if !layers.is_empty() {
+            self.0.push(layers.into());
+        }
+    }
+
+    /// Insert a state, replacing the state of the same type
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, state: T) {
+        let mut layers: Vec<_> = self.layers().cloned().collect();
+        if layers.is_empty() {
+            layers.push(Arc::default());
+        }
+        let layer = layers.last_mut().expect("a layer was pushed");
+        Arc::make_mut(layer).insert(state);
+        self.set_layers(layers);
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.layers().rev().find_map(|layer| layer.get::<T>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers().all(|layer| layer.is_empty())
+    }
+
+    /// Insert the states of `other`, replacing the states of the same types
+    pub fn extend(&mut self, other: StateMap) {
+        let layers = self
+            .layers()
+            .chain(other.layers())
+            .filter(|layer| !layer.is_empty())
+            .cloned()
+            .collect();
+        self.set_layers(layers);
+    }
+
+    /// Add the states of a router to the states of a request, sharing its group
+    pub(crate) fn push_shared(&mut self, router_states: &StateMap) {
+        self.0.extend(router_states.0.iter().cloned());
+    }
+}
+
+/// Extractor of a state registered with `with_state` on the router
+///
+/// Unlike `&self`, a state can be shared by the handlers of several services.
+/// A missing state is an internal error.
+pub struct State<T>(pub T);
+
+impl<C, T> FromContextPart<C> for State<T>
+where
+    C: AsRequestContext,
+    T: Send + Sync + 'static + Clone,
+{
+    fn from_context_part(context: &mut C) -> Result<Self, crate::ErrorData> {
+        let state = context
+            .as_request_context()
+            .extensions
+            .get::<StateMap>()
+            .and_then(StateMap::get::<T>)
+            .cloned()
+            .ok_or_else(|| {
+                crate::ErrorData::internal_error(
+                    format!("missing state {}", std::any::type_name::<T>()),
+                    None,
+                )
+            })?;
+        Ok(State(state))
+    }
+}
+
 impl<C> FromContextPart<C> for crate::Peer<RoleServer>
 where
     C: AsRequestContext,
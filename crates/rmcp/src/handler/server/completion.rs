@@ -0,0 +1,276 @@
+//! Completion of prompt arguments and resource template variables
+//!
+//! A [`Completer`] suggests the values of one argument. It either holds a fixed list of
+//! values or calls a function of the server, which receives the value typed so far and
+//! the arguments already filled in a [`CompletionQuery`]. Either way, the suggestions are
+//! matched against the typed value and truncated to [`CompletionInfo::MAX_VALUES`].
+//!
+//! Completers are attached to the arguments of prompts with
+//! [`PromptRoute::with_completion`](super::router::prompt::PromptRoute::with_completion)
+//! or the `complete` argument of `#[prompt]`, and to the variables of resource templates
+//! with a [`CompletionRouter`](super::router::completion::CompletionRouter). The latter is
+//! served by [`Router::with_completions`](super::router::Router::with_completions) or the
+//! `completion_router` argument of `#[prompt_handler]`.
+//!
+//! ```rust
+//! # use rmcp::{ErrorData as McpError, handler::server::{completion::CompletionQuery, router::prompt::PromptRouter, wrapper::Parameters}, model::*, prompt, prompt_router, schemars};
+//! # #[derive(serde::Deserialize, schemars::JsonSchema)]
+//! # struct QueryArgs { table: String, column: String }
+//! # #[derive(Clone)]
+//! # struct Server { prompt_router: PromptRouter<Self> }
+//! #[prompt_router]
+//! impl Server {
+//!     #[prompt(complete(table = ["users", "orders"], column = Self::complete_column))]
+//!     async fn query(&self, Parameters(args): Parameters<QueryArgs>) -> Vec<PromptMessage> {
+//!         // ...
+//! #       vec![]
+//!     }
+//!
+//!     async fn complete_column(&self, query: CompletionQuery) -> Vec<&'static str> {
+//!         match query.get_argument("table") {
+//!             Some("users") => vec!["id", "name", "email"],
+//!             Some("orders") => vec!["id", "user_id", "total"],
+//!             _ => vec![],
+//!         }
+//!     }
+//! }
+//! ```
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+
+use crate::{
+    error::ErrorData as McpError,
+    model::{ArgumentInfo, CompletionContext, CompletionInfo},
+};
+
+/// What a completer is asked to complete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionQuery {
+    /// The argument being completed, with the value typed so far
+    pub argument: ArgumentInfo,
+    /// The arguments already filled
+    pub context: CompletionContext,
+}
+
+impl CompletionQuery {
+    pub fn new(argument: ArgumentInfo, context: Option<CompletionContext>) -> Self {
+        Self {
+            argument,
+            context: context.unwrap_or_default(),
+        }
+    }
+
+    /// The value typed so far.
+    pub fn value(&self) -> &str {
+        &self.argument.value
+    }
+
+    /// The value of an argument already filled.
+    pub fn get_argument(&self, name: &str) -> Option<&str> {
+        self.context.get_argument(name).map(String::as_str)
+    }
+}
+
+/// How the suggestions are matched against the value typed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompletionMatcher {
+    /// Keep the suggestions starting with the value, ignoring case.
+    Prefix,
+    /// Keep the suggestions containing the characters of the value in order, ignoring
+    /// case, the exact and prefix matches first.
+    #[default]
+    Fuzzy,
+    /// Keep every suggestion, the completer matches them itself.
+    None,
+}
+
+impl CompletionMatcher {
+    /// Filter and order the suggestions matching `value`.
+    pub fn apply(self, value: &str, candidates: Vec<String>) -> Vec<String> {
+        let value = value.to_lowercase();
+        if value.is_empty() || self == Self::None {
+            return candidates;
+        }
+        if self == Self::Prefix {
+            return candidates
+                .into_iter()
+                .filter(|candidate| candidate.to_lowercase().starts_with(&value))
+                .collect();
+        }
+        let mut scored: Vec<(u8, String)> = candidates
+            .into_iter()
+            .filter_map(|candidate| Some((fuzzy_score(&value, &candidate)?, candidate)))
+            .collect();
+        // stable, the suggestions of a same score keep their order
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        scored.into_iter().map(|(_, candidate)| candidate).collect()
+    }
+}
+
+/// The score of `candidate` for a lowercase `value`, `None` if it does not match
+fn fuzzy_score(value: &str, candidate: &str) -> Option<u8> {
+    let candidate = candidate.to_lowercase();
+    if candidate == value {
+        return Some(4);
+    }
+    if candidate.starts_with(value) {
+        return Some(3);
+    }
+    if candidate.contains(value) {
+        return Some(2);
+    }
+    let mut chars = candidate.chars();
+    value
+        .chars()
+        .all(|wanted| chars.any(|char| char == wanted))
+        .then_some(1)
+}
+
+/// What a completer function returns, the suggestions or an error.
+pub trait IntoCompletionValues {
+    fn into_completion_values(self) -> Result<Vec<String>, McpError>;
+}
+
+impl<T: Into<String>> IntoCompletionValues for Vec<T> {
+    fn into_completion_values(self) -> Result<Vec<String>, McpError> {
+        Ok(self.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: IntoCompletionValues> IntoCompletionValues for Result<T, McpError> {
+    fn into_completion_values(self) -> Result<Vec<String>, McpError> {
+        self?.into_completion_values()
+    }
+}
+
+/// Type alias for the functions of completers
+pub type DynCompleterFn<S> = dyn for<'a> Fn(&'a S, CompletionQuery) -> BoxFuture<'a, Result<Vec<String>, McpError>>
+    + Send
+    + Sync;
+
+enum Source<S> {
+    Values(Arc<[String]>),
+    Fn(Arc<DynCompleterFn<S>>),
+}
+
+/// Suggest the values of an argument, cheap to clone.
+pub struct Completer<S> {
+    source: Source<S>,
+    matcher: CompletionMatcher,
+}
+
+impl<S> Clone for Completer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            source: match &self.source {
+                Source::Values(values) => Source::Values(values.clone()),
+                Source::Fn(function) => Source::Fn(function.clone()),
+            },
+            matcher: self.matcher,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Completer<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("Completer");
+        if let Source::Values(values) = &self.source {
+            debug.field("values", values);
+        }
+        debug
+            .field("matcher", &self.matcher)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Completer<S> {
+    /// Suggest values among a fixed list.
+    pub fn values(values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            source: Source::Values(values.into_iter().map(Into::into).collect()),
+            matcher: CompletionMatcher::default(),
+        }
+    }
+
+    /// Suggest the values returned by a function of the server.
+    pub fn from_fn<F>(function: F) -> Self
+    where
+        F: for<'a> Fn(&'a S, CompletionQuery) -> BoxFuture<'a, Result<Vec<String>, McpError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            source: Source::Fn(Arc::new(function)),
+            matcher: CompletionMatcher::default(),
+        }
+    }
+
+    pub fn with_matcher(mut self, matcher: CompletionMatcher) -> Self {
+        self.matcher = matcher;
+        self
+    }
+
+    /// The suggestions for `query`, matched and truncated.
+    pub async fn complete(
+        &self,
+        server: &S,
+        query: CompletionQuery,
+    ) -> Result<CompletionInfo, McpError> {
+        let value = query.argument.value.clone();
+        let candidates = match &self.source {
+            Source::Values(values) => values.to_vec(),
+            Source::Fn(function) => function(server, query).await?,
+        };
+        Ok(CompletionInfo::truncated(
+            self.matcher.apply(&value, candidates),
+        ))
+    }
+}
+
+impl<S, T: Into<String>, const N: usize> From<[T; N]> for Completer<S> {
+    fn from(values: [T; N]) -> Self {
+        Self::values(values)
+    }
+}
+
+impl<S, T: Into<String>> From<Vec<T>> for Completer<S> {
+    fn from(values: Vec<T>) -> Self {
+        Self::values(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_matchers() {
+        let candidates = strings(&["SELECT", "DELETE", "INSERT", "UPDATE"]);
+        assert_eq!(
+            CompletionMatcher::Fuzzy.apply("te", candidates.clone()),
+            ["DELETE", "UPDATE"]
+        );
+        assert_eq!(
+            CompletionMatcher::Fuzzy.apply("sl", candidates.clone()),
+            ["SELECT"]
+        );
+        assert_eq!(
+            CompletionMatcher::Fuzzy.apply("de", candidates.clone()),
+            ["DELETE", "UPDATE"]
+        );
+        assert_eq!(
+            CompletionMatcher::Prefix.apply("in", candidates.clone()),
+            ["INSERT"]
+        );
+        assert_eq!(
+            CompletionMatcher::None.apply("x", candidates.clone()),
+            candidates
+        );
+    }
+}
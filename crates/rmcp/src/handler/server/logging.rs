@@ -0,0 +1,432 @@
+//! Forward `tracing` events to clients as MCP log messages
+//!
+//! [`McpLogging`] connects two pieces:
+//!
+//! - a [`tracing_subscriber::Layer`], created by [`McpLogging::layer`], which turns
+//!   events into `notifications/message`,
+//! - a service wrapper, created by [`McpLogging::wrap`], which keeps track of the
+//!   connected clients, answers `logging/setLevel` for them and advertises the
+//!   logging capability.
+//!
+//! Each client only receives the events at or above the level it asked for. Events
+//! emitted by `rmcp` itself are never forwarded, since sending a notification
+//! produces events of its own.
+//!
+//! ```rust,no_run
+//! # use rmcp::{ServerHandler, ServiceExt, handler::server::logging::{LoggingLayerConfig, McpLogging}};
+//! # use tracing_subscriber::layer::SubscriberExt;
+//! # #[derive(Clone)]
+//! # struct Server;
+//! # impl ServerHandler for Server {}
+//! # async fn run() -> anyhow::Result<()> {
+//! let logging = McpLogging::new(LoggingLayerConfig {
+//!     redact_fields: vec!["password".into()],
+//!     ..Default::default()
+//! });
+//! tracing::subscriber::set_global_default(tracing_subscriber::registry().with(logging.layer()))?;
+//! let service = logging
+//!     .wrap(Server)
+//!     .serve((tokio::io::stdin(), tokio::io::stdout()))
+//!     .await?;
+//! service.waiting().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use crate::{
+    Peer, RoleServer, Service,
+    error::ErrorData as McpError,
+    model::{
+        ClientNotification, ClientRequest, LoggingLevel, LoggingMessageNotificationParam,
+        ServerInfo, ServerNotification, ServerRequest, ServerResult,
+    },
+    service::{NotificationContext, RequestContext},
+};
+
+/// Options of the logging bridge.
+#[derive(Debug, Clone, Default)]
+pub struct LoggingLayerConfig {
+    /// The level for clients that have not sent `logging/setLevel` yet.
+    ///
+    /// `None`, the default, sends nothing to a client until it sets a level.
+    pub default_level: Option<LoggingLevel>,
+    /// Names of the fields whose values are replaced with [`McpLogging::REDACTED`].
+    ///
+    /// This applies to event fields and span fields alike.
+    pub redact_fields: Vec<String>,
+    /// Limit the number of messages sent to each client.
+    pub rate_limit: Option<RateLimit>,
+    /// Whether the fields of the spans enclosing an event are added to its data.
+    pub include_span_fields: bool,
+}
+
+/// Send at most `max_messages` per `interval` to a client.
+///
+/// Messages over the limit are dropped. When the next interval starts, the client
+/// receives a warning telling how many messages were dropped.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_messages: u32,
+    pub interval: Duration,
+}
+
+/// Shared state of the logging bridge, cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct McpLogging {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    config: LoggingLayerConfig,
+    peers: Mutex<Vec<LoggingPeer>>,
+}
+
+#[derive(Debug)]
+struct LoggingPeer {
+    peer: Peer<RoleServer>,
+    sender: mpsc::UnboundedSender<LoggingMessageNotificationParam>,
+    level: Option<LoggingLevel>,
+    window_start: Instant,
+    sent_in_window: u32,
+    dropped: u64,
+}
+
+impl McpLogging {
+    /// The value that replaces redacted fields.
+    pub const REDACTED: &str = "[REDACTED]";
+
+    pub fn new(config: LoggingLayerConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                peers: Default::default(),
+            }),
+        }
+    }
+
+    /// The layer to install in a `tracing` subscriber.
+    pub fn layer(&self) -> McpLoggingLayer {
+        McpLoggingLayer {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Wrap a server so that its clients receive the forwarded events.
+    pub fn wrap<S>(&self, service: S) -> LoggingService<S> {
+        LoggingService {
+            inner: service,
+            logging: self.clone(),
+        }
+    }
+
+    /// Start forwarding events to `peer`, at the default level.
+    ///
+    /// Does nothing if the peer is already registered. Must be called within a
+    /// tokio runtime.
+    pub fn register_peer(&self, peer: &Peer<RoleServer>) {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        {
+            let mut peers = self.shared.lock_peers();
+            if peers.iter().any(|p| p.peer.is_same_peer(peer)) {
+                return;
+            }
+            peers.push(LoggingPeer {
+                peer: peer.clone(),
+                sender,
+                level: self.shared.config.default_level,
+                window_start: Instant::now(),
+                sent_in_window: 0,
+                dropped: 0,
+            });
+        }
+        let worker_peer = peer.clone();
+        // a single worker per peer keeps messages in order
+        tokio::spawn(async move {
+            while let Some(param) = receiver.recv().await {
+                if worker_peer.notify_logging_message(param).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Set the minimum level of the messages sent to `peer`, registering it if needed.
+    pub fn set_level(&self, peer: &Peer<RoleServer>, level: LoggingLevel) {
+        self.register_peer(peer);
+        if let Some(logging_peer) = self
+            .shared
+            .lock_peers()
+            .iter_mut()
+            .find(|p| p.peer.is_same_peer(peer))
+        {
+            logging_peer.level = Some(level);
+        }
+    }
+}
+
+impl LoggingPeer {
+    fn is_connected(&self) -> bool {
+        !self.sender.is_closed() && !self.peer.is_transport_closed()
+    }
+
+    fn accepts(&self, level: LoggingLevel) -> bool {
+        self.level.is_some_and(|min| level >= min)
+    }
+}
+
+impl Shared {
+    fn lock_peers(&self) -> std::sync::MutexGuard<'_, Vec<LoggingPeer>> {
+        self.peers.lock().expect("logging peers lock poisoned")
+    }
+
+    /// Lock the peers, forgetting the disconnected ones.
+    fn lock_connected_peers(&self) -> std::sync::MutexGuard<'_, Vec<LoggingPeer>> {
+        let mut peers = self.lock_peers();
+        peers.retain(LoggingPeer::is_connected);
+        peers
+    }
+
+    fn wants(&self, level: LoggingLevel) -> bool {
+        self.lock_connected_peers().iter().any(|p| p.accepts(level))
+    }
+
+    fn dispatch(&self, param: LoggingMessageNotificationParam) {
+        let now = Instant::now();
+        let mut peers = self.lock_connected_peers();
+        for logging_peer in peers.iter_mut() {
+            if !logging_peer.accepts(param.level) {
+                continue;
+            }
+            if let Some(rate_limit) = self.config.rate_limit {
+                if now.duration_since(logging_peer.window_start) >= rate_limit.interval {
+                    logging_peer.window_start = now;
+                    logging_peer.sent_in_window = 0;
+                    if logging_peer.dropped > 0 {
+                        let _ = logging_peer.sender.send(LoggingMessageNotificationParam {
+                            level: LoggingLevel::Warning,
+                            logger: None,
+                            data: serde_json::json!({
+                                "message": format!(
+                                    "{} log messages were dropped by the rate limit",
+                                    logging_peer.dropped
+                                ),
+                            }),
+                        });
+                        logging_peer.dropped = 0;
+                    }
+                }
+                if logging_peer.sent_in_window >= rate_limit.max_messages {
+                    logging_peer.dropped += 1;
+                    continue;
+                }
+                logging_peer.sent_in_window += 1;
+            }
+            let _ = logging_peer.sender.send(param.clone());
+        }
+    }
+
+    fn redact(&self, fields: &mut Map<String, Value>) {
+        for name in &self.config.redact_fields {
+            if let Some(value) = fields.get_mut(name) {
+                *value = Value::String(McpLogging::REDACTED.to_owned());
+            }
+        }
+    }
+}
+
+/// A [`Layer`] forwarding events to the clients registered in an [`McpLogging`].
+#[derive(Debug, Clone)]
+pub struct McpLoggingLayer {
+    shared: Arc<Shared>,
+}
+
+/// Fields recorded on a span, stored in its extensions.
+#[derive(Debug, Default)]
+struct SpanFields(Map<String, Value>);
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0
+            .insert(field.name().to_owned(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_owned(), format!("{value:?}").into());
+    }
+}
+
+fn logging_level(level: &Level) -> LoggingLevel {
+    match *level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        _ => LoggingLevel::Debug,
+    }
+}
+
+fn is_rmcp_target(target: &str) -> bool {
+    target == "rmcp" || target.starts_with("rmcp::")
+}
+
+impl<S> Layer<S> for McpLoggingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if !self.shared.config.include_span_fields {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = SpanFields::default();
+        attrs.record(&mut JsonVisitor(&mut fields.0));
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut JsonVisitor(&mut fields.0));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if is_rmcp_target(metadata.target()) {
+            return;
+        }
+        let level = logging_level(metadata.level());
+        if !self.shared.wants(level) {
+            return;
+        }
+        let mut data = Map::new();
+        if self.shared.config.include_span_fields {
+            if let Some(scope) = ctx.event_scope(event) {
+                for span in scope.from_root() {
+                    if let Some(fields) = span.extensions().get::<SpanFields>() {
+                        data.extend(fields.0.clone());
+                    }
+                }
+            }
+        }
+        event.record(&mut JsonVisitor(&mut data));
+        self.shared.redact(&mut data);
+        self.shared.dispatch(LoggingMessageNotificationParam {
+            level,
+            logger: Some(metadata.target().to_owned()),
+            data: Value::Object(data),
+        });
+    }
+}
+
+/// A server wrapped by [`McpLogging::wrap`].
+///
+/// `logging/setLevel` requests are answered by the wrapper and never reach the
+/// inner service.
+#[derive(Debug, Clone)]
+pub struct LoggingService<S> {
+    inner: S,
+    logging: McpLogging,
+}
+
+impl<S> LoggingService<S> {
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Service<RoleServer>> Service<RoleServer> for LoggingService<S> {
+    async fn handle_request(
+        &self,
+        request: ClientRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ServerResult, McpError> {
+        if let ClientRequest::SetLevelRequest(request) = &request {
+            self.logging.set_level(&context.peer, request.params.level);
+            return Ok(ServerResult::empty(()));
+        }
+        self.logging.register_peer(&context.peer);
+        let mut result = self.inner.handle_request(request, context).await;
+        if let Ok(ServerResult::InitializeResult(info)) = &mut result {
+            info.capabilities
+                .logging
+                .get_or_insert_with(Default::default);
+        }
+        result
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: ClientNotification,
+        context: NotificationContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if let ClientNotification::InitializedNotification(_) = &notification {
+            self.logging.register_peer(&context.peer);
+        }
+        self.inner.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        let mut info = self.inner.get_info();
+        info.capabilities
+            .logging
+            .get_or_insert_with(Default::default);
+        info
+    }
+
+    fn handle_outgoing_request(&self, request: &mut ServerRequest) -> Result<(), McpError> {
+        self.inner.handle_outgoing_request(request)
+    }
+
+    fn handle_outgoing_notification(
+        &self,
+        notification: &mut ServerNotification,
+    ) -> Result<(), McpError> {
+        self.inner.handle_outgoing_notification(notification)
+    }
+}
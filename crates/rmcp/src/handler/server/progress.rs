@@ -0,0 +1,215 @@
+//! Progress reporting for long-running requests
+//!
+//! [`Progress`] is an extractor bound to the progress token the client attached to
+//! the current request. It takes care of the bookkeeping that the specification
+//! requires from a progress sender:
+//!
+//! - it does nothing when the client did not ask for progress notifications,
+//! - it drops updates that would make the progress value go backwards,
+//! - it throttles notifications so a tight loop does not flood the transport,
+//! - it flushes the last throttled update once the tool returns, before the result
+//!   is sent.
+//!
+//! ```rust
+//! # use rmcp::{handler::server::{progress::Progress, tool::ToolRouter}, tool, tool_router};
+//! # #[derive(Clone)]
+//! # struct Exporter { tool_router: ToolRouter<Self> }
+//! # #[tool_router]
+//! # impl Exporter {
+//! #[tool(description = "Export all rows")]
+//! async fn export(&self, progress: Progress) -> String {
+//!     for row in 0..1000 {
+//!         // ... export the row ...
+//!         let _ = progress
+//!             .report(row as f64 + 1.0, Some(1000.0), None)
+//!             .await;
+//!     }
+//!     "done".to_string()
+//! }
+//! # }
+//! ```
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use super::common::{AsRequestContext, FromContextPart};
+use crate::{
+    Peer, RoleServer, ServiceError,
+    model::{ProgressNotificationParam, ProgressToken},
+    service::RequestContext,
+};
+
+/// A progress reporter for the request being handled.
+///
+/// Cloning a `Progress` yields another handle to the same reporter, so the
+/// monotonic and rate limit guarantees hold across clones. The
+/// [`ToolRouter`](super::router::tool::ToolRouter) calling a tool finishes its
+/// reporter once the tool returns, so the most recent update that was held back by
+/// the rate limiter is sent before the result. A reporter created outside of it
+/// must be flushed with [`Progress::finish`], otherwise that update is dropped.
+///
+/// Note that the [`Meta`](crate::model::Meta) extractor takes the request meta,
+/// so `Progress` must be declared before it in the handler arguments.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    reporter: Option<Arc<ProgressReporter>>,
+}
+
+#[derive(Debug)]
+struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    progress_token: ProgressToken,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    min_interval: Duration,
+    last_progress: Option<f64>,
+    last_sent_at: Option<Instant>,
+    pending: Option<ProgressNotificationParam>,
+    finished: bool,
+}
+
+impl Progress {
+    /// The default minimum interval between two notifications.
+    pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Create a reporter for the given token.
+    ///
+    /// If `progress_token` is `None`, every report is a no-op.
+    pub fn new(progress_token: Option<ProgressToken>, peer: Peer<RoleServer>) -> Self {
+        let reporter = progress_token.map(|progress_token| {
+            Arc::new(ProgressReporter {
+                peer,
+                progress_token,
+                state: Mutex::new(ProgressState {
+                    min_interval: Self::DEFAULT_MIN_INTERVAL,
+                    last_progress: None,
+                    last_sent_at: None,
+                    pending: None,
+                    finished: false,
+                }),
+            })
+        });
+        Self { reporter }
+    }
+
+    /// Create a reporter from the progress token carried by the request meta.
+    ///
+    /// The reporter shared in the extensions of the request by the router calling the
+    /// handler is reused, if any.
+    pub fn from_request_context(context: &RequestContext<RoleServer>) -> Self {
+        if let Some(progress) = context.extensions.get::<Self>() {
+            return progress.clone();
+        }
+        Self::new(context.meta.get_progress_token(), context.peer.clone())
+    }
+
+    /// Set the minimum interval between two notifications.
+    ///
+    /// Updates reported more frequently are coalesced, only the latest one is kept.
+    /// Use [`Duration::ZERO`] to disable rate limiting.
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        if let Some(reporter) = &self.reporter {
+            reporter.lock_state().min_interval = min_interval;
+        }
+        self
+    }
+
+    /// Whether the client asked for progress notifications.
+    pub fn is_enabled(&self) -> bool {
+        self.reporter.is_some()
+    }
+
+    /// The progress token of the request, if any.
+    pub fn progress_token(&self) -> Option<&ProgressToken> {
+        self.reporter
+            .as_ref()
+            .map(|reporter| &reporter.progress_token)
+    }
+
+    /// Report the current progress.
+    ///
+    /// An update is ignored if `progress` is not greater than the last reported
+    /// value, or if the reporter has already finished. If the previous notification
+    /// was sent less than the minimum interval ago, the update is held back until
+    /// the next report or until the reporter finishes.
+    pub async fn report(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    ) -> Result<(), ServiceError> {
+        let Some(reporter) = &self.reporter else {
+            return Ok(());
+        };
+        let param = {
+            let mut state = reporter.lock_state();
+            if state.finished {
+                return Ok(());
+            }
+            if let Some(last_progress) = state.last_progress {
+                if progress.partial_cmp(&last_progress) != Some(std::cmp::Ordering::Greater) {
+                    tracing::debug!(progress, last_progress, "ignore non-increasing progress");
+                    return Ok(());
+                }
+            }
+            state.last_progress = Some(progress);
+            let param = ProgressNotificationParam {
+                progress_token: reporter.progress_token.clone(),
+                progress,
+                total,
+                message,
+            };
+            let now = Instant::now();
+            let throttled = state
+                .last_sent_at
+                .is_some_and(|last_sent_at| now.duration_since(last_sent_at) < state.min_interval);
+            if throttled {
+                state.pending = Some(param);
+                return Ok(());
+            }
+            state.last_sent_at = Some(now);
+            state.pending = None;
+            param
+        };
+        reporter.peer.notify_progress(param).await
+    }
+
+    /// Send the last held back update, if any, and stop reporting.
+    ///
+    /// Any report made after this call is ignored.
+    pub async fn finish(&self) -> Result<(), ServiceError> {
+        let Some(reporter) = &self.reporter else {
+            return Ok(());
+        };
+        let pending = {
+            let mut state = reporter.lock_state();
+            state.finished = true;
+            state.pending.take()
+        };
+        match pending {
+            Some(param) => reporter.peer.notify_progress(param).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl ProgressReporter {
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, ProgressState> {
+        self.state.lock().expect("progress state lock poisoned")
+    }
+}
+
+impl<C> FromContextPart<C> for Progress
+where
+    C: AsRequestContext,
+{
+    fn from_context_part(context: &mut C) -> Result<Self, crate::ErrorData> {
+        Ok(Self::from_request_context(context.as_request_context()))
+    }
+}
@@ -4,13 +4,17 @@
 //! in MCP servers. Prompts allow servers to provide reusable templates for LLM
 //! interactions with customizable arguments.
 
+#[cfg(feature = "prompt-template")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prompt-template")))]
+pub mod template;
+
 use std::{future::Future, marker::PhantomData};
 
 use futures::future::{BoxFuture, FutureExt};
 use serde::de::DeserializeOwned;
 
 use super::common::{AsRequestContext, FromContextPart};
-pub use super::common::{Extension, RequestId};
+pub use super::common::{AsyncFromContextPart, Extension, RequestId, State};
 use crate::{
     RoleServer,
     handler::server::wrapper::Parameters,
@@ -53,6 +57,9 @@ impl<S> AsRequestContext for PromptContext<'_, S> {
 }
 
 /// Trait for handling prompt retrieval
+///
+/// As with `CallToolHandler`, a function is
+/// only a handler when it is `Send + 'static`, since its extractors may be async.
 pub trait GetPromptHandler<S, A> {
     fn handle(
         self,
@@ -174,26 +181,28 @@ where
 }
 
 // Macro to generate GetPromptHandler implementations for various parameter combinations
+// The extractors may be async, so they run in the returned future together with the
+// handler, which must be `'static` like the routes storing it.
 macro_rules! impl_prompt_handler_for {
-    ($($T: ident)*) => {
-        impl_prompt_handler_for!([] [$($T)*]);
+    ($(($T: ident, $M: ident))*) => {
+        impl_prompt_handler_for!([] [$(($T, $M))*]);
     };
     // finished
-    ([$($Tn: ident)*] []) => {
-        impl_prompt_handler_for!(@impl $($Tn)*);
+    ([$(($Tn: ident, $Mn: ident))*] []) => {
+        impl_prompt_handler_for!(@impl $(($Tn, $Mn))*);
     };
-    ([$($Tn: ident)*] [$Tn_1: ident $($Rest: ident)*]) => {
-        impl_prompt_handler_for!(@impl $($Tn)*);
-        impl_prompt_handler_for!([$($Tn)* $Tn_1] [$($Rest)*]);
+    ([$(($Tn: ident, $Mn: ident))*] [($Tn_1: ident, $Mn_1: ident) $($Rest: tt)*]) => {
+        impl_prompt_handler_for!(@impl $(($Tn, $Mn))*);
+        impl_prompt_handler_for!([$(($Tn, $Mn))* ($Tn_1, $Mn_1)] [$($Rest)*]);
     };
-    (@impl $($Tn: ident)*) => {
+    (@impl $(($Tn: ident, $Mn: ident))*) => {
         // Implementation for async methods (transformed by #[prompt] macro)
-        impl<$($Tn,)* S, F, R> GetPromptHandler<S, ($($Tn,)*)> for F
+        impl<$($Tn, $Mn,)* S, F, R> GetPromptHandler<S, ($(($Tn, $Mn),)*)> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<PromptContext<'a, S>> + Send,
+                $Tn: for<'a> AsyncFromContextPart<PromptContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R> + Send,
+            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R> + Send + 'static,
             R: IntoGetPromptResult + Send + 'static,
             S: Send + Sync + 'static,
         {
@@ -203,17 +212,12 @@ macro_rules! impl_prompt_handler_for {
                 mut context: PromptContext<'_, S>,
             ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>>
             {
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                let service = context.server;
-                let fut = self(service, $($Tn,)*);
                 async move {
-                    let result = fut.await;
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    let service = context.server;
+                    let result = self(service, $($Tn,)*).await;
                     result.into_get_prompt_result()
                 }.boxed()
             }
@@ -221,12 +225,12 @@ macro_rules! impl_prompt_handler_for {
 
 
         // Implementation for sync methods
-        impl<$($Tn,)* S, F, R> GetPromptHandler<S, SyncPromptMethodAdapter<($($Tn,)*), R>> for F
+        impl<$($Tn, $Mn,)* S, F, R> GetPromptHandler<S, SyncPromptMethodAdapter<($(($Tn, $Mn),)*), R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<PromptContext<'a, S>> + Send,
+                $Tn: for<'a> AsyncFromContextPart<PromptContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce(&S, $($Tn,)*) -> R + Send,
+            F: FnOnce(&S, $($Tn,)*) -> R + Send + 'static,
             R: IntoGetPromptResult + Send,
             S: Send + Sync,
         {
@@ -236,25 +240,23 @@ macro_rules! impl_prompt_handler_for {
                 mut context: PromptContext<'_, S>,
             ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>>
             {
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                let service = context.server;
-                let result = self(service, $($Tn,)*);
-                std::future::ready(result.into_get_prompt_result()).boxed()
+                async move {
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    let service = context.server;
+                    let result = self(service, $($Tn,)*);
+                    result.into_get_prompt_result()
+                }.boxed()
             }
         }
 
 
         // AsyncPromptAdapter - for standalone functions returning GetPromptResult
-        impl<$($Tn,)* S, F, Fut, R> GetPromptHandler<S, AsyncPromptAdapter<($($Tn,)*), Fut, R>> for F
+        impl<$($Tn, $Mn,)* S, F, Fut, R> GetPromptHandler<S, AsyncPromptAdapter<($(($Tn, $Mn),)*), Fut, R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<PromptContext<'a, S>> + Send + 'static,
+                $Tn: for<'a> AsyncFromContextPart<PromptContext<'a, S>, $Mn> + Send + 'static,
             )*
             F: FnOnce($($Tn,)*) -> Fut + Send + 'static,
             Fut: Future<Output = Result<R, crate::ErrorData>> + Send + 'static,
@@ -267,30 +269,22 @@ macro_rules! impl_prompt_handler_for {
                 mut context: PromptContext<'_, S>,
             ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>>
             {
-                // Extract all parameters before moving into the async block
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-
-                // Since we're dealing with standalone functions that don't take &S,
-                // we can return a 'static future
-                Box::pin(async move {
+                async move {
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
                     let result = self($($Tn,)*).await?;
                     result.into_get_prompt_result()
-                })
+                }.boxed()
             }
         }
 
 
         // SyncPromptAdapter - for standalone sync functions returning Result
-        impl<$($Tn,)* S, F, R> GetPromptHandler<S, SyncPromptAdapter<($($Tn,)*), R>> for F
+        impl<$($Tn, $Mn,)* S, F, R> GetPromptHandler<S, SyncPromptAdapter<($(($Tn, $Mn),)*), R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<PromptContext<'a, S>> + Send + 'static,
+                $Tn: for<'a> AsyncFromContextPart<PromptContext<'a, S>, $Mn> + Send + 'static,
             )*
             F: FnOnce($($Tn,)*) -> Result<R, crate::ErrorData> + Send + 'static,
             R: IntoGetPromptResult + Send + 'static,
@@ -302,15 +296,13 @@ macro_rules! impl_prompt_handler_for {
                 mut context: PromptContext<'_, S>,
             ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>>
             {
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                let result = self($($Tn,)*);
-                std::future::ready(result.and_then(|r| r.into_get_prompt_result())).boxed()
+                async move {
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    let result = self($($Tn,)*);
+                    result.and_then(|r| r.into_get_prompt_result())
+                }.boxed()
             }
         }
 
@@ -318,7 +310,11 @@ macro_rules! impl_prompt_handler_for {
 }
 
 // Invoke the macro to generate implementations for up to 16 parameters
-impl_prompt_handler_for!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15);
+impl_prompt_handler_for!((T0, M0)(T1, M1)(T2, M2)(T3, M3)(T4, M4)(T5, M5)(T6, M6)(
+    T7, M7
+)(T8, M8)(T9, M9)(T10, M10)(T11, M11)(T12, M12)(T13, M13)(
+    T14, M14
+)(T15, M15));
 
 /// Extract prompt arguments from a type's JSON schema
 /// This function analyzes the schema of a type and extracts the properties
@@ -0,0 +1,642 @@
+//! Prompts rendered from templates
+//!
+//! A [`PromptTemplate`] declares the messages of a prompt as text, so that prompts can be
+//! kept in files or loaded from a database instead of being assembled in code:
+//!
+//! ```text
+//! @user
+//! Review the following {{language}} code{{focus| for correctness}}.
+//! @resource file:///src/{{path}}
+//! @image {{screenshot?}}
+//! @assistant
+//! I will review `{{path}}` and report the issues by severity.
+//! ```
+//!
+//! - `@user` and `@assistant`, alone on a line, start the messages of that role. The
+//!   lines before the first of them are a user message.
+//! - `{{name}}` is replaced by the argument `name`, which is required.
+//! - `{{name?}}` is replaced by the argument `name` if given, else by nothing.
+//! - `{{name|text}}` is replaced by the argument `name` if given, else by `text`.
+//! - `@resource <uri>` embeds the contents of a resource, read with
+//!   [`ServerHandler::read_resource`].
+//! - `@image <uri>` adds the image of a blob resource, also read with
+//!   [`ServerHandler::read_resource`].
+//!
+//! The uris of the directives may contain placeholders, whose arguments are percent-encoded
+//! so that they stay within their part of the uri. A directive is skipped when its uri or
+//! one of its placeholders renders empty, and rejected when its uri has a `..` segment. The arguments of the prompt are the variables of the template, in
+//! order of appearance, required when one of their placeholders has no fallback.
+//!
+//! ```rust
+//! # use rmcp::handler::server::{prompt::template::PromptTemplate, router::prompt::PromptRouter};
+//! # struct Server;
+//! # impl rmcp::ServerHandler for Server {}
+//! # fn main() -> Result<(), rmcp::handler::server::prompt::template::PromptTemplateError> {
+//! let template = PromptTemplate::parse("greet", "Say hello to {{name}} in {{language|English}}.")?
+//!     .with_description("Greet someone")
+//!     .with_argument_description("name", "Who to greet");
+//! let router = PromptRouter::<Server>::new().with_route(template);
+//! # Ok(())
+//! # }
+//! ```
+use std::{path::Path, sync::Arc};
+
+use futures::FutureExt;
+
+use crate::{
+    ErrorData as McpError, RoleServer, ServerHandler,
+    handler::server::{
+        prompt::PromptContext,
+        router::prompt::{IntoPromptRoute, PromptRoute},
+    },
+    model::{
+        AnnotateAble, GetPromptResult, Prompt, PromptArgument, PromptMessage, PromptMessageContent,
+        PromptMessageRole, RawEmbeddedResource, RawImageContent, ReadResourceRequestParam,
+        ResourceContents,
+    },
+    service::RequestContext,
+};
+
+/// Errors of the parsing of a template.
+#[derive(Debug, thiserror::Error)]
+pub enum PromptTemplateError {
+    #[error("Unclosed placeholder at line {0}")]
+    UnclosedPlaceholder(usize),
+
+    #[error("Invalid placeholder '{placeholder}' at line {line}")]
+    InvalidPlaceholder { placeholder: String, line: usize },
+
+    #[error("The template has no message")]
+    Empty,
+
+    #[error("Failed to read the template: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Variable {
+        name: String,
+        /// The text without the argument, `None` if the argument is required
+        fallback: Option<String>,
+    },
+}
+
+/// A text with placeholders
+#[derive(Debug, Clone, PartialEq)]
+struct Fragment(Vec<Piece>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Text(Fragment),
+    Resource(Fragment),
+    Image(Fragment),
+}
+
+/// A prompt whose messages are rendered from a template.
+///
+/// See the [module documentation](self) for the syntax of templates.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    prompt: Prompt,
+    blocks: Arc<[(PromptMessageRole, Block)]>,
+}
+
+impl PromptTemplate {
+    /// Parse the template of the prompt `name`.
+    pub fn parse(name: impl Into<String>, source: &str) -> Result<Self, PromptTemplateError> {
+        let blocks = parse_blocks(source)?;
+        if blocks.is_empty() {
+            return Err(PromptTemplateError::Empty);
+        }
+        let mut arguments: Vec<PromptArgument> = Vec::new();
+        for (_, block) in &blocks {
+            let (Block::Text(fragment) | Block::Resource(fragment) | Block::Image(fragment)) =
+                block;
+            for piece in &fragment.0 {
+                let Piece::Variable { name, fallback } = piece else {
+                    continue;
+                };
+                let required = fallback.is_none();
+                match arguments.iter_mut().find(|argument| argument.name == *name) {
+                    Some(argument) => {
+                        argument.required = Some(argument.required == Some(true) || required)
+                    }
+                    None => arguments.push(PromptArgument {
+                        name: name.clone(),
+                        title: None,
+                        description: None,
+                        required: Some(required),
+                    }),
+                }
+            }
+        }
+        let arguments = (!arguments.is_empty()).then_some(arguments);
+        Ok(Self {
+            prompt: Prompt::new(name, None::<String>, arguments),
+            blocks: blocks.into(),
+        })
+    }
+
+    /// Read the template of a file, named after the file without its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, PromptTemplateError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::parse(name, &source)
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.prompt.title = Some(title.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.prompt.description = Some(description.into());
+        self
+    }
+
+    /// Describe an argument, ignored if the template has no such variable.
+    pub fn with_argument_description(mut self, name: &str, description: impl Into<String>) -> Self {
+        let argument = self
+            .prompt
+            .arguments
+            .iter_mut()
+            .flatten()
+            .find(|argument| argument.name == name);
+        if let Some(argument) = argument {
+            argument.description = Some(description.into());
+        }
+        self
+    }
+
+    /// The prompt, with the arguments of the template.
+    pub fn prompt(&self) -> &Prompt {
+        &self.prompt
+    }
+
+    pub fn name(&self) -> &str {
+        &self.prompt.name
+    }
+
+    /// Render the messages for `arguments`.
+    ///
+    /// The arguments are checked first: the required ones must be given, and the others
+    /// must be variables of the template. The resources and images of the directives are
+    /// read from `server`.
+    pub async fn render<S: ServerHandler>(
+        &self,
+        server: &S,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let values = self.validate(arguments.unwrap_or_default())?;
+        let get = |name: &str| {
+            values
+                .iter()
+                .find(|(argument, _)| argument == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let mut messages = Vec::new();
+        for (role, block) in self.blocks.iter() {
+            match block {
+                Block::Text(fragment) => {
+                    let text = fragment.render(get);
+                    if !text.trim().is_empty() {
+                        messages.push(PromptMessage::new_text(role.clone(), text));
+                    }
+                }
+                Block::Resource(fragment) | Block::Image(fragment) => {
+                    let Some(uri) = fragment.render_uri(get)? else {
+                        continue;
+                    };
+                    let uri = uri.as_str();
+                    let result = server
+                        .read_resource(
+                            ReadResourceRequestParam {
+                                uri: uri.to_string(),
+                            },
+                            context.clone(),
+                        )
+                        .await?;
+                    for contents in result.contents {
+                        let content = if matches!(block, Block::Image(_)) {
+                            image_content(uri, contents)?
+                        } else {
+                            PromptMessageContent::Resource {
+                                resource: RawEmbeddedResource {
+                                    meta: None,
+                                    resource: contents,
+                                }
+                                .no_annotation(),
+                            }
+                        };
+                        messages.push(PromptMessage {
+                            role: role.clone(),
+                            content,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(GetPromptResult {
+            description: self.prompt.description.clone(),
+            messages,
+        })
+    }
+
+    /// The values of the given arguments, as text.
+    fn validate(
+        &self,
+        arguments: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Vec<(String, String)>, McpError> {
+        let expected = self.prompt.arguments.as_deref().unwrap_or_default();
+        let mut values = Vec::new();
+        for (name, value) in arguments {
+            if !expected.iter().any(|argument| argument.name == name) {
+                return Err(McpError::invalid_params(
+                    format!("unknown argument '{name}' of prompt '{}'", self.name()),
+                    Some(serde_json::json!({
+                        "arguments": expected.iter().map(|argument| &argument.name).collect::<Vec<_>>()
+                    })),
+                ));
+            }
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(value) => value,
+                serde_json::Value::Bool(_) | serde_json::Value::Number(_) => value.to_string(),
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "argument '{name}' of prompt '{}' is not a string",
+                            self.name()
+                        ),
+                        None,
+                    ));
+                }
+            };
+            values.push((name, value));
+        }
+        let missing: Vec<_> = expected
+            .iter()
+            .filter(|argument| argument.required == Some(true))
+            .filter(|argument| !values.iter().any(|(name, _)| *name == argument.name))
+            .map(|argument| argument.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "missing required arguments of prompt '{}': {}",
+                    self.name(),
+                    missing.join(", ")
+                ),
+                None,
+            ));
+        }
+        Ok(values)
+    }
+
+    /// A route rendering the template.
+    pub fn into_route<S: ServerHandler>(self) -> PromptRoute<S> {
+        let attr = self.prompt.clone();
+        PromptRoute::new_dyn(attr, move |context: PromptContext<'_, S>| {
+            let template = self.clone();
+            async move {
+                template
+                    .render(context.server, context.arguments, context.context)
+                    .await
+            }
+            .boxed()
+        })
+    }
+}
+
+impl<S: ServerHandler> From<PromptTemplate> for PromptRoute<S> {
+    fn from(template: PromptTemplate) -> Self {
+        template.into_route()
+    }
+}
+
+impl<S: ServerHandler> IntoPromptRoute<S, ()> for PromptTemplate {
+    fn into_prompt_route(self) -> PromptRoute<S> {
+        self.into_route()
+    }
+}
+
+fn image_content(uri: &str, contents: ResourceContents) -> Result<PromptMessageContent, McpError> {
+    match contents {
+        ResourceContents::BlobResourceContents {
+            blob,
+            mime_type: Some(mime_type),
+            ..
+        } if mime_type.starts_with("image/") => Ok(PromptMessageContent::Image {
+            image: RawImageContent {
+                data: blob,
+                mime_type,
+                meta: None,
+            }
+            .no_annotation(),
+        }),
+        _ => Err(McpError::internal_error(
+            format!("resource '{uri}' is not an image"),
+            None,
+        )),
+    }
+}
+
+impl Fragment {
+    /// Parse a text starting at `line`.
+    fn parse(source: &str, line: usize) -> Result<Self, PromptTemplateError> {
+        let mut pieces = Vec::new();
+        let mut rest = source;
+        let mut line = line;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                pieces.push(Piece::Text(rest[..start].to_string()));
+            }
+            line += rest[..start].matches('\n').count();
+            let Some(len) = rest[start + 2..].find("}}") else {
+                return Err(PromptTemplateError::UnclosedPlaceholder(line));
+            };
+            let placeholder = &rest[start + 2..start + 2 + len];
+            pieces.push(parse_variable(placeholder).ok_or_else(|| {
+                PromptTemplateError::InvalidPlaceholder {
+                    placeholder: placeholder.to_string(),
+                    line,
+                }
+            })?);
+            line += placeholder.matches('\n').count();
+            rest = &rest[start + 2 + len + 2..];
+        }
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest.to_string()));
+        }
+        Ok(Self(pieces))
+    }
+
+    fn render<'a>(&self, get: impl Fn(&str) -> Option<&'a str>) -> String {
+        let mut text = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(part) => text.push_str(part),
+                Piece::Variable { name, fallback } => {
+                    // the required arguments are checked before rendering
+                    text.push_str(get(name).or(fallback.as_deref()).unwrap_or_default())
+                }
+            }
+        }
+        text
+    }
+
+    /// Render the uri of a directive, or `None` when it or one of its placeholders is empty.
+    fn render_uri<'a>(
+        &self,
+        get: impl Fn(&str) -> Option<&'a str>,
+    ) -> Result<Option<String>, McpError> {
+        let mut uri = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(part) => uri.push_str(part),
+                Piece::Variable { name, fallback } => {
+                    let value = match get(name) {
+                        Some(value) => percent_encode(value),
+                        None => fallback.clone().unwrap_or_default(),
+                    };
+                    if value.is_empty() {
+                        return Ok(None);
+                    }
+                    uri.push_str(&value);
+                }
+            }
+        }
+        let uri = uri.trim();
+        if uri.is_empty() {
+            return Ok(None);
+        }
+        let path = uri.split(['?', '#']).next().unwrap_or_default();
+        if path
+            .split('/')
+            .any(|segment| segment.replace("%2e", ".").replace("%2E", ".") == "..")
+        {
+            return Err(McpError::invalid_params(
+                format!("uri '{uri}' has a '..' segment"),
+                None,
+            ));
+        }
+        Ok(Some(uri.to_string()))
+    }
+}
+
+/// Percent-encode all but the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Parse `name`, `name?` or `name|fallback`.
+fn parse_variable(placeholder: &str) -> Option<Piece> {
+    let (name, fallback) = match placeholder.split_once('|') {
+        Some((name, fallback)) => (name.trim(), Some(fallback.to_string())),
+        None => match placeholder.trim().strip_suffix('?') {
+            Some(name) => (name.trim_end(), Some(String::new())),
+            None => (placeholder.trim(), None),
+        },
+    };
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-');
+    valid.then(|| Piece::Variable {
+        name: name.to_string(),
+        fallback,
+    })
+}
+
+fn parse_blocks(source: &str) -> Result<Vec<(PromptMessageRole, Block)>, PromptTemplateError> {
+    let mut blocks = Vec::new();
+    let mut role = PromptMessageRole::User;
+    // the lines of the current text, with the number of the first one
+    let mut text: Vec<&str> = Vec::new();
+    let mut text_line = 1;
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let directive = line.trim();
+        let block = if let Some(uri) = directive.strip_prefix("@resource ") {
+            Some(Block::Resource(Fragment::parse(uri.trim(), number)?))
+        } else if let Some(uri) = directive.strip_prefix("@image ") {
+            Some(Block::Image(Fragment::parse(uri.trim(), number)?))
+        } else {
+            None
+        };
+        let next_role = match directive {
+            "@user" => Some(PromptMessageRole::User),
+            "@assistant" => Some(PromptMessageRole::Assistant),
+            _ => None,
+        };
+        if block.is_none() && next_role.is_none() {
+            if text.is_empty() {
+                text_line = number;
+            }
+            text.push(line);
+            continue;
+        }
+        flush_text(&role, &mut text, text_line, &mut blocks)?;
+        if let Some(block) = block {
+            blocks.push((role.clone(), block));
+        }
+        if let Some(next_role) = next_role {
+            role = next_role;
+        }
+    }
+    flush_text(&role, &mut text, text_line, &mut blocks)?;
+    Ok(blocks)
+}
+
+/// Add the lines of `text` as a text block, starting at `line`.
+fn flush_text(
+    role: &PromptMessageRole,
+    text: &mut Vec<&str>,
+    line: usize,
+    blocks: &mut Vec<(PromptMessageRole, Block)>,
+) -> Result<(), PromptTemplateError> {
+    // blank lines around a message are not part of it
+    let start = text.iter().position(|line| !line.trim().is_empty());
+    let end = text.iter().rposition(|line| !line.trim().is_empty());
+    if let (Some(start), Some(end)) = (start, end) {
+        let fragment = Fragment::parse(&text[start..=end].join("\n"), line + start)?;
+        blocks.push((role.clone(), Block::Text(fragment)));
+    }
+    text.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REVIEW: &str = "\
+@user
+Review the following {{language}} code{{focus| for correctness}}.
+@resource file:///src/{{path}}
+@image mem://{{screenshot?}}
+
+@assistant
+
+I will review `{{path}}`.
+";
+
+    #[test]
+    fn test_parse() {
+        let template = PromptTemplate::parse("review", REVIEW).unwrap();
+        let arguments: Vec<_> = template
+            .prompt()
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| (argument.name.as_str(), argument.required))
+            .collect();
+        assert_eq!(
+            arguments,
+            [
+                ("language", Some(true)),
+                ("focus", Some(false)),
+                ("path", Some(true)),
+                ("screenshot", Some(false)),
+            ]
+        );
+        let roles: Vec<_> = template
+            .blocks
+            .iter()
+            .map(|(role, _)| role.clone())
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                PromptMessageRole::User,
+                PromptMessageRole::User,
+                PromptMessageRole::User,
+                PromptMessageRole::Assistant,
+            ]
+        );
+        let (_, Block::Text(text)) = &template.blocks[3] else {
+            panic!("not a text");
+        };
+        let text = text.render(|name| (name == "path").then_some("main.rs"));
+        assert_eq!(text, "I will review `main.rs`.");
+    }
+
+    #[test]
+    fn test_render_uri() {
+        let uri = Fragment::parse("file:///src/{{path}}?rev={{rev|main}}", 1).unwrap();
+        let render = |path: &'static str| uri.render_uri(|name| (name == "path").then_some(path));
+        assert_eq!(
+            render("main.rs").unwrap().as_deref(),
+            Some("file:///src/main.rs?rev=main")
+        );
+        // the arguments can't leave their part of the uri
+        assert_eq!(
+            render("../etc/passwd?x=1").unwrap().as_deref(),
+            Some("file:///src/..%2Fetc%2Fpasswd%3Fx%3D1?rev=main")
+        );
+        assert_eq!(
+            render("hé llo").unwrap().as_deref(),
+            Some("file:///src/h%C3%A9%20llo?rev=main")
+        );
+        assert!(render("..").is_err());
+        assert!(render("").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            PromptTemplate::parse("broken", "@user\nhello\n{{name"),
+            Err(PromptTemplateError::UnclosedPlaceholder(3))
+        ));
+        assert!(matches!(
+            PromptTemplate::parse("broken", "hello\n\n{{first name}}"),
+            Err(PromptTemplateError::InvalidPlaceholder { line: 3, .. })
+        ));
+        assert!(matches!(
+            PromptTemplate::parse("empty", "@user\n\n@assistant\n"),
+            Err(PromptTemplateError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let template = PromptTemplate::parse("review", REVIEW).unwrap();
+        let arguments = |value: serde_json::Value| value.as_object().cloned().unwrap();
+        let values = template
+            .validate(arguments(serde_json::json!({
+                "language": "Rust",
+                "path": "main.rs",
+                "focus": null,
+            })))
+            .unwrap();
+        assert_eq!(values.len(), 2);
+        let missing = template
+            .validate(arguments(serde_json::json!({ "language": "Rust" })))
+            .unwrap_err();
+        assert!(missing.message.contains("path"));
+        assert!(
+            template
+                .validate(arguments(serde_json::json!({
+                    "language": "Rust",
+                    "path": "main.rs",
+                    "lines": 10,
+                })))
+                .is_err()
+        );
+    }
+}
@@ -1 +1,3 @@
-
+//! Ready-made resource providers
+#[cfg(feature = "base64")]
+pub mod filesystem;
@@ -0,0 +1,540 @@
+//! Serve directory trees as resources
+//!
+//! [`FileSystemResources`] answers `resources/list`, `resources/read` and the
+//! subscription requests for the files under some roots, with `file://` URIs. A file is
+//! read as a text when its MIME type is a text one and it is valid UTF-8, and as a blob
+//! otherwise.
+//!
+//! Nothing outside the roots is served: a URI is resolved, symbolic links and `..`
+//! included, before it is checked against them. When the client has roots of its own,
+//! only the files under both its roots and the server's are served.
+//!
+//! The roots are scanned at regular intervals while a client is connected, which sends
+//! `notifications/resources/list_changed` when files are added or removed, and
+//! `notifications/resources/updated` when a subscribed file changes.
+//!
+//! ```rust,no_run
+//! # use rmcp::{ErrorData as McpError, RoleServer, ServerHandler, handler::server::resource::filesystem::FileSystemResources, model::*, service::RequestContext};
+//! #[derive(Clone)]
+//! struct Server {
+//!     files: FileSystemResources,
+//! }
+//!
+//! impl ServerHandler for Server {
+//!     fn get_info(&self) -> ServerInfo {
+//!         ServerInfo {
+//!             capabilities: ServerCapabilities::builder()
+//!                 .enable_resources()
+//!                 .enable_resources_subscribe()
+//!                 .enable_resources_list_changed()
+//!                 .build(),
+//!             ..Default::default()
+//!         }
+//!     }
+//!
+//!     async fn list_resources(
+//!         &self,
+//!         request: Option<PaginatedRequestParam>,
+//!         context: RequestContext<RoleServer>,
+//!     ) -> Result<ListResourcesResult, McpError> {
+//!         self.files.list(request, &context).await
+//!     }
+//!
+//!     async fn read_resource(
+//!         &self,
+//!         request: ReadResourceRequestParam,
+//!         context: RequestContext<RoleServer>,
+//!     ) -> Result<ReadResourceResult, McpError> {
+//!         self.files.read(request, &context).await
+//!     }
+//!
+//!     async fn subscribe(
+//!         &self,
+//!         request: SubscribeRequestParam,
+//!         context: RequestContext<RoleServer>,
+//!     ) -> Result<(), McpError> {
+//!         self.files.subscribe(request, &context).await
+//!     }
+//!
+//!     async fn unsubscribe(
+//!         &self,
+//!         request: UnsubscribeRequestParam,
+//!         context: RequestContext<RoleServer>,
+//!     ) -> Result<(), McpError> {
+//!         self.files.unsubscribe(request, &context);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let server = Server {
+//!     files: FileSystemResources::new(["./docs"])?,
+//! };
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+use crate::{
+    Peer, RoleServer,
+    error::ErrorData as McpError,
+    model::{
+        AnnotateAble, ListResourcesResult, OCTET_STREAM_MIME_TYPE, PaginatedRequestParam,
+        RawResource, ReadResourceRequestParam, ReadResourceResult, ResourceContents,
+        ResourceUpdatedNotificationParam, SubscribeRequestParam, UnsubscribeRequestParam, file_uri,
+        file_uri_path, guess_mime_type, guess_mime_type_from_path,
+    },
+    service::RequestContext,
+};
+
+/// Options of the [`FileSystemResources`].
+#[derive(Debug, Clone)]
+pub struct FileSystemResourcesConfig {
+    /// The number of resources in a page of `resources/list`.
+    pub page_size: usize,
+    /// How often the roots are scanned for changes.
+    pub poll_interval: Duration,
+    /// Serve only the files under the roots of the client, when it has some.
+    pub match_client_roots: bool,
+    /// Serve the files and directories whose name starts with a dot.
+    pub include_hidden: bool,
+}
+
+impl Default for FileSystemResourcesConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 100,
+            poll_interval: Duration::from_secs(2),
+            match_client_roots: true,
+            include_hidden: false,
+        }
+    }
+}
+
+/// Serve the files under some roots as resources, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct FileSystemResources {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    roots: Vec<PathBuf>,
+    config: FileSystemResourcesConfig,
+    peers: Mutex<Vec<WatchingPeer>>,
+    watching: AtomicBool,
+}
+
+/// A client notified of the changes
+#[derive(Debug)]
+struct WatchingPeer {
+    peer: Peer<RoleServer>,
+    subscriptions: HashSet<String>,
+}
+
+/// What tells that a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+type Snapshot = BTreeMap<PathBuf, FileStamp>;
+
+fn io_error(error: std::io::Error) -> McpError {
+    McpError::internal_error(format!("file system error: {error}"), None)
+}
+
+fn not_found(uri: &str) -> McpError {
+    McpError::resource_not_found(
+        format!("resource not found: {uri}"),
+        Some(serde_json::json!({ "uri": uri })),
+    )
+}
+
+fn is_hidden(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root).is_ok_and(|relative| {
+        relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json"
+                | "application/xml"
+                | "application/toml"
+                | "application/yaml"
+                | "image/svg+xml"
+        )
+}
+
+/// The contents of a file, a text if it looks like one
+fn file_contents(uri: String, path: &Path, data: Vec<u8>) -> ResourceContents {
+    let mime_type = guess_mime_type(&data, Some(path));
+    let unknown = mime_type == OCTET_STREAM_MIME_TYPE && !data.contains(&0);
+    let data = if is_text(mime_type) || unknown {
+        match String::from_utf8(data) {
+            Ok(text) => {
+                return ResourceContents::TextResourceContents {
+                    uri,
+                    mime_type: Some(if unknown { "text/plain" } else { mime_type }.to_string()),
+                    text,
+                    meta: None,
+                };
+            }
+            Err(error) => error.into_bytes(),
+        }
+    } else {
+        data
+    };
+    ResourceContents::BlobResourceContents {
+        uri,
+        mime_type: Some(mime_type.to_string()),
+        blob: BASE64_STANDARD.encode(data),
+        meta: None,
+    }
+}
+
+impl Shared {
+    fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .find(|root| path.starts_with(root))
+            .map(PathBuf::as_path)
+    }
+
+    /// Every file under the roots, by canonical path
+    fn scan(&self) -> Snapshot {
+        let mut files = Snapshot::new();
+        let mut directories: Vec<PathBuf> = self.roots.clone();
+        while let Some(directory) = directories.pop() {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    if self.config.include_hidden
+                        || !entry.file_name().to_string_lossy().starts_with('.')
+                    {
+                        directories.push(path);
+                    }
+                    continue;
+                }
+                // linked files are served when they are under the roots too, linked
+                // directories are not followed
+                let Ok(path) = std::fs::canonicalize(&path) else {
+                    continue;
+                };
+                let Ok(metadata) = std::fs::metadata(&path) else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                let Some(root) = self.root_of(&path) else {
+                    continue;
+                };
+                if !self.config.include_hidden && is_hidden(&path, root) {
+                    continue;
+                }
+                files.insert(
+                    path,
+                    FileStamp {
+                        len: metadata.len(),
+                        modified: metadata.modified().ok(),
+                    },
+                );
+            }
+        }
+        files
+    }
+
+    /// The canonical path of a `file://` uri, if it is under the roots
+    fn resolve(&self, uri: &str) -> Result<PathBuf, McpError> {
+        let path = file_uri_path(uri).ok_or_else(|| not_found(uri))?;
+        let path = std::fs::canonicalize(path).map_err(|_| not_found(uri))?;
+        let root = self.root_of(&path).ok_or_else(|| not_found(uri))?;
+        if !self.config.include_hidden && is_hidden(&path, root) {
+            return Err(not_found(uri));
+        }
+        Ok(path)
+    }
+}
+
+impl FileSystemResources {
+    /// Serve the files under `roots`, which must exist.
+    pub fn new(roots: impl IntoIterator<Item = impl AsRef<Path>>) -> std::io::Result<Self> {
+        Self::with_config(roots, FileSystemResourcesConfig::default())
+    }
+
+    pub fn with_config(
+        roots: impl IntoIterator<Item = impl AsRef<Path>>,
+        config: FileSystemResourcesConfig,
+    ) -> std::io::Result<Self> {
+        let roots = roots
+            .into_iter()
+            .map(std::fs::canonicalize)
+            .collect::<std::io::Result<_>>()?;
+        Ok(Self {
+            shared: Arc::new(Shared {
+                roots,
+                config,
+                peers: Default::default(),
+                watching: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// The canonical roots.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.shared.roots
+    }
+
+    pub fn config(&self) -> &FileSystemResourcesConfig {
+        &self.shared.config
+    }
+
+    /// Answer `resources/list`, the cursor is the index of the first resource of the page.
+    pub async fn list(
+        &self,
+        params: Option<PaginatedRequestParam>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        self.watch(&context.peer);
+        let client_roots = self.client_roots(&context.peer).await?;
+        let shared = self.shared.clone();
+        let files = tokio::task::spawn_blocking(move || shared.scan())
+            .await
+            .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+        let resources: Vec<_> = files
+            .into_iter()
+            .filter(|(path, _)| is_allowed(path, client_roots.as_deref()))
+            .filter_map(|(path, stamp)| {
+                let root = self.shared.root_of(&path)?;
+                let name = path.strip_prefix(root).ok()?.to_string_lossy().into_owned();
+                let mut resource = RawResource::new(file_uri(&path).ok()?, name);
+                resource.mime_type = guess_mime_type_from_path(&path).map(str::to_string);
+                resource.size = u32::try_from(stamp.len).ok();
+                Some(resource.no_annotation())
+            })
+            .collect();
+        let start = match params.and_then(|params| params.cursor) {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .map_err(|_| McpError::invalid_params("invalid cursor", None))?,
+            None => 0,
+        };
+        let end = start
+            .saturating_add(self.shared.config.page_size.max(1))
+            .min(resources.len());
+        Ok(ListResourcesResult {
+            next_cursor: (end < resources.len()).then(|| end.to_string()),
+            resources: resources.get(start..end).unwrap_or_default().to_vec(),
+        })
+    }
+
+    /// Answer `resources/read`.
+    pub async fn read(
+        &self,
+        params: ReadResourceRequestParam,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.watch(&context.peer);
+        let client_roots = self.client_roots(&context.peer).await?;
+        let shared = self.shared.clone();
+        let contents = tokio::task::spawn_blocking(move || {
+            let path = shared.resolve(&params.uri)?;
+            if !is_allowed(&path, client_roots.as_deref()) {
+                return Err(not_found(&params.uri));
+            }
+            let data = std::fs::read(&path).map_err(io_error)?;
+            Ok(file_contents(params.uri, &path, data))
+        })
+        .await
+        .map_err(|error| McpError::internal_error(error.to_string(), None))??;
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    /// Answer `resources/subscribe`, the client is notified when the file changes.
+    pub async fn subscribe(
+        &self,
+        params: SubscribeRequestParam,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let client_roots = self.client_roots(&context.peer).await?;
+        let shared = self.shared.clone();
+        let uri = tokio::task::spawn_blocking(move || {
+            let path = shared.resolve(&params.uri)?;
+            if !is_allowed(&path, client_roots.as_deref()) {
+                return Err(not_found(&params.uri));
+            }
+            file_uri(&path).map_err(io_error)
+        })
+        .await
+        .map_err(|error| McpError::internal_error(error.to_string(), None))??;
+        self.watch(&context.peer);
+        let mut peers = self.shared.peers.lock().expect("watching peers lock");
+        if let Some(watching) = peers
+            .iter_mut()
+            .find(|watching| watching.peer.is_same_peer(&context.peer))
+        {
+            watching.subscriptions.insert(uri);
+        }
+        Ok(())
+    }
+
+    /// Answer `resources/unsubscribe`.
+    pub fn unsubscribe(
+        &self,
+        params: UnsubscribeRequestParam,
+        context: &RequestContext<RoleServer>,
+    ) {
+        let uri = self
+            .shared
+            .resolve(&params.uri)
+            .ok()
+            .and_then(|path| file_uri(&path).ok())
+            .unwrap_or(params.uri);
+        let mut peers = self.shared.peers.lock().expect("watching peers lock");
+        for watching in peers.iter_mut() {
+            if watching.peer.is_same_peer(&context.peer) {
+                watching.subscriptions.remove(&uri);
+            }
+        }
+    }
+
+    /// The roots of the client, `None` if it has none or they are not matched
+    async fn client_roots(
+        &self,
+        peer: &Peer<RoleServer>,
+    ) -> Result<Option<Vec<PathBuf>>, McpError> {
+        let has_roots = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if !self.shared.config.match_client_roots || !has_roots {
+            return Ok(None);
+        }
+        let roots = peer.list_roots().await.map_err(|error| {
+            McpError::internal_error(format!("failed to list the client roots: {error}"), None)
+        })?;
+        Ok(Some(
+            roots
+                .roots
+                .iter()
+                .filter_map(|root| file_uri_path(&root.uri))
+                .map(|path| std::fs::canonicalize(&path).unwrap_or(path))
+                .collect(),
+        ))
+    }
+
+    /// Notify `peer` of the changes, scanning the roots while a client is connected.
+    pub fn watch(&self, peer: &Peer<RoleServer>) {
+        let mut peers = self.shared.peers.lock().expect("watching peers lock");
+        if !peers
+            .iter()
+            .any(|watching| watching.peer.is_same_peer(peer))
+        {
+            peers.push(WatchingPeer {
+                peer: peer.clone(),
+                subscriptions: HashSet::new(),
+            });
+        }
+        if self.shared.watching.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let shared = Arc::downgrade(&self.shared);
+        let interval = self.shared.config.poll_interval;
+        tokio::spawn(async move {
+            let mut snapshot = None;
+            loop {
+                let Some(shared) = Weak::upgrade(&shared) else {
+                    break;
+                };
+                let scanning = shared.clone();
+                let Ok(current) = tokio::task::spawn_blocking(move || scanning.scan()).await else {
+                    shared.watching.store(false, Ordering::Release);
+                    break;
+                };
+                let previous = snapshot.replace(current);
+                let current = snapshot.as_ref().expect("snapshot just taken");
+                let Some(changes) = changes(&shared, previous.as_ref(), current) else {
+                    break;
+                };
+                for (peer, uri) in changes {
+                    let result = match uri {
+                        Some(uri) => {
+                            peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                                .await
+                        }
+                        None => peer.notify_resource_list_changed().await,
+                    };
+                    if let Err(error) = result {
+                        tracing::debug!(%error, "failed to notify a resource change");
+                    }
+                }
+                drop(shared);
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
+
+/// The notifications to send for the changes since `previous`, as the uri of an updated
+/// resource or `None` for a changed list, and `None` once no client is left.
+fn changes(
+    shared: &Shared,
+    previous: Option<&Snapshot>,
+    current: &Snapshot,
+) -> Option<Vec<(Peer<RoleServer>, Option<String>)>> {
+    let mut peers = shared.peers.lock().expect("watching peers lock");
+    peers.retain(|watching| !watching.peer.is_transport_closed());
+    if peers.is_empty() {
+        shared.watching.store(false, Ordering::Release);
+        return None;
+    }
+    let Some(previous) = previous else {
+        return Some(Vec::new());
+    };
+    let list_changed = !previous.keys().eq(current.keys());
+    let updated: Vec<String> = previous
+        .iter()
+        .filter(|(path, stamp)| current.get(*path) != Some(*stamp))
+        .filter_map(|(path, _)| file_uri(path).ok())
+        .collect();
+    let mut changes = Vec::new();
+    for watching in peers.iter() {
+        if list_changed {
+            changes.push((watching.peer.clone(), None));
+        }
+        for uri in &updated {
+            if watching.subscriptions.contains(uri) {
+                changes.push((watching.peer.clone(), Some(uri.clone())));
+            }
+        }
+    }
+    Some(changes)
+}
+
+fn is_allowed(path: &Path, client_roots: Option<&[PathBuf]>) -> bool {
+    client_roots.is_none_or(|roots| roots.iter().any(|root| path.starts_with(root)))
+}
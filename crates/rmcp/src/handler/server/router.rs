@@ -10,12 +10,15 @@ use crate::{
     service::NotificationContext,
 };
 
+pub mod completion;
 pub mod prompt;
 pub mod tool;
 
 pub struct Router<S> {
     pub tool_router: tool::ToolRouter<S>,
     pub prompt_router: prompt::PromptRouter<S>,
+    /// Completes the arguments the prompt router has no completer for
+    pub completion_router: completion::CompletionRouter<S>,
     pub service: Arc<S>,
 }
 
@@ -27,6 +30,7 @@ where
         Self {
             tool_router: tool::ToolRouter::new(),
             prompt_router: prompt::PromptRouter::new(),
+            completion_router: completion::CompletionRouter::new(),
             service: Arc::new(service),
         }
     }
@@ -60,6 +64,19 @@ where
         }
         self
     }
+
+    pub fn with_completions(mut self, router: completion::CompletionRouter<S>) -> Self {
+        self.completion_router.merge(router);
+        self
+    }
+
+    /// Share a state with the tools and the prompts, which extract it with
+    /// [`State`](crate::handler::server::tool::State)
+    pub fn with_state<T: Clone + Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.tool_router.insert_state(state.clone());
+        self.prompt_router.insert_state(state);
+        self
+    }
 }
 
 impl<S> Service<RoleServer> for Router<S>
@@ -121,6 +138,35 @@ where
                         .await
                 }
             }
+            ClientRequest::CompleteRequest(request)
+                if request
+                    .params
+                    .r#ref
+                    .as_prompt_name()
+                    .and_then(|prompt| {
+                        self.prompt_router
+                            .completer(prompt, &request.params.argument.name)
+                    })
+                    .is_some() =>
+            {
+                let result = self
+                    .prompt_router
+                    .complete(self.service.as_ref(), request.params)
+                    .await?;
+                Ok(ServerResult::CompleteResult(result))
+            }
+            ClientRequest::CompleteRequest(request)
+                if self
+                    .completion_router
+                    .completer(&request.params.r#ref, &request.params.argument.name)
+                    .is_some() =>
+            {
+                let result = self
+                    .completion_router
+                    .complete(self.service.as_ref(), request.params)
+                    .await?;
+                Ok(ServerResult::CompleteResult(result))
+            }
             ClientRequest::ListPromptsRequest(_) => {
                 let prompts = self.prompt_router.list_all();
                 Ok(ServerResult::ListPromptsResult(ListPromptsResult {
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use super::prompt::PromptRouter;
+use crate::{
+    handler::server::completion::{Completer, CompletionQuery},
+    model::{CompleteRequestParam, CompleteResult, Reference},
+};
+
+/// Completers of the arguments of prompts and the variables of resource templates.
+///
+/// Resource templates are referred to by their URI template.
+#[derive(Debug)]
+pub struct CompletionRouter<S> {
+    prompts: HashMap<(String, String), Completer<S>>,
+    resource_templates: HashMap<(String, String), Completer<S>>,
+}
+
+impl<S> Default for CompletionRouter<S> {
+    fn default() -> Self {
+        Self {
+            prompts: HashMap::new(),
+            resource_templates: HashMap::new(),
+        }
+    }
+}
+
+impl<S> Clone for CompletionRouter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            prompts: self.prompts.clone(),
+            resource_templates: self.resource_templates.clone(),
+        }
+    }
+}
+
+impl<S> CompletionRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Complete an argument of a prompt.
+    pub fn with_prompt_argument(
+        mut self,
+        prompt: impl Into<String>,
+        argument: impl Into<String>,
+        completer: impl Into<Completer<S>>,
+    ) -> Self {
+        self.prompts
+            .insert((prompt.into(), argument.into()), completer.into());
+        self
+    }
+
+    /// Complete a variable of a resource template.
+    pub fn with_resource_variable(
+        mut self,
+        uri_template: impl Into<String>,
+        variable: impl Into<String>,
+        completer: impl Into<Completer<S>>,
+    ) -> Self {
+        self.resource_templates
+            .insert((uri_template.into(), variable.into()), completer.into());
+        self
+    }
+
+    /// Add the completers of the prompts of `router`.
+    pub fn with_prompt_router(mut self, router: &PromptRouter<S>) -> Self {
+        for route in router.map.values() {
+            for (argument, completer) in route.completions() {
+                self.prompts.insert(
+                    (route.name().to_string(), argument.clone()),
+                    completer.clone(),
+                );
+            }
+        }
+        self
+    }
+
+    pub fn merge(&mut self, other: CompletionRouter<S>) {
+        self.prompts.extend(other.prompts);
+        self.resource_templates.extend(other.resource_templates);
+    }
+
+    /// The completer of an argument of the referred prompt or resource template.
+    pub fn completer(&self, reference: &Reference, argument: &str) -> Option<&Completer<S>> {
+        let key = |name: &str| (name.to_string(), argument.to_string());
+        match reference {
+            Reference::Prompt(prompt) => self.prompts.get(&key(&prompt.name)),
+            Reference::Resource(resource) => self.resource_templates.get(&key(&resource.uri)),
+        }
+    }
+
+    /// Answer `completion/complete`, with no values for the arguments without completer.
+    pub async fn complete(
+        &self,
+        server: &S,
+        request: CompleteRequestParam,
+    ) -> Result<CompleteResult, crate::ErrorData> {
+        let Some(completer) = self.completer(&request.r#ref, &request.argument.name) else {
+            return Ok(CompleteResult::default());
+        };
+        let completion = completer
+            .complete(
+                server,
+                CompletionQuery::new(request.argument, request.context),
+            )
+            .await?;
+        Ok(CompleteResult { completion })
+    }
+}
+
+impl<S> std::ops::Add<CompletionRouter<S>> for CompletionRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Output = Self;
+
+    fn add(mut self, other: CompletionRouter<S>) -> Self::Output {
+        self.merge(other);
+        self
+    }
+}
@@ -1,16 +1,22 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use futures::future::BoxFuture;
 
 use crate::{
-    handler::server::prompt::{DynGetPromptHandler, GetPromptHandler, PromptContext},
-    model::{GetPromptResult, Prompt},
+    handler::server::{
+        common::StateMap,
+        completion::{Completer, CompletionQuery},
+        prompt::{DynGetPromptHandler, GetPromptHandler, PromptContext},
+    },
+    model::{CompleteRequestParam, CompleteResult, GetPromptResult, Prompt},
 };
 
 pub struct PromptRoute<S> {
     #[allow(clippy::type_complexity)]
     pub get: Arc<DynGetPromptHandler<S>>,
     pub attr: crate::model::Prompt,
+    /// The completers of the arguments, by argument name
+    completions: HashMap<String, Completer<S>>,
 }
 
 impl<S> std::fmt::Debug for PromptRoute<S> {
@@ -19,6 +25,7 @@ impl<S> std::fmt::Debug for PromptRoute<S> {
             .field("name", &self.attr.name)
             .field("description", &self.attr.description)
             .field("arguments", &self.attr.arguments)
+            .field("completions", &self.completions)
             .finish()
     }
 }
@@ -28,6 +35,7 @@ impl<S> Clone for PromptRoute<S> {
         Self {
             get: self.get.clone(),
             attr: self.attr.clone(),
+            completions: self.completions.clone(),
         }
     }
 }
@@ -43,6 +51,7 @@ impl<S: Send + Sync + 'static> PromptRoute<S> {
                 handler.handle(context)
             }),
             attr: attr.into(),
+            completions: HashMap::new(),
         }
     }
 
@@ -58,12 +67,44 @@ impl<S: Send + Sync + 'static> PromptRoute<S> {
         Self {
             get: Arc::new(handler),
             attr: attr.into(),
+            completions: HashMap::new(),
         }
     }
 
+    /// Complete the values of `argument` with `completer`.
+    pub fn with_completion(
+        mut self,
+        argument: impl Into<String>,
+        completer: impl Into<Completer<S>>,
+    ) -> Self {
+        self.completions.insert(argument.into(), completer.into());
+        self
+    }
+
+    pub fn with_completions(
+        mut self,
+        completions: impl IntoIterator<Item = (impl Into<String>, Completer<S>)>,
+    ) -> Self {
+        self.completions.extend(
+            completions
+                .into_iter()
+                .map(|(argument, completer)| (argument.into(), completer)),
+        );
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.attr.name
     }
+
+    /// The completer of an argument.
+    pub fn completer(&self, argument: &str) -> Option<&Completer<S>> {
+        self.completions.get(argument)
+    }
+
+    pub(crate) fn completions(&self) -> impl Iterator<Item = (&String, &Completer<S>)> {
+        self.completions.iter()
+    }
 }
 
 pub trait IntoPromptRoute<S, A> {
@@ -108,12 +149,15 @@ where
 pub struct PromptRouter<S> {
     #[allow(clippy::type_complexity)]
     pub map: std::collections::HashMap<Cow<'static, str>, PromptRoute<S>>,
+
+    states: StateMap,
 }
 
 impl<S> Default for PromptRouter<S> {
     fn default() -> Self {
         Self {
             map: std::collections::HashMap::new(),
+            states: StateMap::new(),
         }
     }
 }
@@ -122,6 +166,7 @@ impl<S> Clone for PromptRouter<S> {
     fn clone(&self) -> Self {
         Self {
             map: self.map.clone(),
+            states: self.states.clone(),
         }
     }
 }
@@ -142,6 +187,7 @@ where
     pub fn new() -> Self {
         Self {
             map: std::collections::HashMap::new(),
+            states: StateMap::new(),
         }
     }
 
@@ -157,7 +203,18 @@ where
         self.map.insert(item.attr.name.clone().into(), item);
     }
 
+    /// Share a state with the prompts, which extract it with [`State`](crate::handler::server::prompt::State)
+    pub fn with_state<T: Clone + Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.insert_state(state);
+        self
+    }
+
+    pub fn insert_state<T: Clone + Send + Sync + 'static>(&mut self, state: T) {
+        self.states.insert(state);
+    }
+
     pub fn merge(&mut self, other: PromptRouter<S>) {
+        self.states.extend(other.states);
         for item in other.map.into_values() {
             self.add_route(item);
         }
@@ -173,7 +230,7 @@ where
 
     pub async fn get_prompt(
         &self,
-        context: PromptContext<'_, S>,
+        mut context: PromptContext<'_, S>,
     ) -> Result<GetPromptResult, crate::ErrorData> {
         let item = self.map.get(context.name.as_str()).ok_or_else(|| {
             crate::ErrorData::invalid_params(
@@ -183,9 +240,45 @@ where
                 })),
             )
         })?;
+        // added to the states of the routers this request went through
+        if !self.states.is_empty() {
+            context
+                .context
+                .extensions
+                .get_or_insert_default::<StateMap>()
+                .push_shared(&self.states);
+        }
         (item.get)(context).await
     }
 
+    /// The completer of an argument of a prompt.
+    pub fn completer(&self, prompt: &str, argument: &str) -> Option<&Completer<S>> {
+        self.map.get(prompt)?.completer(argument)
+    }
+
+    /// Answer `completion/complete` for the arguments of the prompts, with no values for
+    /// the other references and arguments.
+    pub async fn complete(
+        &self,
+        server: &S,
+        request: CompleteRequestParam,
+    ) -> Result<CompleteResult, crate::ErrorData> {
+        let completer = request
+            .r#ref
+            .as_prompt_name()
+            .and_then(|prompt| self.completer(prompt, &request.argument.name));
+        let Some(completer) = completer else {
+            return Ok(CompleteResult::default());
+        };
+        let completion = completer
+            .complete(
+                server,
+                CompletionQuery::new(request.argument, request.context),
+            )
+            .await?;
+        Ok(CompleteResult { completion })
+    }
+
     pub fn list_all(&self) -> Vec<crate::model::Prompt> {
         self.map.values().map(|item| item.attr.clone()).collect()
     }
@@ -4,8 +4,10 @@ use futures::{FutureExt, future::BoxFuture};
 use schemars::JsonSchema;
 
 use crate::{
-    handler::server::tool::{
-        CallToolHandler, DynCallToolHandler, ToolCallContext, schema_for_type,
+    handler::server::{
+        progress::Progress,
+        tool::{CallToolHandler, DynCallToolHandler, StateMap, ToolCallContext, schema_for_type},
+        tool_output::ToolOutputSink,
     },
     model::{CallToolResult, Tool, ToolAnnotations},
 };
@@ -195,6 +197,8 @@ pub struct ToolRouter<S> {
 
     // Track which tools were registered dynamically
     dynamic_tool_names: HashSet<String>,
+
+    states: StateMap,
 }
 
 impl<S> Default for ToolRouter<S> {
@@ -203,6 +207,7 @@ impl<S> Default for ToolRouter<S> {
             map: std::collections::HashMap::new(),
             transparent_when_not_found: false,
             dynamic_tool_names: HashSet::new(),
+            states: StateMap::new(),
         }
     }
 }
@@ -212,6 +217,7 @@ impl<S> Clone for ToolRouter<S> {
             map: self.map.clone(),
             transparent_when_not_found: self.transparent_when_not_found,
             dynamic_tool_names: self.dynamic_tool_names.clone(),
+            states: self.states.clone(),
         }
     }
 }
@@ -234,6 +240,7 @@ where
             map: std::collections::HashMap::new(),
             transparent_when_not_found: false,
             dynamic_tool_names: HashSet::new(),
+            states: StateMap::new(),
         }
     }
     pub fn with_route<R, A>(mut self, route: R) -> Self
@@ -248,7 +255,18 @@ where
         self.map.insert(item.attr.name.clone(), item);
     }
 
+    /// Share a state with the tools, which extract it with [`State`](crate::handler::server::tool::State)
+    pub fn with_state<T: Clone + Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.insert_state(state);
+        self
+    }
+
+    pub fn insert_state<T: Clone + Send + Sync + 'static>(&mut self, state: T) {
+        self.states.insert(state);
+    }
+
     pub fn merge(&mut self, other: ToolRouter<S>) {
+        self.states.extend(other.states);
         for item in other.map.into_values() {
             self.add_route(item);
         }
@@ -262,16 +280,63 @@ where
     }
     pub async fn call(
         &self,
-        context: ToolCallContext<'_, S>,
+        mut context: ToolCallContext<'_, S>,
     ) -> Result<CallToolResult, crate::ErrorData> {
         let item = self
             .map
             .get(context.name())
             .ok_or_else(|| crate::ErrorData::invalid_params("tool not found", None))?;
+        // added to the states of the routers this request went through
+        if !self.states.is_empty() {
+            context
+                .request_context
+                .extensions
+                .get_or_insert_default::<StateMap>()
+                .push_shared(&self.states);
+        }
+        // shared with the routers this request goes through, which keep the chunks
+        let output = if context
+            .request_context
+            .extensions
+            .get::<ToolOutputSink>()
+            .is_none()
+        {
+            let output = ToolOutputSink::from_request_context(&context.request_context);
+            context.request_context.extensions.insert(output.clone());
+            Some(output)
+        } else {
+            None
+        };
 
-        let result = (item.call)(context).await?;
+        // finished once the tool returns, so the last held back update precedes the result
+        let progress = if context
+            .request_context
+            .extensions
+            .get::<Progress>()
+            .is_none()
+        {
+            let progress = Progress::from_request_context(&context.request_context);
+            context.request_context.extensions.insert(progress.clone());
+            Some(progress)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "metrics")]
+        let (name, start) = (context.name().to_owned(), std::time::Instant::now());
+        let mut result = (item.call)(context).await;
+        if let Some(progress) = progress {
+            if let Err(error) = progress.finish().await {
+                tracing::warn!(%error, "fail to flush progress notification");
+            }
+        }
+        if let Some(output) = output {
+            result = output.complete(result).await;
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_tool_call(name, &result, start);
 
-        Ok(result)
+        result
     }
 
     pub fn list_all(&self) -> Vec<crate::model::Tool> {
@@ -0,0 +1,723 @@
+//! Run tool calls in the background as tasks
+//!
+//! A tool call can take longer than a client is willing to wait for a response. When
+//! the `_meta` of a `tools/call` request carries a [`TaskMetadata`](crate::model::TaskMetadata), a server wrapped by
+//! [`TaskManager::wrap`] starts the call in the background and answers right away with
+//! a [`CreateTaskResult`]. The client then follows the task with:
+//!
+//! - `tasks/get`, which returns the current [`Task`],
+//! - `tasks/result`, which waits for the task to finish and returns the result of the call,
+//! - `tasks/cancel`, which cancels the call,
+//! - `tasks/list`, which lists the known tasks.
+//!
+//! A task belongs to the connection which started it, the `tasks/*` requests of other
+//! connections do not see it. The client also receives a `notifications/tasks/status`
+//! notification when a task finishes. Tasks and their results are kept in a [`TaskStore`], [`InMemoryTaskStore`]
+//! by default, and removed once their time to live has elapsed.
+//!
+//! ```rust,no_run
+//! # use rmcp::{ServerHandler, ServiceExt, handler::server::task::TaskManager};
+//! # #[derive(Clone)]
+//! # struct Server;
+//! # impl ServerHandler for Server {}
+//! # async fn run() -> anyhow::Result<()> {
+//! let tasks = TaskManager::default();
+//! let service = tasks
+//!     .wrap(Server)
+//!     .serve((tokio::io::stdin(), tokio::io::stdout()))
+//!     .await?;
+//! service.waiting().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    Peer, RoleServer, Service,
+    error::ErrorData as McpError,
+    model::{
+        CallToolRequest, CallToolResult, ClientNotification, ClientRequest, CreateTaskResult,
+        GetTaskResult, ListTasksResult, PaginatedRequestParam, ServerInfo, ServerNotification,
+        ServerRequest, ServerResult, Task, TaskStatus,
+    },
+    service::{NotificationContext, RequestContext},
+};
+
+/// A task as kept by a [`TaskStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredTask {
+    pub task: Task,
+    /// The connection which started the task, the only one allowed to see it.
+    pub owner: String,
+    /// The outcome of the tool call, once the task is completed or failed.
+    pub result: Option<Result<CallToolResult, McpError>>,
+    /// When the task may be removed from the store.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredTask {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Where the tasks and their results are kept.
+///
+/// Running tasks are always tracked in memory by the [`TaskManager`]; a store keeping
+/// its tasks somewhere else keeps the results of finished tasks out of the memory of
+/// the server.
+pub trait TaskStore: Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn insert(&self, task: StoredTask) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    fn get(
+        &self,
+        task_id: &str,
+    ) -> impl Future<Output = Result<Option<StoredTask>, Self::Error>> + Send;
+    /// Replace a task, does nothing if it has been removed.
+    fn update(&self, task: StoredTask) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    /// All the tasks of `owner`, from the oldest to the latest.
+    fn list(&self, owner: &str) -> impl Future<Output = Result<Vec<Task>, Self::Error>> + Send;
+    /// Remove the tasks expired at `now`, returning their ids.
+    fn remove_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Vec<String>, Self::Error>> + Send;
+}
+
+/// Keep the tasks in memory, they are lost when the process exits.
+#[derive(Debug, Default)]
+pub struct InMemoryTaskStore {
+    tasks: Mutex<HashMap<String, StoredTask>>,
+}
+
+impl TaskStore for InMemoryTaskStore {
+    type Error = std::convert::Infallible;
+
+    async fn insert(&self, task: StoredTask) -> Result<(), Self::Error> {
+        self.tasks
+            .lock()
+            .expect("task store lock")
+            .insert(task.task.task_id.clone(), task);
+        Ok(())
+    }
+
+    async fn get(&self, task_id: &str) -> Result<Option<StoredTask>, Self::Error> {
+        Ok(self
+            .tasks
+            .lock()
+            .expect("task store lock")
+            .get(task_id)
+            .cloned())
+    }
+
+    async fn update(&self, task: StoredTask) -> Result<(), Self::Error> {
+        if let Some(stored) = self
+            .tasks
+            .lock()
+            .expect("task store lock")
+            .get_mut(&task.task.task_id)
+        {
+            *stored = task;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, owner: &str) -> Result<Vec<Task>, Self::Error> {
+        let mut tasks: Vec<Task> = self
+            .tasks
+            .lock()
+            .expect("task store lock")
+            .values()
+            .filter(|stored| stored.owner == owner)
+            .map(|stored| stored.task.clone())
+            .collect();
+        tasks.sort_by(|a, b| (a.created_at, &a.task_id).cmp(&(b.created_at, &b.task_id)));
+        Ok(tasks)
+    }
+
+    async fn remove_expired(&self, now: DateTime<Utc>) -> Result<Vec<String>, Self::Error> {
+        let mut removed = Vec::new();
+        self.tasks
+            .lock()
+            .expect("task store lock")
+            .retain(|task_id, stored| {
+                let expired = stored.is_expired(now);
+                if expired {
+                    removed.push(task_id.clone());
+                }
+                !expired
+            });
+        Ok(removed)
+    }
+}
+
+/// Options of the [`TaskManager`].
+#[derive(Debug, Clone)]
+pub struct TaskManagerConfig {
+    /// The time to live of tasks whose client did not ask for one, `None` keeps them forever.
+    pub default_ttl: Option<Duration>,
+    /// The longest time to live a client can ask for.
+    pub max_ttl: Option<Duration>,
+    /// The delay between two `tasks/get` requests suggested to clients.
+    pub poll_interval: Duration,
+    /// How often expired tasks are removed from the store.
+    pub cleanup_interval: Duration,
+    /// The number of tasks in a page of `tasks/list`.
+    pub page_size: usize,
+}
+
+impl Default for TaskManagerConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl: Some(Duration::from_secs(60 * 60)),
+            max_ttl: Some(Duration::from_secs(24 * 60 * 60)),
+            poll_interval: Duration::from_secs(1),
+            cleanup_interval: Duration::from_secs(60),
+            page_size: 50,
+        }
+    }
+}
+
+/// Start tool calls as tasks and answer the `tasks/*` requests, cheap to clone.
+///
+/// A manager can be shared by several servers, each connection only sees the tasks it
+/// started.
+#[derive(Debug)]
+pub struct TaskManager<T = InMemoryTaskStore> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for TaskManager<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new(InMemoryTaskStore::default(), TaskManagerConfig::default())
+    }
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    store: T,
+    config: TaskManagerConfig,
+    running: Mutex<HashMap<String, RunningTask>>,
+    cleanup_started: AtomicBool,
+}
+
+/// A task started by this manager which has not been saved with a terminal status yet.
+#[derive(Debug)]
+struct RunningTask {
+    ct: CancellationToken,
+    status: watch::Sender<TaskStatus>,
+    /// Set by the one of `finish` and `cancel` which moves the task to its terminal status,
+    /// the other gives up.
+    finishing: bool,
+}
+
+fn store_error(error: impl std::error::Error) -> McpError {
+    McpError::internal_error(format!("task store error: {error}"), None)
+}
+
+fn unknown_task(task_id: &str) -> McpError {
+    McpError::invalid_params(format!("unknown task: {task_id}"), None)
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+impl<T: TaskStore> TaskManager<T> {
+    pub fn new(store: T, config: TaskManagerConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                store,
+                config,
+                running: Default::default(),
+                cleanup_started: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn store(&self) -> &T {
+        &self.shared.store
+    }
+
+    pub fn config(&self) -> &TaskManagerConfig {
+        &self.shared.config
+    }
+
+    /// Wrap a server so that its tool calls can run as tasks.
+    pub fn wrap<S>(&self, service: S) -> TaskService<S, T> {
+        TaskService {
+            inner: Arc::new(service),
+            tasks: self.clone(),
+        }
+    }
+
+    /// Start `request` in the background on `service`, the task is tied to `context.peer`.
+    ///
+    /// The task metadata is removed from `context.meta`. Must be called within a tokio
+    /// runtime.
+    pub async fn start<S: Service<RoleServer>>(
+        &self,
+        service: Arc<S>,
+        request: CallToolRequest,
+        mut context: RequestContext<RoleServer>,
+    ) -> Result<Task, McpError> {
+        let config = &self.shared.config;
+        let requested_ttl = context
+            .meta
+            .remove_task()
+            .and_then(|task| task.ttl)
+            .map(Duration::from_millis);
+        let ttl = match (requested_ttl.or(config.default_ttl), config.max_ttl) {
+            (Some(ttl), Some(max_ttl)) => Some(ttl.min(max_ttl)),
+            (None, Some(max_ttl)) => Some(max_ttl),
+            (ttl, None) => ttl,
+        };
+        let created_at = Utc::now();
+        let task_id = uuid::Uuid::new_v4().to_string();
+        let task = Task {
+            task_id: task_id.clone(),
+            status: TaskStatus::Working,
+            status_message: None,
+            created_at,
+            ttl: ttl.map(millis),
+            poll_interval: Some(millis(config.poll_interval)),
+        };
+        self.shared
+            .store
+            .insert(StoredTask {
+                task: task.clone(),
+                owner: context.peer.connection_id().to_owned(),
+                result: None,
+                expires_at: ttl
+                    .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+                    .map(|ttl| created_at + ttl),
+            })
+            .await
+            .map_err(store_error)?;
+        let ct = CancellationToken::new();
+        self.shared
+            .running
+            .lock()
+            .expect("running tasks lock")
+            .insert(
+                task_id.clone(),
+                RunningTask {
+                    ct: ct.clone(),
+                    status: watch::channel(TaskStatus::Working).0,
+                    finishing: false,
+                },
+            );
+        self.start_cleanup();
+
+        context.ct = ct.clone();
+        let peer = context.peer.clone();
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let result = tokio::select! {
+                result = service.handle_request(ClientRequest::CallToolRequest(request), context) => result,
+                _ = ct.cancelled() => return,
+            };
+            let result = match result {
+                Ok(ServerResult::CallToolResult(result)) => Ok(result),
+                Ok(_) => Err(McpError::internal_error(
+                    "tool call returned an unexpected result",
+                    None,
+                )),
+                Err(error) => Err(error),
+            };
+            if let Err(error) = manager.finish(&task_id, result, &peer).await {
+                tracing::error!(%task_id, %error, "failed to store the task result");
+            }
+        });
+        Ok(task)
+    }
+
+    async fn finish(
+        &self,
+        task_id: &str,
+        result: Result<CallToolResult, McpError>,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), McpError> {
+        if self.claim(task_id).is_none() {
+            // cancelled or expired meanwhile
+            return Ok(());
+        }
+        let mut stored = match self.shared.store.get(task_id).await {
+            Ok(Some(stored)) => stored,
+            result => {
+                self.release(task_id);
+                return result.map(drop).map_err(store_error);
+            }
+        };
+        let (status, status_message) = match &result {
+            Ok(result) if result.is_error == Some(true) => (
+                TaskStatus::Failed,
+                Some("the tool returned an error".into()),
+            ),
+            Ok(_) => (TaskStatus::Completed, None),
+            Err(error) => (TaskStatus::Failed, Some(error.message.to_string())),
+        };
+        stored.task.status = status;
+        stored.task.status_message = status_message;
+        stored.result = Some(result);
+        self.transition(stored, peer).await
+    }
+
+    /// Claim the terminal transition of a running task, returning its cancellation token,
+    /// or `None` if the task is not running or another transition claimed it first.
+    ///
+    /// Only the claimer writes the terminal status, so a cancellation and the result of
+    /// the tool call can't overwrite each other.
+    fn claim(&self, task_id: &str) -> Option<CancellationToken> {
+        let mut running = self.shared.running.lock().expect("running tasks lock");
+        match running.get_mut(task_id) {
+            Some(task) if !task.finishing => {
+                task.finishing = true;
+                Some(task.ct.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Stop tracking a running task, returning the sender of its status.
+    fn release(&self, task_id: &str) -> Option<watch::Sender<TaskStatus>> {
+        self.shared
+            .running
+            .lock()
+            .expect("running tasks lock")
+            .remove(task_id)
+            .map(|task| task.status)
+    }
+
+    /// Save a claimed task which reached a terminal status and tell its waiters and client.
+    async fn transition(
+        &self,
+        stored: StoredTask,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), McpError> {
+        let task = stored.task.clone();
+        let updated = self.shared.store.update(stored).await.map_err(store_error);
+        // the waiters read the store once the status is sent
+        if let Some(status) = self.release(&task.task_id) {
+            status.send_replace(task.status);
+        }
+        updated?;
+        if let Err(error) = peer.notify_task_status(task).await {
+            tracing::debug!(%error, "failed to send the task status");
+        }
+        Ok(())
+    }
+
+    /// The task, if it has not expired and belongs to the connection of `peer`.
+    async fn get_stored(
+        &self,
+        task_id: &str,
+        peer: &Peer<RoleServer>,
+    ) -> Result<StoredTask, McpError> {
+        match self.shared.store.get(task_id).await.map_err(store_error)? {
+            Some(stored)
+                if !stored.is_expired(Utc::now()) && stored.owner == peer.connection_id() =>
+            {
+                Ok(stored)
+            }
+            _ => Err(unknown_task(task_id)),
+        }
+    }
+
+    /// Answer `tasks/get`.
+    pub async fn get(&self, task_id: &str, peer: &Peer<RoleServer>) -> Result<Task, McpError> {
+        self.get_stored(task_id, peer)
+            .await
+            .map(|stored| stored.task)
+    }
+
+    /// Answer `tasks/result`, waiting for the task to finish.
+    pub async fn result(
+        &self,
+        task_id: &str,
+        peer: &Peer<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let stored = self.get_stored(task_id, peer).await?;
+        let stored = if stored.task.status.is_terminal() {
+            stored
+        } else {
+            let status = self
+                .shared
+                .running
+                .lock()
+                .expect("running tasks lock")
+                .get(task_id)
+                .map(|running| running.status.subscribe());
+            match status {
+                Some(mut status) => {
+                    // the sender is dropped after the terminal status is sent
+                    let _ = status.wait_for(TaskStatus::is_terminal).await;
+                    self.get_stored(task_id, peer).await?
+                }
+                // finished since the task was read
+                None => match self.get_stored(task_id, peer).await? {
+                    stored if stored.task.status.is_terminal() => stored,
+                    _ => {
+                        return Err(McpError::internal_error(
+                            format!("task {task_id} is not running on this server"),
+                            None,
+                        ));
+                    }
+                },
+            }
+        };
+        match stored.result {
+            Some(result) => result,
+            None => Err(McpError::invalid_request(
+                format!("task {task_id} was cancelled"),
+                None,
+            )),
+        }
+    }
+
+    /// Answer `tasks/cancel`.
+    pub async fn cancel(&self, task_id: &str, peer: &Peer<RoleServer>) -> Result<Task, McpError> {
+        let already_finished =
+            || McpError::invalid_params(format!("task {task_id} has already finished"), None);
+        let mut stored = self.get_stored(task_id, peer).await?;
+        if stored.task.status.is_terminal() {
+            return Err(already_finished());
+        }
+        let Some(ct) = self.claim(task_id) else {
+            let running = self
+                .shared
+                .running
+                .lock()
+                .expect("running tasks lock")
+                .contains_key(task_id);
+            // the result is being saved, or was since the task was read
+            if running
+                || self
+                    .get_stored(task_id, peer)
+                    .await?
+                    .task
+                    .status
+                    .is_terminal()
+            {
+                return Err(already_finished());
+            }
+            return Err(McpError::internal_error(
+                format!("task {task_id} is not running on this server"),
+                None,
+            ));
+        };
+        ct.cancel();
+        stored.task.status = TaskStatus::Cancelled;
+        stored.task.status_message = Some("cancelled by the client".into());
+        let task = stored.task.clone();
+        self.transition(stored, peer).await?;
+        Ok(task)
+    }
+
+    /// Answer `tasks/list`, the cursor is the index of the first task of the page.
+    pub async fn list(
+        &self,
+        params: Option<PaginatedRequestParam>,
+        peer: &Peer<RoleServer>,
+    ) -> Result<ListTasksResult, McpError> {
+        let now = Utc::now();
+        let tasks = self
+            .shared
+            .store
+            .list(peer.connection_id())
+            .await
+            .map_err(store_error)?;
+        let tasks: Vec<Task> = tasks
+            .into_iter()
+            .filter(|task| {
+                task.ttl.is_none_or(|ttl| {
+                    task.created_at + chrono::Duration::milliseconds(ttl as i64) > now
+                })
+            })
+            .collect();
+        let start = match params.and_then(|params| params.cursor) {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .map_err(|_| McpError::invalid_params("invalid cursor", None))?,
+            None => 0,
+        };
+        let end = start
+            .saturating_add(self.shared.config.page_size.max(1))
+            .min(tasks.len());
+        Ok(ListTasksResult {
+            next_cursor: (end < tasks.len()).then(|| end.to_string()),
+            tasks: tasks.get(start..end).unwrap_or_default().to_vec(),
+        })
+    }
+
+    /// Remove the expired tasks, cancelling those still running.
+    pub async fn remove_expired(&self) -> Result<usize, McpError> {
+        let removed = self
+            .shared
+            .store
+            .remove_expired(Utc::now())
+            .await
+            .map_err(store_error)?;
+        let mut running = self.shared.running.lock().expect("running tasks lock");
+        for task_id in &removed {
+            if let Some(task) = running.remove(task_id) {
+                task.ct.cancel();
+            }
+        }
+        Ok(removed.len())
+    }
+
+    fn start_cleanup(&self) {
+        if self.shared.cleanup_started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let shared = Arc::downgrade(&self.shared);
+        let interval = self.shared.config.cleanup_interval;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(shared) = Weak::upgrade(&shared) else {
+                    break;
+                };
+                if let Err(error) = (TaskManager { shared }).remove_expired().await {
+                    tracing::error!(%error, "failed to remove the expired tasks");
+                }
+            }
+        });
+    }
+}
+
+/// A server wrapped by [`TaskManager::wrap`].
+///
+/// `tools/call` requests asking for a task and the `tasks/*` requests are answered
+/// by the wrapper, the tool call itself still runs on the inner service.
+#[derive(Debug)]
+pub struct TaskService<S, T = InMemoryTaskStore> {
+    inner: Arc<S>,
+    tasks: TaskManager<T>,
+}
+
+impl<S, T> Clone for TaskService<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            tasks: self.tasks.clone(),
+        }
+    }
+}
+
+impl<S, T> TaskService<S, T> {
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn tasks(&self) -> &TaskManager<T> {
+        &self.tasks
+    }
+}
+
+fn advertise_tasks(info: &mut ServerInfo) {
+    info.capabilities
+        .experimental
+        .get_or_insert_with(Default::default)
+        .entry("tasks".to_string())
+        .or_default();
+}
+
+impl<S: Service<RoleServer>, T: TaskStore> Service<RoleServer> for TaskService<S, T> {
+    async fn handle_request(
+        &self,
+        request: ClientRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ServerResult, McpError> {
+        match request {
+            ClientRequest::CallToolRequest(request) if context.meta.get_task().is_some() => {
+                let task = self
+                    .tasks
+                    .start(self.inner.clone(), request, context)
+                    .await?;
+                Ok(ServerResult::CreateTaskResult(CreateTaskResult { task }))
+            }
+            ClientRequest::GetTaskRequest(request) => {
+                let task = self
+                    .tasks
+                    .get(&request.params.task_id, &context.peer)
+                    .await?;
+                Ok(ServerResult::GetTaskResult(GetTaskResult { task }))
+            }
+            ClientRequest::GetTaskResultRequest(request) => {
+                let task_id = request.params.task_id;
+                tokio::select! {
+                    result = self.tasks.result(&task_id, &context.peer) => result.map(ServerResult::CallToolResult),
+                    _ = context.ct.cancelled() => Err(McpError::invalid_request("request cancelled", None)),
+                }
+            }
+            ClientRequest::CancelTaskRequest(request) => {
+                let task = self
+                    .tasks
+                    .cancel(&request.params.task_id, &context.peer)
+                    .await?;
+                Ok(ServerResult::GetTaskResult(GetTaskResult { task }))
+            }
+            ClientRequest::ListTasksRequest(request) => self
+                .tasks
+                .list(request.params, &context.peer)
+                .await
+                .map(ServerResult::ListTasksResult),
+            request => {
+                let mut result = self.inner.handle_request(request, context).await;
+                if let Ok(ServerResult::InitializeResult(info)) = &mut result {
+                    advertise_tasks(info);
+                }
+                result
+            }
+        }
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: ClientNotification,
+        context: NotificationContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.inner.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        let mut info = self.inner.get_info();
+        advertise_tasks(&mut info);
+        info
+    }
+
+    fn handle_outgoing_request(&self, request: &mut ServerRequest) -> Result<(), McpError> {
+        self.inner.handle_outgoing_request(request)
+    }
+
+    fn handle_outgoing_notification(
+        &self,
+        notification: &mut ServerNotification,
+    ) -> Result<(), McpError> {
+        self.inner.handle_outgoing_notification(notification)
+    }
+}
@@ -9,8 +9,14 @@ use serde::de::DeserializeOwned;
 
 use super::common::{AsRequestContext, FromContextPart};
 pub use super::{
-    common::{Extension, RequestId, cached_schema_for_type, schema_for_type},
+    cancellation::{CancelOutcome, Cancellation},
+    common::{
+        AsyncFromContextPart, Extension, RequestId, State, StateMap, cached_schema_for_type,
+        schema_for_type,
+    },
+    progress::Progress,
     router::tool::{ToolRoute, ToolRouter},
+    tool_output::ToolOutputSink,
 };
 use crate::{
     RoleServer,
@@ -76,11 +82,26 @@ impl<T: IntoContents> IntoCallToolResult for T {
     }
 }
 
-impl<T: IntoContents, E: IntoContents> IntoCallToolResult for Result<T, E> {
+/// An error returned by a tool
+///
+/// The error turns either into a protocol error, or into a tool result with `is_error`
+/// which the model can see. Every [`IntoContents`] is a tool result error. For an error
+/// enum, derive it with `#[derive(McpError)]` to choose per variant.
+pub trait IntoToolError {
+    fn into_tool_error(self) -> Result<CallToolResult, crate::ErrorData>;
+}
+
+impl<E: IntoContents> IntoToolError for E {
+    fn into_tool_error(self) -> Result<CallToolResult, crate::ErrorData> {
+        Ok(CallToolResult::error(self.into_contents()))
+    }
+}
+
+impl<T: IntoCallToolResult, E: IntoToolError> IntoCallToolResult for Result<T, E> {
     fn into_call_tool_result(self) -> Result<CallToolResult, crate::ErrorData> {
         match self {
-            Ok(value) => Ok(CallToolResult::success(value.into_contents())),
-            Err(error) => Ok(CallToolResult::error(error.into_contents())),
+            Ok(value) => value.into_call_tool_result(),
+            Err(error) => error.into_tool_error(),
         }
     }
 }
@@ -135,6 +156,12 @@ impl IntoCallToolResult for Result<CallToolResult, crate::ErrorData> {
     }
 }
 
+/// Trait for handling tool calls
+///
+/// The handlers are implemented for functions whose parameters are extractors. Since the
+/// extractors may be async, a function is only a handler when it is `Send + 'static`, as
+/// the routes built with [`ToolRoute`]
+/// already require.
 pub trait CallToolHandler<S, A> {
     fn call(
         self,
@@ -196,25 +223,27 @@ pub struct SyncAdapter<P, R>(PhantomData<fn(P) -> R>);
 pub struct AsyncMethodAdapter<P, R>(PhantomData<fn(P) -> R>);
 pub struct SyncMethodAdapter<P, R>(PhantomData<fn(P) -> R>);
 
+// The extractors may be async, so they run in the returned future together with the
+// handler, which must be `'static` like the routes storing it.
 macro_rules! impl_for {
-    ($($T: ident)*) => {
-        impl_for!([] [$($T)*]);
+    ($(($T: ident, $M: ident))*) => {
+        impl_for!([] [$(($T, $M))*]);
     };
     // finished
-    ([$($Tn: ident)*] []) => {
-        impl_for!(@impl $($Tn)*);
+    ([$(($Tn: ident, $Mn: ident))*] []) => {
+        impl_for!(@impl $(($Tn, $Mn))*);
     };
-    ([$($Tn: ident)*] [$Tn_1: ident $($Rest: ident)*]) => {
-        impl_for!(@impl $($Tn)*);
-        impl_for!([$($Tn)* $Tn_1] [$($Rest)*]);
+    ([$(($Tn: ident, $Mn: ident))*] [($Tn_1: ident, $Mn_1: ident) $($Rest: tt)*]) => {
+        impl_for!(@impl $(($Tn, $Mn))*);
+        impl_for!([$(($Tn, $Mn))* ($Tn_1, $Mn_1)] [$($Rest)*]);
     };
-    (@impl $($Tn: ident)*) => {
-        impl<$($Tn,)* S, F,  R> CallToolHandler<S, AsyncMethodAdapter<($($Tn,)*), R>> for F
+    (@impl $(($Tn: ident, $Mn: ident))*) => {
+        impl<$($Tn, $Mn,)* S, F,  R> CallToolHandler<S, AsyncMethodAdapter<($(($Tn, $Mn),)*), R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<ToolCallContext<'a, S>> ,
+                $Tn: for<'a> AsyncFromContextPart<ToolCallContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R>,
+            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R> + Send + 'static,
 
             // Need RTN support here(I guess), https://github.com/rust-lang/rust/pull/138424
             // Fut: Future<Output = R> + Send + 'a,
@@ -226,28 +255,23 @@ macro_rules! impl_for {
                 self,
                 mut context: ToolCallContext<'_, S>,
             ) -> BoxFuture<'_, Result<CallToolResult, crate::ErrorData>>{
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                let service = context.service;
-                let fut = self(service, $($Tn,)*);
                 async move {
-                    let result = fut.await;
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    let service = context.service;
+                    let result = self(service, $($Tn,)*).await;
                     result.into_call_tool_result()
                 }.boxed()
             }
         }
 
-        impl<$($Tn,)* S, F, Fut, R> CallToolHandler<S, AsyncAdapter<($($Tn,)*), Fut, R>> for F
+        impl<$($Tn, $Mn,)* S, F, Fut, R> CallToolHandler<S, AsyncAdapter<($(($Tn, $Mn),)*), Fut, R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<ToolCallContext<'a, S>> ,
+                $Tn: for<'a> AsyncFromContextPart<ToolCallContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce($($Tn,)*) -> Fut + Send + ,
+            F: FnOnce($($Tn,)*) -> Fut + Send + 'static,
             Fut: Future<Output = R> + Send + 'static,
             R: IntoCallToolResult + Send + 'static,
             S: Send + Sync,
@@ -255,72 +279,67 @@ macro_rules! impl_for {
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
-                mut context: ToolCallContext<S>,
-            ) -> BoxFuture<'static, Result<CallToolResult, crate::ErrorData>>{
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                let fut = self($($Tn,)*);
+                mut context: ToolCallContext<'_, S>,
+            ) -> BoxFuture<'_, Result<CallToolResult, crate::ErrorData>>{
                 async move {
-                    let result = fut.await;
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    let result = self($($Tn,)*).await;
                     result.into_call_tool_result()
                 }.boxed()
             }
         }
 
-        impl<$($Tn,)* S, F, R> CallToolHandler<S, SyncMethodAdapter<($($Tn,)*), R>> for F
+        impl<$($Tn, $Mn,)* S, F, R> CallToolHandler<S, SyncMethodAdapter<($(($Tn, $Mn),)*), R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<ToolCallContext<'a, S>> + ,
+                $Tn: for<'a> AsyncFromContextPart<ToolCallContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce(&S, $($Tn,)*) -> R + Send + ,
+            F: FnOnce(&S, $($Tn,)*) -> R + Send + 'static,
             R: IntoCallToolResult + Send + ,
             S: Send + Sync,
         {
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
-                mut context: ToolCallContext<S>,
-            ) -> BoxFuture<'static, Result<CallToolResult, crate::ErrorData>> {
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                std::future::ready(self(context.service, $($Tn,)*).into_call_tool_result()).boxed()
+                mut context: ToolCallContext<'_, S>,
+            ) -> BoxFuture<'_, Result<CallToolResult, crate::ErrorData>> {
+                async move {
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    self(context.service, $($Tn,)*).into_call_tool_result()
+                }.boxed()
             }
         }
 
-        impl<$($Tn,)* S, F, R> CallToolHandler<S, SyncAdapter<($($Tn,)*), R>> for F
+        impl<$($Tn, $Mn,)* S, F, R> CallToolHandler<S, SyncAdapter<($(($Tn, $Mn),)*), R>> for F
         where
             $(
-                $Tn: for<'a> FromContextPart<ToolCallContext<'a, S>> + ,
+                $Tn: for<'a> AsyncFromContextPart<ToolCallContext<'a, S>, $Mn> + Send,
             )*
-            F: FnOnce($($Tn,)*) -> R + Send + ,
+            F: FnOnce($($Tn,)*) -> R + Send + 'static,
             R: IntoCallToolResult + Send + ,
             S: Send + Sync,
         {
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
-                mut context: ToolCallContext<S>,
-            ) -> BoxFuture<'static, Result<CallToolResult, crate::ErrorData>>  {
-                $(
-                    let result = $Tn::from_context_part(&mut context);
-                    let $Tn = match result {
-                        Ok(value) => value,
-                        Err(e) => return std::future::ready(Err(e)).boxed(),
-                    };
-                )*
-                std::future::ready(self($($Tn,)*).into_call_tool_result()).boxed()
+                mut context: ToolCallContext<'_, S>,
+            ) -> BoxFuture<'_, Result<CallToolResult, crate::ErrorData>>  {
+                async move {
+                    $(
+                        let $Tn = $Tn::from_context_part_async(&mut context).await?;
+                    )*
+                    self($($Tn,)*).into_call_tool_result()
+                }.boxed()
             }
         }
     };
 }
-impl_for!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15);
+impl_for!((T0, M0)(T1, M1)(T2, M2)(T3, M3)(T4, M4)(T5, M5)(T6, M6)(
+    T7, M7
+)(T8, M8)(T9, M9)(T10, M10)(T11, M11)(T12, M12)(T13, M13)(
+    T14, M14
+)(T15, M15));
@@ -0,0 +1,150 @@
+//! Streaming the output of a tool while it runs
+//!
+//! [`ToolOutputSink`] is an extractor sending chunks of content before the tool returns.
+//! A chunk is sent in the `_meta` of a progress notification of the call, when the client
+//! asked for the output to be streamed in the `_meta` of the request. Otherwise the chunks
+//! are kept, and put before the content of the result once the tool returns, so the tool
+//! behaves the same for every client.
+//!
+//! On the client side, `Peer::call_tool_streaming` yields the chunks as they arrive and
+//! assembles the whole result.
+//!
+//! ```rust
+//! # use rmcp::{handler::server::{tool::ToolRouter, tool_output::ToolOutputSink}, tool, tool_router};
+//! # #[derive(Clone)]
+//! # struct Builder { tool_router: ToolRouter<Self> }
+//! # #[tool_router]
+//! # impl Builder {
+//! #[tool(description = "Build the project")]
+//! async fn build(&self, output: ToolOutputSink) -> String {
+//!     for step in ["fetch", "compile", "link"] {
+//!         // ... run the step ...
+//!         let _ = output.send(format!("{step} done\n")).await;
+//!     }
+//!     "build succeeded".to_string()
+//! }
+//! # }
+//! ```
+use std::sync::Arc;
+
+use super::common::{AsRequestContext, FromContextPart};
+use crate::{
+    Peer, RoleServer, ServiceError,
+    model::{
+        CallToolResult, Content, GetMeta, IntoContents, ProgressNotification,
+        ProgressNotificationParam, ProgressToken, ServerNotification,
+    },
+    service::RequestContext,
+};
+
+/// A sink for the output of the tool call being handled.
+///
+/// The chunks which are not streamed are put in the result by the
+/// [`ToolRouter`](super::router::tool::ToolRouter) calling the tool, a sink created
+/// outside of it drops them.
+///
+/// Cloning a `ToolOutputSink` yields another handle to the same sink. Every chunk is
+/// sent as a progress notification, so a tool should not report progress with a
+/// [`Progress`](super::progress::Progress) at the same time.
+#[derive(Debug, Clone)]
+pub struct ToolOutputSink {
+    inner: Arc<ToolOutputInner>,
+}
+
+#[derive(Debug)]
+struct ToolOutputInner {
+    peer: Peer<RoleServer>,
+    /// `None` when the chunks are kept for the result
+    progress_token: Option<ProgressToken>,
+    // held while sending, so the chunks are sent in order
+    state: tokio::sync::Mutex<ToolOutputState>,
+}
+
+#[derive(Debug, Default)]
+struct ToolOutputState {
+    sent: u64,
+    kept: Vec<Content>,
+}
+
+impl ToolOutputSink {
+    /// Create a sink streaming the chunks with the given progress token.
+    ///
+    /// If `progress_token` is `None`, the chunks are kept for the result.
+    pub fn new(progress_token: Option<ProgressToken>, peer: Peer<RoleServer>) -> Self {
+        Self {
+            inner: Arc::new(ToolOutputInner {
+                peer,
+                progress_token,
+                state: Default::default(),
+            }),
+        }
+    }
+
+    /// Create a sink for the request, streaming only if the request asks for it.
+    pub fn from_request_context(context: &RequestContext<RoleServer>) -> Self {
+        let progress_token = context
+            .meta
+            .get_stream_tool_output()
+            .then(|| context.meta.get_progress_token())
+            .flatten();
+        Self::new(progress_token, context.peer.clone())
+    }
+
+    /// Whether the chunks are sent to the client while the tool runs.
+    pub fn is_streaming(&self) -> bool {
+        self.inner.progress_token.is_some()
+    }
+
+    /// Send a chunk of output.
+    pub async fn send(&self, content: impl IntoContents) -> Result<(), ServiceError> {
+        let content = content.into_contents();
+        if content.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.inner.state.lock().await;
+        let Some(progress_token) = &self.inner.progress_token else {
+            state.kept.extend(content);
+            return Ok(());
+        };
+        state.sent += 1;
+        let mut notification = ServerNotification::ProgressNotification(ProgressNotification::new(
+            ProgressNotificationParam {
+                progress_token: progress_token.clone(),
+                progress: state.sent as f64,
+                total: None,
+                message: None,
+            },
+        ));
+        notification.get_meta_mut().set_tool_output(content);
+        self.inner.peer.send_notification(notification).await
+    }
+
+    /// Put the kept chunks before the content of the result.
+    pub(crate) async fn complete(
+        &self,
+        result: Result<CallToolResult, crate::ErrorData>,
+    ) -> Result<CallToolResult, crate::ErrorData> {
+        let mut kept = std::mem::take(&mut self.inner.state.lock().await.kept);
+        result.map(|mut result| {
+            if !kept.is_empty() {
+                kept.append(&mut result.content);
+                result.content = kept;
+            }
+            result
+        })
+    }
+}
+
+impl<C> FromContextPart<C> for ToolOutputSink
+where
+    C: AsRequestContext,
+{
+    fn from_context_part(context: &mut C) -> Result<Self, crate::ErrorData> {
+        let context = context.as_request_context();
+        Ok(context
+            .extensions
+            .get::<ToolOutputSink>()
+            .cloned()
+            .unwrap_or_else(|| Self::from_request_context(context)))
+    }
+}
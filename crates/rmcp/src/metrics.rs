@@ -0,0 +1,226 @@
+//! Counters and histograms of the services and transports
+//!
+//! Metrics are sent to the [`MetricsRecorder`] installed with [`set_recorder`], nothing
+//! is recorded until then. The `metrics-crate` feature provides a recorder forwarding to
+//! the [`metrics`](https://docs.rs/metrics) crate, and the `metrics-opentelemetry` feature
+//! one forwarding to an OpenTelemetry [`Meter`](::opentelemetry::metrics::Meter).
+//!
+//! ```rust,ignore
+//! rmcp::metrics::set_recorder(rmcp::metrics::MetricsCrateRecorder)?;
+//! ```
+//!
+//! # Semantic convention
+//!
+//! | Name | Kind | Unit | Labels | Recorded |
+//! |------|------|------|--------|----------|
+//! | `mcp.requests` | counter | | `mcp.role`, `mcp.method`, `mcp.outcome` | for each request received from the peer |
+//! | `mcp.request.duration` | histogram | s | `mcp.role`, `mcp.method`, `mcp.outcome` | for each request received from the peer |
+//! | `mcp.request.errors` | counter | | `mcp.role`, `mcp.method`, `mcp.error.code` | for each request answered with an error |
+//! | `mcp.tool.calls` | counter | | `mcp.tool`, `mcp.outcome` | for each call through a [`ToolRouter`](crate::handler::server::tool::ToolRouter) |
+//! | `mcp.tool.duration` | histogram | s | `mcp.tool`, `mcp.outcome` | for each call through a [`ToolRouter`](crate::handler::server::tool::ToolRouter) |
+//! | `mcp.sessions.active` | gauge | | | by the local session manager of the streamable HTTP server |
+//! | `mcp.sse.reconnects` | counter | | `mcp.outcome` | for each attempt of an SSE client to reconnect |
+//! | `mcp.transport.messages` | counter | | `mcp.transport`, `mcp.direction` | for each message through a worker transport |
+//! | `mcp.transport.bytes` | counter | By | `mcp.transport`, `mcp.direction` | for each line read or written by an async read/write transport, and each message body or event received or sent by the streamable HTTP server; the other HTTP and SSE transports do not record it |
+//!
+//! The labels take the following values:
+//! - `mcp.role`: `server` or `client`, the role of the service handling the request
+//! - `mcp.method`: the JSON-RPC method, such as `tools/call`
+//! - `mcp.outcome`: `ok`, `error`, `cancelled` or `rejected` for requests, `ok`, `error`
+//!   or `tool_error` for tool calls, and `ok` or `error` for reconnections
+//! - `mcp.error.code`: the JSON-RPC error code
+//! - `mcp.tool`: the name of the tool
+//! - `mcp.transport`: the type of the transport, such as `async_rw` or `LocalSessionWorker`
+//! - `mcp.direction`: `in` for received messages, `out` for sent ones
+use std::{borrow::Cow, sync::OnceLock};
+
+#[cfg(feature = "metrics-crate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics-crate")))]
+mod metrics_crate;
+#[cfg(feature = "metrics-crate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics-crate")))]
+pub use metrics_crate::MetricsCrateRecorder;
+#[cfg(feature = "metrics-opentelemetry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics-opentelemetry")))]
+mod opentelemetry;
+#[cfg(feature = "metrics-opentelemetry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics-opentelemetry")))]
+pub use opentelemetry::OpenTelemetryRecorder;
+
+/// The names of the metrics.
+pub mod names {
+    pub const REQUESTS: &str = "mcp.requests";
+    pub const REQUEST_DURATION: &str = "mcp.request.duration";
+    pub const REQUEST_ERRORS: &str = "mcp.request.errors";
+    pub const TOOL_CALLS: &str = "mcp.tool.calls";
+    pub const TOOL_DURATION: &str = "mcp.tool.duration";
+    pub const SESSIONS_ACTIVE: &str = "mcp.sessions.active";
+    pub const SSE_RECONNECTS: &str = "mcp.sse.reconnects";
+    pub const TRANSPORT_MESSAGES: &str = "mcp.transport.messages";
+    pub const TRANSPORT_BYTES: &str = "mcp.transport.bytes";
+}
+
+/// The keys of the labels.
+pub mod labels {
+    pub const ROLE: &str = "mcp.role";
+    pub const METHOD: &str = "mcp.method";
+    pub const OUTCOME: &str = "mcp.outcome";
+    pub const ERROR_CODE: &str = "mcp.error.code";
+    pub const TOOL: &str = "mcp.tool";
+    pub const TRANSPORT: &str = "mcp.transport";
+    pub const DIRECTION: &str = "mcp.direction";
+}
+
+/// A label of a metric.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub key: &'static str,
+    pub value: Cow<'static, str>,
+}
+
+impl Label {
+    pub fn new(key: &'static str, value: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            key,
+            value: value.into(),
+        }
+    }
+}
+
+/// Receives the metrics recorded by the SDK.
+pub trait MetricsRecorder: Send + Sync + 'static {
+    fn increment_counter(&self, name: &'static str, value: u64, labels: &[Label]);
+    /// Add `delta` to a gauge, which may go up and down.
+    fn add_to_gauge(&self, name: &'static str, delta: f64, labels: &[Label]);
+    fn record_histogram(&self, name: &'static str, value: f64, labels: &[Label]);
+}
+
+static RECORDER: OnceLock<Box<dyn MetricsRecorder>> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+#[error("a metrics recorder is already set")]
+pub struct SetRecorderError;
+
+/// Install the recorder of the process, it can only be done once.
+pub fn set_recorder(recorder: impl MetricsRecorder) -> Result<(), SetRecorderError> {
+    RECORDER
+        .set(Box::new(recorder))
+        .map_err(|_| SetRecorderError)
+}
+
+/// The recorder installed with [`set_recorder`].
+pub fn recorder() -> Option<&'static dyn MetricsRecorder> {
+    RECORDER.get().map(Box::as_ref)
+}
+
+#[cfg(any(
+    feature = "client-side-sse",
+    feature = "transport-worker",
+    feature = "transport-async-rw"
+))]
+pub(crate) fn increment_counter(name: &'static str, value: u64, labels: &[Label]) {
+    if let Some(recorder) = recorder() {
+        recorder.increment_counter(name, value, labels);
+    }
+}
+
+#[cfg(feature = "transport-streamable-http-server-session")]
+pub(crate) fn add_to_gauge(name: &'static str, delta: f64, labels: &[Label]) {
+    if let Some(recorder) = recorder() {
+        recorder.add_to_gauge(name, delta, labels);
+    }
+}
+
+/// Record a request received from the peer and how it ended.
+pub(crate) fn record_request(
+    is_client: bool,
+    method: &'static str,
+    outcome: &'static str,
+    start: std::time::Instant,
+    error_code: Option<i32>,
+) {
+    let Some(recorder) = recorder() else {
+        return;
+    };
+    let role = Label::new(labels::ROLE, if is_client { "client" } else { "server" });
+    let method = Label::new(labels::METHOD, method);
+    if let Some(code) = error_code {
+        recorder.increment_counter(
+            names::REQUEST_ERRORS,
+            1,
+            &[
+                role.clone(),
+                method.clone(),
+                Label::new(labels::ERROR_CODE, code.to_string()),
+            ],
+        );
+    }
+    let labels = [role, method, Label::new(labels::OUTCOME, outcome)];
+    recorder.increment_counter(names::REQUESTS, 1, &labels);
+    recorder.record_histogram(
+        names::REQUEST_DURATION,
+        start.elapsed().as_secs_f64(),
+        &labels,
+    );
+}
+
+/// Record a call to a tool of a router.
+#[cfg(feature = "server")]
+pub(crate) fn record_tool_call(
+    tool: String,
+    result: &Result<crate::model::CallToolResult, crate::ErrorData>,
+    start: std::time::Instant,
+) {
+    let Some(recorder) = recorder() else {
+        return;
+    };
+    let outcome = match result {
+        Ok(result) if result.is_error == Some(true) => "tool_error",
+        Ok(_) => "ok",
+        Err(_) => "error",
+    };
+    let labels = [
+        Label::new(labels::TOOL, tool),
+        Label::new(labels::OUTCOME, outcome),
+    ];
+    recorder.increment_counter(names::TOOL_CALLS, 1, &labels);
+    recorder.record_histogram(names::TOOL_DURATION, start.elapsed().as_secs_f64(), &labels);
+}
+
+/// Record a message received or sent by a transport.
+#[cfg(feature = "transport-worker")]
+pub(crate) fn record_transport_message(transport: &'static str, direction: &'static str) {
+    increment_counter(
+        names::TRANSPORT_MESSAGES,
+        1,
+        &[
+            Label::new(labels::TRANSPORT, transport),
+            Label::new(labels::DIRECTION, direction),
+        ],
+    );
+}
+
+/// Record bytes received or sent by a transport.
+#[cfg(feature = "transport-async-rw")]
+pub(crate) fn record_transport_bytes(
+    transport: &'static str,
+    direction: &'static str,
+    bytes: usize,
+) {
+    increment_counter(
+        names::TRANSPORT_BYTES,
+        bytes as u64,
+        &[
+            Label::new(labels::TRANSPORT, transport),
+            Label::new(labels::DIRECTION, direction),
+        ],
+    );
+}
+
+/// The last segment of the path of a type, without its generics.
+#[cfg(feature = "transport-worker")]
+pub(crate) fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
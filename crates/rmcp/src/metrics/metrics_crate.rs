@@ -0,0 +1,26 @@
+use super::{Label, MetricsRecorder};
+
+/// Forward the metrics to the recorder of the [`metrics`] crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsCrateRecorder;
+
+fn to_labels(labels: &[Label]) -> Vec<::metrics::Label> {
+    labels
+        .iter()
+        .map(|label| ::metrics::Label::new(label.key, label.value.to_string()))
+        .collect()
+}
+
+impl MetricsRecorder for MetricsCrateRecorder {
+    fn increment_counter(&self, name: &'static str, value: u64, labels: &[Label]) {
+        ::metrics::counter!(name, to_labels(labels)).increment(value);
+    }
+
+    fn add_to_gauge(&self, name: &'static str, delta: f64, labels: &[Label]) {
+        ::metrics::gauge!(name, to_labels(labels)).increment(delta);
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64, labels: &[Label]) {
+        ::metrics::histogram!(name, to_labels(labels)).record(value);
+    }
+}
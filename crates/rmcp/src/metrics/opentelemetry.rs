@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use ::opentelemetry::{
+    KeyValue,
+    metrics::{Counter, Histogram, Meter, UpDownCounter},
+};
+
+use super::{Label, MetricsRecorder, names};
+
+/// Forward the metrics to the instruments of an OpenTelemetry [`Meter`].
+///
+/// The instruments are created the first time a metric is recorded.
+#[derive(Debug, Clone)]
+pub struct OpenTelemetryRecorder {
+    meter: Meter,
+    instruments: Arc<Mutex<Instruments>>,
+}
+
+#[derive(Debug, Default)]
+struct Instruments {
+    counters: HashMap<&'static str, Counter<u64>>,
+    gauges: HashMap<&'static str, UpDownCounter<f64>>,
+    histograms: HashMap<&'static str, Histogram<f64>>,
+}
+
+impl OpenTelemetryRecorder {
+    pub fn new(meter: Meter) -> Self {
+        Self {
+            meter,
+            instruments: Default::default(),
+        }
+    }
+
+    /// Use the meter `rmcp` of the global meter provider.
+    pub fn global() -> Self {
+        Self::new(::opentelemetry::global::meter("rmcp"))
+    }
+
+    fn instrument<I: Clone>(
+        &self,
+        name: &'static str,
+        select: impl Fn(&mut Instruments) -> &mut HashMap<&'static str, I>,
+        create: impl FnOnce(&Meter) -> I,
+    ) -> I {
+        let mut instruments = self
+            .instruments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        select(&mut instruments)
+            .entry(name)
+            .or_insert_with(|| create(&self.meter))
+            .clone()
+    }
+}
+
+fn to_attributes(labels: &[Label]) -> Vec<KeyValue> {
+    labels
+        .iter()
+        .map(|label| KeyValue::new(label.key, label.value.to_string()))
+        .collect()
+}
+
+fn unit(name: &str) -> Option<&'static str> {
+    match name {
+        names::REQUEST_DURATION | names::TOOL_DURATION => Some("s"),
+        names::TRANSPORT_BYTES => Some("By"),
+        _ => None,
+    }
+}
+
+impl MetricsRecorder for OpenTelemetryRecorder {
+    fn increment_counter(&self, name: &'static str, value: u64, labels: &[Label]) {
+        let counter = self.instrument(
+            name,
+            |instruments| &mut instruments.counters,
+            |meter| {
+                let builder = meter.u64_counter(name);
+                match unit(name) {
+                    Some(unit) => builder.with_unit(unit).build(),
+                    None => builder.build(),
+                }
+            },
+        );
+        counter.add(value, &to_attributes(labels));
+    }
+
+    fn add_to_gauge(&self, name: &'static str, delta: f64, labels: &[Label]) {
+        let gauge = self.instrument(
+            name,
+            |instruments| &mut instruments.gauges,
+            |meter| meter.f64_up_down_counter(name).build(),
+        );
+        gauge.add(delta, &to_attributes(labels));
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64, labels: &[Label]) {
+        let histogram = self.instrument(
+            name,
+            |instruments| &mut instruments.histograms,
+            |meter| {
+                let builder = meter.f64_histogram(name);
+                match unit(name) {
+                    Some(unit) => builder.with_unit(unit).build(),
+                    None => builder.build(),
+                }
+            },
+        );
+        histogram.record(value, &to_attributes(labels));
+    }
+}
@@ -1,23 +1,32 @@
 use std::{borrow::Cow, sync::Arc};
 mod annotated;
+mod blob;
 mod capabilities;
 mod content;
+mod downgrade;
 mod extension;
 mod meta;
 mod prompt;
 mod resource;
 mod serde_impl;
+mod task;
 mod tool;
+mod tool_output;
+mod trace;
 pub use annotated::*;
+pub use blob::*;
 pub use capabilities::*;
 pub use content::*;
+pub use downgrade::*;
 pub use extension::*;
 pub use meta::*;
 pub use prompt::*;
 pub use resource::*;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
+pub use task::*;
 pub use tool::*;
+pub use trace::*;
 
 /// A JSON object type alias for convenient handling of JSON data.
 ///
@@ -129,7 +138,7 @@ const_string!(JsonRpcVersion2_0 = "2.0");
 ///
 /// This ensures compatibility between clients and servers by specifying
 /// which version of the Model Context Protocol is being used.
-#[derive(Debug, Clone, Eq, PartialEq, Hash, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ProtocolVersion(Cow<'static, str>);
 
@@ -149,8 +158,46 @@ impl ProtocolVersion {
     pub const V_2025_06_18: Self = Self(Cow::Borrowed("2025-06-18"));
     pub const V_2025_03_26: Self = Self(Cow::Borrowed("2025-03-26"));
     pub const V_2024_11_05: Self = Self(Cow::Borrowed("2024-11-05"));
-    //  Keep LATEST at 2025-03-26 until full 2025-06-18 compliance and automated testing are in place.
-    pub const LATEST: Self = Self::V_2025_03_26;
+    pub const LATEST: Self = Self::V_2025_06_18;
+    /// The versions this crate can negotiate, from the oldest to the latest.
+    pub const KNOWN: &[Self] = &[Self::V_2024_11_05, Self::V_2025_03_26, Self::V_2025_06_18];
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_known(&self) -> bool {
+        Self::KNOWN.contains(self)
+    }
+
+    /// Pick the version to use when a peer requests `requested` and we support up to `supported`.
+    ///
+    /// Returns `None` if `requested` is unknown.
+    pub fn negotiate(requested: &Self, supported: &Self) -> Option<Self> {
+        if !requested.is_known() {
+            return None;
+        }
+        match requested.partial_cmp(supported) {
+            Some(std::cmp::Ordering::Greater) => Some(supported.clone()),
+            Some(_) => Some(requested.clone()),
+            // we support a custom version, keep it
+            None => Some(supported.clone()),
+        }
+    }
+
+    fn known_index(&self) -> Option<usize> {
+        Self::KNOWN.iter().position(|version| version == self)
+    }
+}
+
+/// Distinct versions are only ordered when both are [known](ProtocolVersion::KNOWN).
+impl PartialOrd for ProtocolVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        if self == other {
+            return Some(std::cmp::Ordering::Equal);
+        }
+        Some(self.known_index()?.cmp(&other.known_index()?))
+    }
 }
 
 impl Serialize for ProtocolVersion {
@@ -959,7 +1006,7 @@ pub type ToolListChangedNotification = NotificationNoParam<ToolListChangedNotifi
 // =============================================================================
 
 /// Logging levels supported by the MCP protocol
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy)]
 #[serde(rename_all = "lowercase")] //match spec
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum LoggingLevel {
@@ -1242,6 +1289,18 @@ impl CompletionInfo {
         })
     }
 
+    /// Create CompletionInfo with the first [`Self::MAX_VALUES`] values, telling how many
+    /// there are in total
+    pub fn truncated(mut values: Vec<String>) -> Self {
+        let total = values.len();
+        values.truncate(Self::MAX_VALUES);
+        Self {
+            values,
+            total: Some(u32::try_from(total).unwrap_or(u32::MAX)),
+            has_more: Some(total > Self::MAX_VALUES),
+        }
+    }
+
     /// Check if this completion response indicates more results are available
     pub fn has_more_results(&self) -> bool {
         self.has_more.unwrap_or(false)
@@ -1730,7 +1789,11 @@ ts_union!(
     | SubscribeRequest
     | UnsubscribeRequest
     | CallToolRequest
-    | ListToolsRequest;
+    | ListToolsRequest
+    | GetTaskRequest
+    | GetTaskResultRequest
+    | CancelTaskRequest
+    | ListTasksRequest;
 );
 
 impl ClientRequest {
@@ -1749,6 +1812,10 @@ impl ClientRequest {
             ClientRequest::UnsubscribeRequest(r) => r.method.as_str(),
             ClientRequest::CallToolRequest(r) => r.method.as_str(),
             ClientRequest::ListToolsRequest(r) => r.method.as_str(),
+            ClientRequest::GetTaskRequest(r) => r.method.as_str(),
+            ClientRequest::GetTaskResultRequest(r) => r.method.as_str(),
+            ClientRequest::CancelTaskRequest(r) => r.method.as_str(),
+            ClientRequest::ListTasksRequest(r) => r.method.as_str(),
         }
     }
 }
@@ -1789,7 +1856,8 @@ ts_union!(
     | ResourceUpdatedNotification
     | ResourceListChangedNotification
     | ToolListChangedNotification
-    | PromptListChangedNotification;
+    | PromptListChangedNotification
+    | TaskStatusNotification;
 );
 
 ts_union!(
@@ -1804,6 +1872,9 @@ ts_union!(
     | CallToolResult
     | ListToolsResult
     | CreateElicitationResult
+    | CreateTaskResult
+    | GetTaskResult
+    | ListTasksResult
     | EmptyResult
     ;
 );
@@ -1838,6 +1909,28 @@ impl TryInto<CancelledNotification> for ClientNotification {
     }
 }
 
+impl TryInto<ProgressNotification> for ServerNotification {
+    type Error = ServerNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ServerNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl TryInto<ProgressNotification> for ClientNotification {
+    type Error = ClientNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ClientNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
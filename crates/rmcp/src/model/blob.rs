@@ -0,0 +1,546 @@
+//! Binary content encoded in base64
+//!
+//! Images, audio and blob resources carry their data as base64 strings. The constructors
+//! here encode bytes, read files and detect their MIME type, and the `decode` accessors
+//! turn the strings back into bytes when asked. A [`BlobLimit`] bounds the size of the
+//! binary content a service sends.
+use std::path::Path;
+
+#[cfg(feature = "base64")]
+use base64::{DecodeError, Engine, prelude::BASE64_STANDARD};
+
+use super::*;
+
+/// The MIME type of binary data without a better guess
+pub const OCTET_STREAM_MIME_TYPE: &str = "application/octet-stream";
+
+// checked in order, the first matching signature wins
+const MAGIC_NUMBERS: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"ID3", "audio/mpeg"),
+    (b"\xff\xfb", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"\x00asm", "application/wasm"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+];
+
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("wav", "audio/wav"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("m4a", "audio/mp4"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("pdf", "application/pdf"),
+    ("wasm", "application/wasm"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("toml", "application/toml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+];
+
+/// Guess the MIME type of some data, from its first bytes, then from the extension of
+/// its path.
+///
+/// Falls back to [`OCTET_STREAM_MIME_TYPE`].
+pub fn guess_mime_type(data: &[u8], path: Option<&Path>) -> &'static str {
+    let riff = |format: &[u8]| data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == format;
+    if riff(b"WEBP") {
+        return "image/webp";
+    }
+    if riff(b"WAVE") {
+        return "audio/wav";
+    }
+    if let Some((_, mime_type)) = MAGIC_NUMBERS
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+    {
+        return mime_type;
+    }
+    path.and_then(guess_mime_type_from_path)
+        .unwrap_or(OCTET_STREAM_MIME_TYPE)
+}
+
+/// Guess the MIME type of a file from its extension.
+pub fn guess_mime_type_from_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+#[cfg(feature = "base64")]
+impl RawImageContent {
+    /// Encode an image.
+    pub fn from_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        Self {
+            data: BASE64_STANDARD.encode(data),
+            mime_type: mime_type.into(),
+            meta: None,
+        }
+    }
+
+    /// Decode the image.
+    pub fn decode(&self) -> Result<Vec<u8>, DecodeError> {
+        BASE64_STANDARD.decode(&self.data)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl RawAudioContent {
+    /// Encode an audio clip.
+    pub fn from_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        Self {
+            data: BASE64_STANDARD.encode(data),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// Decode the audio clip.
+    pub fn decode(&self) -> Result<Vec<u8>, DecodeError> {
+        BASE64_STANDARD.decode(&self.data)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl ResourceContents {
+    /// Encode binary contents, guessing their MIME type from the data and the uri.
+    pub fn blob(data: impl AsRef<[u8]>, uri: impl Into<String>) -> Self {
+        let uri = uri.into();
+        let data = data.as_ref();
+        Self::BlobResourceContents {
+            mime_type: Some(guess_mime_type(data, Some(Path::new(&uri))).to_string()),
+            blob: BASE64_STANDARD.encode(data),
+            uri,
+            meta: None,
+        }
+    }
+
+    /// Read a file as a blob, with its `file://` uri.
+    ///
+    /// The whole file is read in memory, blocking the thread.
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let mime_type = guess_mime_type(&data, Some(path));
+        Ok(Self::BlobResourceContents {
+            uri: file_uri(path)?,
+            mime_type: Some(mime_type.to_string()),
+            blob: BASE64_STANDARD.encode(data),
+            meta: None,
+        })
+    }
+
+    /// The bytes of the contents, decoding a blob and taking the UTF-8 of a text.
+    pub fn decode(&self) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            Self::TextResourceContents { text, .. } => Ok(text.as_bytes().to_vec()),
+            Self::BlobResourceContents { blob, .. } => BASE64_STANDARD.decode(blob),
+        }
+    }
+}
+
+#[cfg(feature = "base64")]
+impl RawContent {
+    pub fn image_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        RawContent::Image(RawImageContent::from_bytes(data, mime_type))
+    }
+
+    pub fn audio_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        RawContent::Audio(RawAudioContent::from_bytes(data, mime_type))
+    }
+
+    /// Read a file, as an image or audio content if it is one, else as an embedded blob
+    /// resource.
+    ///
+    /// The whole file is read in memory, blocking the thread.
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let mime_type = guess_mime_type(&data, Some(path));
+        Ok(if mime_type.starts_with("image/") {
+            RawContent::image_bytes(data, mime_type)
+        } else if mime_type.starts_with("audio/") {
+            RawContent::audio_bytes(data, mime_type)
+        } else {
+            RawContent::resource(ResourceContents::BlobResourceContents {
+                uri: file_uri(path)?,
+                mime_type: Some(mime_type.to_string()),
+                blob: BASE64_STANDARD.encode(data),
+                meta: None,
+            })
+        })
+    }
+}
+
+#[cfg(feature = "base64")]
+impl Content {
+    pub fn image_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        RawContent::image_bytes(data, mime_type).no_annotation()
+    }
+
+    pub fn audio_bytes(data: impl AsRef<[u8]>, mime_type: impl Into<String>) -> Self {
+        RawContent::audio_bytes(data, mime_type).no_annotation()
+    }
+
+    /// Embed binary contents, guessing their MIME type from the data and the uri.
+    pub fn blob(data: impl AsRef<[u8]>, uri: impl Into<String>) -> Self {
+        RawContent::resource(ResourceContents::blob(data, uri)).no_annotation()
+    }
+
+    /// See [`RawContent::from_path`].
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        RawContent::from_path(path).map(|content| content.no_annotation())
+    }
+}
+
+/// What a [`BlobLimit`] does with a blob larger than its maximum size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlobOverflow {
+    /// Fail the request.
+    #[default]
+    Reject,
+    /// Split a blob resource in several contents with the same uri, each within the limit.
+    Chunk,
+    /// Replace an embedded blob resource with a link to it, which the peer reads
+    /// separately.
+    Link,
+}
+
+/// Limit the size of the binary data a service sends in its results.
+///
+/// Set with [`ServeConfig::with_blob_limit`](crate::service::ServeConfig::with_blob_limit).
+/// The images, audio clips and blob resources are measured once decoded. A blob over the
+/// limit is handled as [`BlobOverflow`] says, the images, audio clips and what can't be
+/// chunked or linked fail the request with an internal error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobLimit {
+    /// The maximum size of a blob, in bytes
+    pub max_size: usize,
+    pub overflow: BlobOverflow,
+}
+
+/// The size of base64 data once decoded
+fn decoded_len(data: &str) -> usize {
+    let padding = data.bytes().rev().take_while(|byte| *byte == b'=').count();
+    (data.len() * 3 / 4).saturating_sub(padding)
+}
+
+impl BlobLimit {
+    /// Reject the blobs larger than `max_size` bytes.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            overflow: BlobOverflow::default(),
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: BlobOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    fn too_large(&self, what: &str, size: usize) -> ErrorData {
+        ErrorData::internal_error(
+            format!(
+                "{what} of {size} bytes exceeds the limit of {} bytes",
+                self.max_size
+            ),
+            Some(serde_json::json!({ "size": size, "maxSize": self.max_size })),
+        )
+    }
+
+    fn check(&self, what: &str, data: &str) -> Result<(), ErrorData> {
+        let size = decoded_len(data);
+        if size > self.max_size {
+            return Err(self.too_large(what, size));
+        }
+        Ok(())
+    }
+
+    /// The blob resource split in chunks within the limit, `None` if it is within the
+    /// limit already
+    fn chunk(
+        &self,
+        resource: &ResourceContents,
+    ) -> Result<Option<Vec<ResourceContents>>, ErrorData> {
+        let ResourceContents::BlobResourceContents {
+            uri,
+            mime_type,
+            blob,
+            meta,
+        } = resource
+        else {
+            return Ok(None);
+        };
+        let size = decoded_len(blob);
+        if size <= self.max_size {
+            return Ok(None);
+        }
+        // cut between groups of 4 characters, which decode to 3 bytes each
+        let chunk_len = self.max_size / 3 * 4;
+        if self.overflow != BlobOverflow::Chunk || chunk_len == 0 || !blob.is_ascii() {
+            return Err(self.too_large(&format!("blob {uri}"), size));
+        }
+        Ok(Some(
+            blob.as_bytes()
+                .chunks(chunk_len)
+                .map(|chunk| ResourceContents::BlobResourceContents {
+                    uri: uri.clone(),
+                    mime_type: mime_type.clone(),
+                    blob: String::from_utf8_lossy(chunk).into_owned(),
+                    meta: meta.clone(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// A link to the blob resource when it is over the limit and may be linked
+    fn link(&self, resource: &ResourceContents) -> Option<RawResource> {
+        let ResourceContents::BlobResourceContents {
+            uri,
+            mime_type,
+            blob,
+            ..
+        } = resource
+        else {
+            return None;
+        };
+        let size = decoded_len(blob);
+        if self.overflow != BlobOverflow::Link || size <= self.max_size {
+            return None;
+        }
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        let mut link = RawResource::new(uri.clone(), name);
+        link.mime_type = mime_type.clone();
+        link.size = u32::try_from(size).ok();
+        Some(link)
+    }
+
+    /// The embedded resource within the limit, chunked or linked
+    fn limit_embedded<T>(
+        &self,
+        embedded: &RawEmbeddedResource,
+        annotations: &Option<Annotations>,
+        from_link: impl Fn(Annotated<RawResource>) -> T,
+        from_embedded: impl Fn(Annotated<RawEmbeddedResource>) -> T,
+    ) -> Result<Option<Vec<T>>, ErrorData> {
+        if let Some(link) = self.link(&embedded.resource) {
+            return Ok(Some(vec![from_link(Annotated {
+                raw: link,
+                annotations: annotations.clone(),
+            })]));
+        }
+        Ok(self.chunk(&embedded.resource)?.map(|chunks| {
+            chunks
+                .into_iter()
+                .map(|resource| {
+                    from_embedded(Annotated {
+                        raw: RawEmbeddedResource {
+                            meta: embedded.meta.clone(),
+                            resource,
+                        },
+                        annotations: annotations.clone(),
+                    })
+                })
+                .collect()
+        }))
+    }
+
+    /// Limit the contents of a tool result.
+    pub fn limit_contents(&self, contents: &mut Vec<Content>) -> Result<(), ErrorData> {
+        let mut limited = Vec::with_capacity(contents.len());
+        for content in std::mem::take(contents) {
+            match &content.raw {
+                RawContent::Image(image) => self.check("image", &image.data)?,
+                RawContent::Audio(audio) => self.check("audio", &audio.data)?,
+                RawContent::Resource(embedded) => {
+                    if let Some(replaced) = self.limit_embedded(
+                        embedded,
+                        &content.annotations,
+                        |link| link.map(RawContent::ResourceLink),
+                        |embedded| embedded.map(RawContent::Resource),
+                    )? {
+                        limited.extend(replaced);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            limited.push(content);
+        }
+        *contents = limited;
+        Ok(())
+    }
+
+    /// Limit the contents of a read resource, which can only be chunked.
+    pub fn limit_resource_contents(
+        &self,
+        contents: &mut Vec<ResourceContents>,
+    ) -> Result<(), ErrorData> {
+        let mut limited = Vec::with_capacity(contents.len());
+        for resource in std::mem::take(contents) {
+            match self.chunk(&resource)? {
+                Some(chunks) => limited.extend(chunks),
+                None => limited.push(resource),
+            }
+        }
+        *contents = limited;
+        Ok(())
+    }
+
+    /// Limit the messages of a prompt.
+    pub fn limit_messages(&self, messages: &mut Vec<PromptMessage>) -> Result<(), ErrorData> {
+        let mut limited = Vec::with_capacity(messages.len());
+        for message in std::mem::take(messages) {
+            match &message.content {
+                PromptMessageContent::Image { image } => self.check("image", &image.data)?,
+                PromptMessageContent::Resource { resource } => {
+                    if let Some(replaced) = self.limit_embedded(
+                        resource,
+                        &resource.annotations,
+                        |link| PromptMessage {
+                            role: message.role.clone(),
+                            content: PromptMessageContent::ResourceLink { link },
+                        },
+                        |resource| PromptMessage {
+                            role: message.role.clone(),
+                            content: PromptMessageContent::Resource { resource },
+                        },
+                    )? {
+                        limited.extend(replaced);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            limited.push(message);
+        }
+        *messages = limited;
+        Ok(())
+    }
+}
+
+impl<T: AnnotateAble> Annotated<T> {
+    fn map<U: AnnotateAble>(self, f: impl FnOnce(T) -> U) -> Annotated<U> {
+        Annotated {
+            raw: f(self.raw),
+            annotations: self.annotations,
+        }
+    }
+}
+
+/// A result whose binary content a [`BlobLimit`] bounds before it is sent.
+pub trait LimitBlobs {
+    fn limit_blobs(&mut self, limit: &BlobLimit) -> Result<(), ErrorData>;
+}
+
+impl LimitBlobs for ServerResult {
+    fn limit_blobs(&mut self, limit: &BlobLimit) -> Result<(), ErrorData> {
+        match self {
+            ServerResult::CallToolResult(result) => limit.limit_contents(&mut result.content),
+            ServerResult::ReadResourceResult(result) => {
+                limit.limit_resource_contents(&mut result.contents)
+            }
+            ServerResult::GetPromptResult(result) => limit.limit_messages(&mut result.messages),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The message of a sampling result is a single content, so it is only checked.
+impl LimitBlobs for ClientResult {
+    fn limit_blobs(&mut self, limit: &BlobLimit) -> Result<(), ErrorData> {
+        let ClientResult::CreateMessageResult(result) = self else {
+            return Ok(());
+        };
+        match &result.message.content.raw {
+            RawContent::Image(image) => limit.check("image", &image.data),
+            RawContent::Audio(audio) => limit.check("audio", &audio.data),
+            RawContent::Resource(embedded) => match &embedded.resource {
+                ResourceContents::BlobResourceContents { uri, blob, .. } => {
+                    limit.check(&format!("blob {uri}"), blob)
+                }
+                ResourceContents::TextResourceContents { .. } => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_mime_type() {
+        assert_eq!(guess_mime_type(b"\x89PNG\r\n\x1a\n....", None), "image/png");
+        assert_eq!(guess_mime_type(b"RIFF\0\0\0\0WAVEfmt ", None), "audio/wav");
+        // the data wins over the extension
+        assert_eq!(
+            guess_mime_type(b"%PDF-1.7", Some(Path::new("report.txt"))),
+            "application/pdf"
+        );
+        assert_eq!(
+            guess_mime_type(b"a,b\n1,2\n", Some(Path::new("data.CSV"))),
+            "text/csv"
+        );
+        assert_eq!(guess_mime_type(b"\0\x01", None), OCTET_STREAM_MIME_TYPE);
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_blob_round_trip() {
+        let image = RawImageContent::from_bytes(b"\x89PNG\r\n\x1a\n", "image/png");
+        assert_eq!(image.data, "iVBORw0KGgo=");
+        assert_eq!(image.decode().unwrap(), b"\x89PNG\r\n\x1a\n");
+
+        let blob = ResourceContents::blob(bytes_of(300), "mem://data.bin");
+        let ResourceContents::BlobResourceContents { mime_type, .. } = &blob else {
+            panic!("not a blob");
+        };
+        assert_eq!(mime_type.as_deref(), Some(OCTET_STREAM_MIME_TYPE));
+        assert_eq!(blob.decode().unwrap(), bytes_of(300));
+    }
+
+    #[test]
+    fn test_decoded_len() {
+        assert_eq!(decoded_len(""), 0);
+        assert_eq!(decoded_len("YQ=="), 1);
+        assert_eq!(decoded_len("YWI="), 2);
+        assert_eq!(decoded_len("YWJj"), 3);
+        assert_eq!(decoded_len("YWJjZA=="), 4);
+    }
+
+    #[cfg(feature = "base64")]
+    fn bytes_of(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+}
@@ -0,0 +1,254 @@
+//! Adapting messages to the negotiated protocol version
+//!
+//! Messages are built against [`ProtocolVersion::LATEST`]. Before a message is sent
+//! to a peer which negotiated an older version, the fields this version does not
+//! define are stripped, or rewritten into something the peer understands. A request
+//! the version does not define at all fails without being sent.
+use super::*;
+
+/// Strip or rewrite what an older protocol version does not define.
+///
+/// Versions unknown to this crate are left untouched.
+pub trait Downgrade {
+    fn downgrade(&mut self, version: &ProtocolVersion);
+
+    /// Whether `version` defines this message at all.
+    ///
+    /// A request which is not defined is rejected instead of being sent.
+    fn is_defined_in(&self, _version: &ProtocolVersion) -> bool {
+        true
+    }
+}
+
+fn is_before(version: &ProtocolVersion, introduced: &ProtocolVersion) -> bool {
+    version < introduced
+}
+
+macro_rules! no_downgrade {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Downgrade for $t {
+                fn downgrade(&mut self, _version: &ProtocolVersion) {}
+            }
+        )*
+    };
+}
+
+no_downgrade!(ClientResult);
+
+impl<T: Downgrade> Downgrade for Vec<T> {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        self.iter_mut().for_each(|item| item.downgrade(version));
+    }
+}
+
+impl<Req, Resp, Not> Downgrade for JsonRpcMessage<Req, Resp, Not>
+where
+    Req: Downgrade,
+    Resp: Downgrade,
+    Not: Downgrade,
+{
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        match self {
+            JsonRpcMessage::Request(request) => request.request.downgrade(version),
+            JsonRpcMessage::Response(response) => response.result.downgrade(version),
+            JsonRpcMessage::Notification(notification) => {
+                notification.notification.downgrade(version)
+            }
+            JsonRpcMessage::Error(_) => {}
+        }
+    }
+}
+
+impl Downgrade for Implementation {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.title = None;
+            self.icons = None;
+            self.website_url = None;
+        }
+    }
+}
+
+impl Downgrade for InitializeRequestParam {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        self.client_info.downgrade(version);
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.capabilities.elicitation = None;
+        }
+    }
+}
+
+impl Downgrade for InitializeResult {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        self.server_info.downgrade(version);
+        if is_before(version, &ProtocolVersion::V_2025_03_26) {
+            self.capabilities.completions = None;
+        }
+    }
+}
+
+impl Downgrade for Tool {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.title = None;
+            self.output_schema = None;
+            self.icons = None;
+        }
+        if is_before(version, &ProtocolVersion::V_2025_03_26) {
+            self.annotations = None;
+        }
+    }
+}
+
+impl Downgrade for Prompt {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.title = None;
+            self.icons = None;
+            for argument in self.arguments.iter_mut().flatten() {
+                argument.title = None;
+            }
+        }
+    }
+}
+
+impl Downgrade for Resource {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.raw.title = None;
+            self.raw.icons = None;
+        }
+    }
+}
+
+impl Downgrade for ResourceTemplate {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_06_18) {
+            self.raw.title = None;
+        }
+    }
+}
+
+/// Resource links are sent as a text content carrying the uri, and audio as a text
+/// content naming its type.
+impl Downgrade for Content {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        match &self.raw {
+            RawContent::ResourceLink(link)
+                if is_before(version, &ProtocolVersion::V_2025_06_18) =>
+            {
+                self.raw = RawContent::text(link.uri.clone());
+            }
+            RawContent::Audio(audio) if is_before(version, &ProtocolVersion::V_2025_03_26) => {
+                self.raw = RawContent::text(format!("[{} audio omitted]", audio.mime_type));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Downgrade for PromptMessage {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if !is_before(version, &ProtocolVersion::V_2025_06_18) {
+            return;
+        }
+        if let PromptMessageContent::ResourceLink { link } = &self.content {
+            self.content = PromptMessageContent::text(link.uri.clone());
+        }
+    }
+}
+
+/// Structured content is moved into a text content, unless the tool already
+/// returned content for older clients.
+impl Downgrade for CallToolResult {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        self.content.downgrade(version);
+        if !is_before(version, &ProtocolVersion::V_2025_06_18) {
+            return;
+        }
+        if let Some(structured_content) = self.structured_content.take() {
+            if self.content.is_empty() {
+                self.content
+                    .push(Content::text(structured_content.to_string()));
+            }
+        }
+    }
+}
+
+impl Downgrade for ServerResult {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        match self {
+            ServerResult::InitializeResult(result) => result.downgrade(version),
+            ServerResult::GetPromptResult(result) => result.messages.downgrade(version),
+            ServerResult::ListPromptsResult(result) => result.prompts.downgrade(version),
+            ServerResult::ListResourcesResult(result) => result.resources.downgrade(version),
+            ServerResult::ListResourceTemplatesResult(result) => {
+                result.resource_templates.downgrade(version)
+            }
+            ServerResult::CallToolResult(result) => result.downgrade(version),
+            ServerResult::ListToolsResult(result) => result.tools.downgrade(version),
+            ServerResult::CompleteResult(_)
+            | ServerResult::ReadResourceResult(_)
+            | ServerResult::CreateElicitationResult(_)
+            | ServerResult::CreateTaskResult(_)
+            | ServerResult::GetTaskResult(_)
+            | ServerResult::ListTasksResult(_)
+            | ServerResult::EmptyResult(_) => {}
+        }
+    }
+}
+
+impl Downgrade for ClientRequest {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        match self {
+            ClientRequest::InitializeRequest(request) => request.params.downgrade(version),
+            ClientRequest::CompleteRequest(request) => {
+                if is_before(version, &ProtocolVersion::V_2025_06_18) {
+                    request.params.context = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Elicitation is only defined since 2025-06-18.
+impl Downgrade for ServerRequest {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if let ServerRequest::CreateMessageRequest(request) = self {
+            for message in &mut request.params.messages {
+                message.content.downgrade(version);
+            }
+        }
+    }
+
+    fn is_defined_in(&self, version: &ProtocolVersion) -> bool {
+        !matches!(self, ServerRequest::CreateElicitationRequest(_))
+            || !is_before(version, &ProtocolVersion::V_2025_06_18)
+    }
+}
+
+impl Downgrade for ProgressNotificationParam {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if is_before(version, &ProtocolVersion::V_2025_03_26) {
+            self.message = None;
+        }
+    }
+}
+
+impl Downgrade for ClientNotification {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if let ClientNotification::ProgressNotification(notification) = self {
+            notification.params.downgrade(version);
+        }
+    }
+}
+
+impl Downgrade for ServerNotification {
+    fn downgrade(&mut self, version: &ProtocolVersion) {
+        if let ServerNotification::ProgressNotification(notification) = self {
+            notification.params.downgrade(version);
+        }
+    }
+}
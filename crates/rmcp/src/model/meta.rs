@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
-    ClientNotification, ClientRequest, Extensions, JsonObject, JsonRpcMessage, NumberOrString,
-    ProgressToken, ServerNotification, ServerRequest,
+    ClientNotification, ClientRequest, ConstString, Extensions, JsonObject, JsonRpcMessage,
+    NumberOrString, ProgressToken, ServerNotification, ServerRequest,
 };
 
 pub trait GetMeta {
@@ -18,6 +18,10 @@ pub trait GetExtensions {
     fn extensions_mut(&mut self) -> &mut Extensions;
 }
 
+pub trait GetMethod {
+    fn method(&self) -> &'static str;
+}
+
 macro_rules! variant_extension {
     (
         $Enum: ident {
@@ -40,6 +44,15 @@ macro_rules! variant_extension {
                 }
             }
         }
+        impl GetMethod for $Enum {
+            fn method(&self) -> &'static str {
+                match self {
+                    $(
+                        $Enum::$variant(v) => v.method.as_str(),
+                    )*
+                }
+            }
+        }
         impl GetMeta for $Enum {
             fn get_meta_mut(&mut self) -> &mut Meta {
                 self.extensions_mut().get_or_insert_default()
@@ -66,6 +79,10 @@ variant_extension! {
         UnsubscribeRequest
         CallToolRequest
         ListToolsRequest
+        GetTaskRequest
+        GetTaskResultRequest
+        CancelTaskRequest
+        ListTasksRequest
     }
 }
 
@@ -96,6 +113,7 @@ variant_extension! {
         ResourceListChangedNotification
         ToolListChangedNotification
         PromptListChangedNotification
+        TaskStatusNotification
     }
 }
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
 
 use super::{Annotated, Icon, Meta};
@@ -84,6 +86,51 @@ impl ResourceContents {
     }
 }
 
+/// The `file://` URI of a path, made absolute.
+pub fn file_uri(path: &Path) -> std::io::Result<String> {
+    let path = std::path::absolute(path)?;
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            byte => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    Ok(uri)
+}
+
+/// The path of a `file://` URI, `None` for other URIs.
+pub fn file_uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // an empty or `localhost` host
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    if !path.starts_with('/') {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut chars = path.bytes();
+    while let Some(byte) = chars.next() {
+        if byte == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `/C:/dir` on windows
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&path[1..]));
+    }
+    Some(PathBuf::from(path))
+}
+
 impl RawResource {
     /// Creates a new Resource from a URI with explicit mime type
     pub fn new(uri: impl Into<String>, name: impl Into<String>) -> Self {
@@ -105,6 +152,23 @@ mod tests {
 
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn test_file_uri() {
+        let uri = file_uri(Path::new("/srv/my docs/100%.txt")).unwrap();
+        assert_eq!(uri, "file:///srv/my%20docs/100%25.txt");
+        assert_eq!(
+            file_uri_path(&uri),
+            Some(PathBuf::from("/srv/my docs/100%.txt"))
+        );
+        assert_eq!(
+            file_uri_path("file://localhost/etc/hosts"),
+            Some(PathBuf::from("/etc/hosts"))
+        );
+        assert_eq!(file_uri_path("https://example.com/file"), None);
+        assert_eq!(file_uri_path("file:///bad%2"), None);
+    }
+
     #[test]
     fn test_resource_serialization() {
         let resource = RawResource {
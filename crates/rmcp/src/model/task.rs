@@ -0,0 +1,142 @@
+//! Tool calls running in the background as tasks
+//!
+//! A client asks for a `tools/call` to run as a task by adding [`TaskMetadata`] to the
+//! request's `_meta`, the server then answers right away with a [`CreateTaskResult`]. The
+//! task is polled with `tasks/get`, its result is retrieved with `tasks/result`, and it can
+//! be cancelled with `tasks/cancel`.
+use chrono::{DateTime, Utc};
+
+use super::*;
+use crate::const_string;
+
+/// The state of a task.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum TaskStatus {
+    /// The task is running
+    Working,
+    /// The task waits for an input from the client, such as an elicitation
+    InputRequired,
+    /// The task has completed, its result is available
+    Completed,
+    /// The task has failed, `tasks/result` returns the error
+    Failed,
+    /// The task was cancelled before completion
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Whether the status will never change again.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A tool call running in the background.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Task {
+    pub task_id: String,
+    pub status: TaskStatus,
+    /// A human readable description of the current status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// How long the task and its result are kept after creation, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    /// The suggested delay between two `tasks/get` requests, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval: Option<u64>,
+}
+
+/// Added to the `_meta` of a `tools/call` request to run it as a task.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TaskMetadata {
+    /// The requested time to live of the task, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+}
+
+/// The `modelcontextprotocol.io/` prefix is reserved by the specification.
+const TASK_FIELD: &str = "rmcp/task";
+
+impl Meta {
+    pub fn get_task(&self) -> Option<TaskMetadata> {
+        self.0
+            .get(TASK_FIELD)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    pub fn set_task(&mut self, task: TaskMetadata) {
+        self.0.insert(
+            TASK_FIELD.to_string(),
+            serde_json::to_value(task).unwrap_or(Value::Null),
+        );
+    }
+
+    pub fn remove_task(&mut self) -> Option<TaskMetadata> {
+        self.0
+            .remove(TASK_FIELD)
+            .and_then(|value| serde_json::from_value(value).ok())
+    }
+}
+
+/// The response to a `tools/call` request which runs as a task.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateTaskResult {
+    pub task: Task,
+}
+
+/// The parameters of the `tasks/get`, `tasks/result` and `tasks/cancel` requests.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TaskRequestParam {
+    pub task_id: String,
+}
+
+/// The response to a `tasks/get` or `tasks/cancel` request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GetTaskResult {
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+const_string!(GetTaskRequestMethod = "tasks/get");
+/// Request to get the status of a task
+pub type GetTaskRequest = Request<GetTaskRequestMethod, TaskRequestParam>;
+
+const_string!(GetTaskResultRequestMethod = "tasks/result");
+/// Request to wait for a task to finish and get the result of its tool call
+pub type GetTaskResultRequest = Request<GetTaskResultRequestMethod, TaskRequestParam>;
+
+const_string!(CancelTaskRequestMethod = "tasks/cancel");
+/// Request to cancel a task
+pub type CancelTaskRequest = Request<CancelTaskRequestMethod, TaskRequestParam>;
+
+const_string!(ListTasksRequestMethod = "tasks/list");
+/// Request to list the tasks of the server
+pub type ListTasksRequest = RequestOptionalParam<ListTasksRequestMethod, PaginatedRequestParam>;
+
+/// The response to a `tasks/list` request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ListTasksResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<Cursor>,
+    pub tasks: Vec<Task>,
+}
+
+const_string!(TaskStatusNotificationMethod = "notifications/tasks/status");
+/// Notification sent when the status of a task changes
+pub type TaskStatusNotification = Notification<TaskStatusNotificationMethod, Task>;
@@ -0,0 +1,40 @@
+//! Tool output streamed in progress notifications
+//!
+//! A client asks for the output of a `tools/call` to be streamed by setting the
+//! `rmcp/streamToolOutput` flag in the `_meta` of the request. While the tool runs, the server
+//! then sends chunks of its output in the `_meta` of the progress notifications of the
+//! call. Without the flag, the whole output is in the result.
+use super::*;
+
+// not part of the specification, which reserves the `modelcontextprotocol.io/` prefix
+const STREAM_TOOL_OUTPUT_FIELD: &str = "rmcp/streamToolOutput";
+const TOOL_OUTPUT_FIELD: &str = "rmcp/toolOutput";
+
+impl Meta {
+    /// Whether the request asks for the output of the tool to be streamed.
+    pub fn get_stream_tool_output(&self) -> bool {
+        self.0
+            .get(STREAM_TOOL_OUTPUT_FIELD)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    pub fn set_stream_tool_output(&mut self, stream: bool) {
+        self.0
+            .insert(STREAM_TOOL_OUTPUT_FIELD.to_string(), Value::Bool(stream));
+    }
+
+    /// The chunk of tool output carried by a progress notification.
+    pub fn get_tool_output(&self) -> Option<Vec<Content>> {
+        self.0
+            .get(TOOL_OUTPUT_FIELD)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    pub fn set_tool_output(&mut self, content: Vec<Content>) {
+        self.0.insert(
+            TOOL_OUTPUT_FIELD.to_string(),
+            serde_json::to_value(content).unwrap_or(Value::Null),
+        );
+    }
+}
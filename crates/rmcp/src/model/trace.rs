@@ -0,0 +1,150 @@
+//! W3C trace context carried in `_meta`
+//!
+//! A request can carry the [trace context](https://www.w3.org/TR/trace-context/) of
+//! its sender in the `traceparent` and `tracestate` fields of its `_meta`, so the
+//! spans of the receiver continue the trace of the sender.
+use super::*;
+
+const TRACEPARENT_FIELD: &str = "traceparent";
+const TRACESTATE_FIELD: &str = "tracestate";
+
+/// The `traceparent` and `tracestate` of a W3C trace context.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    /// `{version}-{trace-id}-{parent-id}-{trace-flags}`, in lowercase hex
+    pub traceparent: String,
+    /// Vendor specific data, as a list of `key=value`
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    pub fn new(traceparent: impl Into<String>, tracestate: Option<String>) -> Self {
+        Self {
+            traceparent: traceparent.into(),
+            tracestate,
+        }
+    }
+
+    fn parts(&self) -> Option<[&str; 4]> {
+        let mut parts = self.traceparent.split('-');
+        let parts = [parts.next()?, parts.next()?, parts.next()?, parts.next()?];
+        let is_hex = |part: &str, len: usize| {
+            part.len() == len
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        };
+        let [version, trace_id, parent_id, flags] = parts;
+        let valid = is_hex(version, 2)
+            && version != "ff"
+            && is_hex(trace_id, 32)
+            && trace_id.bytes().any(|b| b != b'0')
+            && is_hex(parent_id, 16)
+            && parent_id.bytes().any(|b| b != b'0')
+            && is_hex(flags, 2)
+            // only version 00 is known, later versions may add fields
+            && (version != "00" || self.traceparent.len() == 55);
+        valid.then_some(parts)
+    }
+
+    /// Whether the `traceparent` is well formed.
+    pub fn is_valid(&self) -> bool {
+        self.parts().is_some()
+    }
+
+    /// The id of the whole trace.
+    pub fn trace_id(&self) -> Option<&str> {
+        self.parts().map(|[_, trace_id, _, _]| trace_id)
+    }
+
+    /// The id of the span of the sender.
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parts().map(|[_, _, parent_id, _]| parent_id)
+    }
+
+    /// Whether the sender records the trace.
+    pub fn is_sampled(&self) -> bool {
+        self.parts()
+            .and_then(|[_, _, _, flags]| u8::from_str_radix(flags, 16).ok())
+            .is_some_and(|flags| flags & 1 == 1)
+    }
+}
+
+impl Meta {
+    /// The trace context of the sender, if it is well formed.
+    pub fn get_trace_context(&self) -> Option<TraceContext> {
+        let traceparent = self.0.get(TRACEPARENT_FIELD)?.as_str()?;
+        let tracestate = self
+            .0
+            .get(TRACESTATE_FIELD)
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let context = TraceContext::new(traceparent, tracestate);
+        context.is_valid().then_some(context)
+    }
+
+    pub fn set_trace_context(&mut self, context: TraceContext) {
+        self.0.insert(
+            TRACEPARENT_FIELD.to_string(),
+            Value::String(context.traceparent),
+        );
+        match context.tracestate {
+            Some(tracestate) => {
+                self.0
+                    .insert(TRACESTATE_FIELD.to_string(), Value::String(tracestate));
+            }
+            None => {
+                self.0.remove(TRACESTATE_FIELD);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_parsing() {
+        let context = TraceContext::new(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            None,
+        );
+        assert_eq!(context.trace_id(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(context.parent_id(), Some("00f067aa0ba902b7"));
+        assert!(context.is_sampled());
+
+        for invalid in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        ] {
+            assert!(!TraceContext::new(invalid, None).is_valid(), "{invalid}");
+        }
+        // later versions may have more fields
+        assert!(
+            TraceContext::new(
+                "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra",
+                None
+            )
+            .is_valid()
+        );
+    }
+
+    #[test]
+    fn test_meta_round_trip() {
+        let mut meta = Meta::new();
+        assert_eq!(meta.get_trace_context(), None);
+        let context = TraceContext::new(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00",
+            Some("vendor=value".into()),
+        );
+        meta.set_trace_context(context.clone());
+        assert_eq!(meta.get_trace_context(), Some(context));
+        meta.insert("traceparent".into(), Value::String("garbage".into()));
+        assert_eq!(meta.get_trace_context(), None);
+    }
+}
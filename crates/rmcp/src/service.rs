@@ -4,9 +4,10 @@ use thiserror::Error;
 use crate::{
     error::ErrorData as McpError,
     model::{
-        CancelledNotification, CancelledNotificationParam, Extensions, GetExtensions, GetMeta,
-        JsonRpcError, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, Meta,
-        NumberOrString, ProgressToken, RequestId, ServerJsonRpcMessage,
+        CancelledNotification, CancelledNotificationParam, Downgrade, ErrorCode, Extensions,
+        GetExtensions, GetMeta, GetMethod, JsonRpcError, JsonRpcMessage, JsonRpcNotification,
+        JsonRpcRequest, JsonRpcResponse, LimitBlobs, Meta, NumberOrString, ProgressNotification,
+        ProgressNotificationParam, ProgressToken, ProtocolVersion, RequestId, ServerJsonRpcMessage,
     },
     transport::{DynamicTransportError, IntoTransport, Transport},
 };
@@ -22,6 +23,10 @@ mod server;
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
 pub use server::*;
+mod config;
+pub use config::*;
+pub mod middleware;
+pub mod propagation;
 #[cfg(feature = "tower")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
 mod tower;
@@ -65,18 +70,23 @@ impl<T> TransferObject for T where
 
 #[allow(private_bounds, reason = "there's no the third implementation")]
 pub trait ServiceRole: std::fmt::Debug + Send + Sync + 'static + Copy + Clone {
-    type Req: TransferObject + GetMeta + GetExtensions;
-    type Resp: TransferObject;
+    type Req: TransferObject + GetMeta + GetExtensions + GetMethod + Downgrade;
+    type Resp: TransferObject + Downgrade + LimitBlobs;
     type Not: TryInto<CancelledNotification, Error = Self::Not>
         + From<CancelledNotification>
-        + TransferObject;
-    type PeerReq: TransferObject + GetMeta + GetExtensions;
+        + TransferObject
+        + GetMethod
+        + Downgrade;
+    type PeerReq: TransferObject + GetMeta + GetExtensions + GetMethod;
     type PeerResp: TransferObject;
     type PeerNot: TryInto<CancelledNotification, Error = Self::PeerNot>
         + From<CancelledNotification>
+        + TryInto<ProgressNotification, Error = Self::PeerNot>
+        + From<ProgressNotification>
         + TransferObject
         + GetMeta
-        + GetExtensions;
+        + GetExtensions
+        + GetMethod;
     type InitializeError;
     const IS_CLIENT: bool;
     type Info: TransferObject;
@@ -103,6 +113,22 @@ pub trait Service<R: ServiceRole>: Send + Sync + 'static {
         context: NotificationContext<R>,
     ) -> impl Future<Output = Result<(), McpError>> + Send + '_;
     fn get_info(&self) -> R::Info;
+
+    /// See a request sent to the peer through a [`Peer`] before it goes out.
+    ///
+    /// An error fails the request without sending it.
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// See a notification sent to the peer through a [`Peer`] before it goes out.
+    ///
+    /// An error fails the notification without sending it.
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        let _ = notification;
+        Ok(())
+    }
 }
 
 pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
@@ -112,6 +138,15 @@ pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
     fn into_dyn(self) -> Box<dyn DynService<R>> {
         Box::new(self)
     }
+    /// Wrap this service with a [`Middleware`](middleware::Middleware).
+    ///
+    /// The middleware added last is the first to see the requests and notifications.
+    fn with_middleware<M>(self, middleware: M) -> middleware::Layered<Self, M>
+    where
+        M: middleware::Middleware<R>,
+    {
+        middleware::Layered::new(self, middleware)
+    }
     fn serve<T, E, A>(
         self,
         transport: T,
@@ -128,6 +163,20 @@ pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
         transport: T,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<R, Self>, R::InitializeError>> + Send
+    where
+        T: IntoTransport<R, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+        Self: Sized,
+    {
+        Self::serve_with_config(self, transport, ServeConfig::default(), ct)
+    }
+    /// Serve with limits on the requests of the peer and custom buffer sizes
+    fn serve_with_config<T, E, A>(
+        self,
+        transport: T,
+        config: ServeConfig,
+        ct: CancellationToken,
+    ) -> impl Future<Output = Result<RunningService<R, Self>, R::InitializeError>> + Send
     where
         T: IntoTransport<R, E, A>,
         E: std::error::Error + Send + Sync + 'static,
@@ -154,6 +203,14 @@ impl<R: ServiceRole> Service<R> for Box<dyn DynService<R>> {
     fn get_info(&self) -> R::Info {
         DynService::get_info(self.as_ref())
     }
+
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        DynService::handle_outgoing_request(self.as_ref(), request)
+    }
+
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        DynService::handle_outgoing_notification(self.as_ref(), notification)
+    }
 }
 
 pub trait DynService<R: ServiceRole>: Send + Sync {
@@ -168,6 +225,8 @@ pub trait DynService<R: ServiceRole>: Send + Sync {
         context: NotificationContext<R>,
     ) -> BoxFuture<'_, Result<(), McpError>>;
     fn get_info(&self) -> R::Info;
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError>;
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError>;
 }
 
 impl<R: ServiceRole, S: Service<R>> DynService<R> for S {
@@ -188,6 +247,12 @@ impl<R: ServiceRole, S: Service<R>> DynService<R> for S {
     fn get_info(&self) -> R::Info {
         self.get_info()
     }
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        Service::handle_outgoing_request(self, request)
+    }
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        Service::handle_outgoing_notification(self, notification)
+    }
 }
 
 use std::{
@@ -244,36 +309,73 @@ pub struct RequestHandle<R: ServiceRole> {
     pub peer: Peer<R>,
     pub id: RequestId,
     pub progress_token: ProgressToken,
+    /// the idle timeout and the progress notifications restarting it
+    idle: Option<(Duration, PeerProgressSubscription)>,
 }
 
 impl<R: ServiceRole> RequestHandle<R> {
     pub const REQUEST_TIMEOUT_REASON: &str = "request timeout";
+    pub const REQUEST_IDLE_TIMEOUT_REASON: &str = "request idle timeout, no progress received";
+
+    /// Set the maximum time to wait without receiving a progress notification for this request.
+    ///
+    /// The timer starts when the response is awaited, and restarts every time a progress
+    /// notification for the request arrives. Combine it with [`PeerRequestOptions::timeout`]
+    /// to still bound the total time.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        let progress = self.peer.subscribe_progress(self.progress_token.clone());
+        self.idle = Some((idle_timeout, progress));
+        self
+    }
+
+    /// Wait for the response.
+    ///
+    /// If [`PeerRequestOptions::timeout`] or the [idle timeout](RequestHandle::with_idle_timeout)
+    /// expires first, the request is cancelled and [`ServiceError::Timeout`] is returned.
     pub async fn await_response(self) -> Result<R::PeerResp, ServiceError> {
-        if let Some(timeout) = self.options.timeout {
-            let timeout_result = tokio::time::timeout(timeout, async move {
-                self.rx.await.map_err(|_e| ServiceError::TransportClosed)?
-            })
-            .await;
-            match timeout_result {
-                Ok(response) => response,
-                Err(_) => {
-                    let error = Err(ServiceError::Timeout { timeout });
-                    // cancel this request
-                    let notification = CancelledNotification {
-                        params: CancelledNotificationParam {
-                            request_id: self.id,
-                            reason: Some(Self::REQUEST_TIMEOUT_REASON.to_owned()),
-                        },
-                        method: crate::model::CancelledNotificationMethod,
-                        extensions: Default::default(),
-                    };
-                    let _ = self.peer.send_notification(notification.into()).await;
-                    error
+        let RequestHandle {
+            mut rx,
+            options,
+            peer,
+            id,
+            idle,
+            ..
+        } = self;
+        let (idle_timeout, mut progress) = idle.unzip();
+        let deadline = options
+            .timeout
+            .map(|timeout| tokio::time::Instant::now() + timeout);
+        let (timeout, reason) = loop {
+            let idle_deadline =
+                idle_timeout.map(|idle_timeout| tokio::time::Instant::now() + idle_timeout);
+            tokio::select! {
+                response = &mut rx => {
+                    return response.map_err(|_e| ServiceError::TransportClosed)?;
+                }
+                Some(_progress) = async { progress.as_mut()?.receiver.recv().await }, if progress.is_some() => {
+                    // progress received, restart the idle timer
+                    continue;
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() => {
+                    break (options.timeout.unwrap_or_default(), Self::REQUEST_TIMEOUT_REASON);
+                }
+                _ = tokio::time::sleep_until(idle_deadline.unwrap_or_else(tokio::time::Instant::now)), if idle_deadline.is_some() => {
+                    break (idle_timeout.unwrap_or_default(), Self::REQUEST_IDLE_TIMEOUT_REASON);
                 }
             }
-        } else {
-            self.rx.await.map_err(|_e| ServiceError::TransportClosed)?
-        }
+        };
+        drop(progress);
+        // cancel this request
+        let notification = CancelledNotification {
+            params: CancelledNotificationParam {
+                request_id: id,
+                reason: Some(reason.to_owned()),
+            },
+            method: crate::model::CancelledNotificationMethod,
+            extensions: Default::default(),
+        };
+        let _ = peer.send_notification(notification.into()).await;
+        Err(ServiceError::Timeout { timeout })
     }
 
     /// Cancel this request
@@ -314,7 +416,12 @@ pub struct Peer<R: ServiceRole> {
     tx: mpsc::Sender<PeerSinkMessage<R>>,
     request_id_provider: Arc<dyn RequestIdProvider>,
     progress_token_provider: Arc<dyn ProgressTokenProvider>,
+    progress_subscribers: ProgressSubscribers,
     info: Arc<tokio::sync::OnceCell<R::PeerInfo>>,
+    protocol_version: Arc<std::sync::OnceLock<ProtocolVersion>>,
+    trace_propagator: Option<Arc<dyn propagation::TracePropagator>>,
+    #[cfg(feature = "server")]
+    connection_id: Arc<std::sync::OnceLock<String>>,
 }
 
 impl<R: ServiceRole> std::fmt::Debug for Peer<R> {
@@ -328,8 +435,70 @@ impl<R: ServiceRole> std::fmt::Debug for Peer<R> {
 
 type ProxyOutbound<R> = mpsc::Receiver<PeerSinkMessage<R>>;
 
+type ProgressSubscribers =
+    Arc<std::sync::Mutex<HashMap<ProgressToken, Vec<mpsc::UnboundedSender<ProgressNotification>>>>>;
+
+/// A stream of the progress notifications received for one progress token.
+///
+/// Created by [`Peer::subscribe_progress`], it unsubscribes when dropped.
+#[derive(Debug)]
+pub struct PeerProgressSubscription {
+    progress_token: ProgressToken,
+    receiver: mpsc::UnboundedReceiver<ProgressNotification>,
+    subscribers: ProgressSubscribers,
+}
+
+impl PeerProgressSubscription {
+    pub fn progress_token(&self) -> &ProgressToken {
+        &self.progress_token
+    }
+
+    /// Poll the next notification, with its `_meta`.
+    pub fn poll_next_notification(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<ProgressNotification>> {
+        self.receiver.poll_recv(cx)
+    }
+
+    /// Stop receiving notifications; the ones already received can still be read.
+    pub fn unsubscribe(&mut self) {
+        self.receiver.close();
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .expect("progress subscribers lock poisoned");
+        if let Some(senders) = subscribers.get_mut(&self.progress_token) {
+            senders.retain(|sender| !sender.is_closed());
+            if senders.is_empty() {
+                subscribers.remove(&self.progress_token);
+            }
+        }
+    }
+}
+
+impl futures::Stream for PeerProgressSubscription {
+    type Item = ProgressNotificationParam;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.receiver
+            .poll_recv(cx)
+            .map(|notification| notification.map(|notification| notification.params))
+    }
+}
+
+impl Drop for PeerProgressSubscription {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct PeerRequestOptions {
+    /// The maximum time to wait for the response, counted from when the request is sent.
     pub timeout: Option<Duration>,
     pub meta: Option<Meta>,
 }
@@ -338,21 +507,36 @@ impl PeerRequestOptions {
     pub fn no_options() -> Self {
         Self::default()
     }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_meta(mut self, meta: Meta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
 }
 
 impl<R: ServiceRole> Peer<R> {
-    const CLIENT_CHANNEL_BUFFER_SIZE: usize = 1024;
     pub(crate) fn new(
         request_id_provider: Arc<dyn RequestIdProvider>,
         peer_info: Option<R::PeerInfo>,
+        config: &ServeConfig,
     ) -> (Peer<R>, ProxyOutbound<R>) {
-        let (tx, rx) = mpsc::channel(Self::CLIENT_CHANNEL_BUFFER_SIZE);
+        let (tx, rx) = mpsc::channel(config.peer_channel_buffer_size);
         (
             Self {
                 tx,
                 request_id_provider,
                 progress_token_provider: Arc::new(AtomicU32ProgressTokenProvider::default()),
+                progress_subscribers: Default::default(),
                 info: Arc::new(tokio::sync::OnceCell::new_with(peer_info)),
+                protocol_version: Default::default(),
+                trace_propagator: config.trace_propagator.clone(),
+                #[cfg(feature = "server")]
+                connection_id: Default::default(),
             },
             rx,
         )
@@ -396,6 +580,21 @@ impl<R: ServiceRole> Peer<R> {
         if let Some(meta) = options.meta.clone() {
             request.get_meta_mut().extend(meta);
         }
+        // keep the trace context set by the caller
+        if request.get_meta().get_trace_context().is_none() {
+            if let Some(context) = self
+                .trace_propagator
+                .as_ref()
+                .and_then(|propagator| propagator.inject(&tracing::Span::current()))
+            {
+                request.get_meta_mut().set_trace_context(context);
+            }
+        }
+        // the meta of the options may carry its own progress token
+        let progress_token = request
+            .get_meta()
+            .get_progress_token()
+            .unwrap_or(progress_token);
         let (responder, receiver) = tokio::sync::oneshot::channel();
         self.tx
             .send(PeerSinkMessage::Request {
@@ -409,6 +608,7 @@ impl<R: ServiceRole> Peer<R> {
             id,
             rx: receiver,
             progress_token,
+            idle: None,
             options,
             peer: self.clone(),
         })
@@ -425,9 +625,69 @@ impl<R: ServiceRole> Peer<R> {
         }
     }
 
+    /// A random identifier of the connection, shared by the clones of this peer.
+    #[cfg(feature = "server")]
+    pub(crate) fn connection_id(&self) -> &str {
+        self.connection_id
+            .get_or_init(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    /// The protocol version negotiated during initialization.
+    pub fn protocol_version(&self) -> Option<&ProtocolVersion> {
+        self.protocol_version.get()
+    }
+
+    pub(crate) fn set_protocol_version(&self, protocol_version: ProtocolVersion) {
+        if self.protocol_version.set(protocol_version).is_err() {
+            tracing::warn!("protocol version already negotiated");
+        }
+    }
+
     pub fn is_transport_closed(&self) -> bool {
         self.tx.is_closed()
     }
+
+    /// Whether both handles refer to the same connection.
+    pub fn is_same_peer(&self, other: &Self) -> bool {
+        self.tx.same_channel(&other.tx)
+    }
+
+    /// Generate a progress token that has not been used by this peer yet.
+    pub fn next_progress_token(&self) -> ProgressToken {
+        self.progress_token_provider.next_progress_token()
+    }
+
+    /// Subscribe to the progress notifications the remote peer sends for `progress_token`.
+    ///
+    /// Subscribe before sending the request carrying the token, otherwise early
+    /// notifications may be missed. Every subscription to the same token receives
+    /// all of its notifications.
+    pub fn subscribe_progress(&self, progress_token: ProgressToken) -> PeerProgressSubscription {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.progress_subscribers
+            .lock()
+            .expect("progress subscribers lock poisoned")
+            .entry(progress_token.clone())
+            .or_default()
+            .push(sender);
+        PeerProgressSubscription {
+            progress_token,
+            receiver,
+            subscribers: self.progress_subscribers.clone(),
+        }
+    }
+
+    fn dispatch_progress(&self, notification: &ProgressNotification) {
+        let subscribers = self
+            .progress_subscribers
+            .lock()
+            .expect("progress subscribers lock poisoned");
+        if let Some(senders) = subscribers.get(&notification.params.progress_token) {
+            for sender in senders {
+                let _ = sender.send(notification.clone());
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -436,6 +696,7 @@ pub struct RunningService<R: ServiceRole, S: Service<R>> {
     peer: Peer<R>,
     handle: tokio::task::JoinHandle<QuitReason>,
     cancellation_token: CancellationToken,
+    shutdown_tx: tokio::sync::oneshot::Sender<Duration>,
     dg: DropGuard,
 }
 impl<R: ServiceRole, S: Service<R>> Deref for RunningService<R, S> {
@@ -468,6 +729,28 @@ impl<R: ServiceRole, S: Service<R>> RunningService<R, S> {
         dg.disarm().cancel();
         handle.await
     }
+    /// Stop the service after the requests being handled are answered.
+    ///
+    /// New requests of the peer are answered with an error right away. Once the
+    /// requests being handled have finished and their responses are sent, or when
+    /// `grace_period` is over, the transport is closed. The remaining requests are
+    /// cancelled and counted in [`QuitReason::Shutdown`].
+    pub async fn shutdown(
+        self,
+        grace_period: Duration,
+    ) -> Result<QuitReason, tokio::task::JoinError> {
+        let RunningService {
+            dg,
+            handle,
+            shutdown_tx,
+            ..
+        } = self;
+        // the service may already be stopped
+        let _ = shutdown_tx.send(grace_period);
+        let quit_reason = handle.await;
+        drop(dg);
+        quit_reason
+    }
 }
 
 // use a wrapper type so we can tweak the implementation if needed
@@ -484,6 +767,12 @@ pub enum QuitReason {
     Cancelled,
     Closed,
     JoinError(tokio::task::JoinError),
+    /// Stopped by [`RunningService::shutdown`]
+    Shutdown {
+        /// The requests of the peer which were neither answered nor cancelled by the
+        /// peer at the end of the grace period
+        abandoned: usize,
+    },
 }
 
 /// Request execution context
@@ -535,8 +824,36 @@ where
     T: IntoTransport<R, E, A>,
     E: std::error::Error + Send + Sync + 'static,
 {
-    let (peer, peer_rx) = Peer::new(Arc::new(AtomicU32RequestIdProvider::default()), peer_info);
-    serve_inner(service, transport.into_transport(), peer, peer_rx, ct)
+    serve_directly_with_config(service, transport, peer_info, ServeConfig::default(), ct)
+}
+
+/// Use this function to skip initialization process
+pub fn serve_directly_with_config<R, S, T, E, A>(
+    service: S,
+    transport: T,
+    peer_info: Option<R::PeerInfo>,
+    config: ServeConfig,
+    ct: CancellationToken,
+) -> RunningService<R, S>
+where
+    R: ServiceRole,
+    S: Service<R>,
+    T: IntoTransport<R, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let (peer, peer_rx) = Peer::new(
+        Arc::new(AtomicU32RequestIdProvider::default()),
+        peer_info,
+        &config,
+    );
+    serve_inner(
+        service,
+        transport.into_transport(),
+        peer,
+        peer_rx,
+        config,
+        ct,
+    )
 }
 
 #[instrument(skip_all)]
@@ -545,6 +862,7 @@ fn serve_inner<R, S, T>(
     transport: T,
     peer: Peer<R>,
     mut peer_rx: tokio::sync::mpsc::Receiver<PeerSinkMessage<R>>,
+    config: ServeConfig,
     ct: CancellationToken,
 ) -> RunningService<R, S>
 where
@@ -552,9 +870,10 @@ where
     S: Service<R>,
     T: Transport<R> + 'static,
 {
-    const SINK_PROXY_BUFFER_SIZE: usize = 64;
     let (sink_proxy_tx, mut sink_proxy_rx) =
-        tokio::sync::mpsc::channel::<TxJsonRpcMessage<R>>(SINK_PROXY_BUFFER_SIZE);
+        tokio::sync::mpsc::channel::<TxJsonRpcMessage<R>>(config.sink_buffer_size);
+    let limiter = Arc::new(RequestLimiter::new(&config));
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<Duration>();
     let peer_info = peer.peer_info();
     if R::IS_CLIENT {
         tracing::info!(?peer_info, "Service initialized as client");
@@ -573,11 +892,18 @@ where
     // let mut stream = std::pin::pin!(stream);
     let serve_loop_ct = ct.child_token();
     let peer_return: Peer<R> = peer.clone();
+    // outgoing messages are adapted to the negotiated version
+    let protocol_version = peer.protocol_version().cloned();
     let current_span = tracing::Span::current();
     let handle = tokio::spawn(async move {
         let mut transport = transport.into_transport();
         let mut batch_messages = VecDeque::<RxJsonRpcMessage<R>>::new();
         let mut send_task_set = tokio::task::JoinSet::<SendTaskResult>::new();
+        // tracked to wait for them during a shutdown
+        let mut request_task_set = tokio::task::JoinSet::<()>::new();
+        let mut response_send_set = tokio::task::JoinSet::<()>::new();
+        let mut shutdown_requested = false;
+        let mut drain_deadline = None::<tokio::time::Instant>;
         #[derive(Debug)]
         enum SendTaskResult {
             Request {
@@ -599,6 +925,14 @@ where
         }
 
         let quit_reason = loop {
+            if drain_deadline.is_some()
+                && request_task_set.is_empty()
+                && response_send_set.is_empty()
+                && sink_proxy_rx.is_empty()
+            {
+                tracing::info!("all requests answered");
+                break QuitReason::Shutdown { abandoned: 0 };
+            }
             let evt = if let Some(m) = batch_messages.pop_front() {
                 Event::PeerMessage(m)
             } else {
@@ -645,6 +979,36 @@ where
                         tracing::info!("task cancelled");
                         break QuitReason::Cancelled
                     }
+                    grace_period = &mut shutdown_rx, if !shutdown_requested => {
+                        shutdown_requested = true;
+                        // the sender is dropped when the service is cancelled instead
+                        if let Ok(grace_period) = grace_period {
+                            tracing::info!(?grace_period, "shutting down");
+                            drain_deadline = Some(tokio::time::Instant::now() + grace_period);
+                        }
+                        continue
+                    }
+                    _ = tokio::time::sleep_until(drain_deadline.unwrap_or_else(tokio::time::Instant::now)), if drain_deadline.is_some() => {
+                        // the requests of the peer not answered yet, not the tasks rejecting
+                        // the requests received during the shutdown
+                        let abandoned = local_ct_pool.len();
+                        tracing::warn!(abandoned, "grace period over, cancelling the remaining requests");
+                        for (_, ct) in local_ct_pool.drain() {
+                            ct.cancel();
+                        }
+                        request_task_set.abort_all();
+                        response_send_set.abort_all();
+                        break QuitReason::Shutdown { abandoned }
+                    }
+                    Some(result) = request_task_set.join_next(), if !request_task_set.is_empty() => {
+                        if let Err(error) = result {
+                            tracing::error!(%error, "request handler panicked");
+                        }
+                        continue
+                    }
+                    Some(_) = response_send_set.join_next(), if !response_send_set.is_empty() => {
+                        continue
+                    }
                 }
             };
 
@@ -678,7 +1042,10 @@ where
                     }
                 }
                 // response and error
-                Event::ToSink(m) => {
+                Event::ToSink(mut m) => {
+                    if let Some(version) = &protocol_version {
+                        m.downgrade(version);
+                    }
                     if let Some(id) = match &m {
                         JsonRpcMessage::Response(response) => Some(&response.id),
                         JsonRpcMessage::Error(error) => Some(&error.id),
@@ -689,7 +1056,7 @@ where
                         }
                         let send = transport.send(m);
                         let current_span = tracing::Span::current();
-                        tokio::spawn(async move {
+                        response_send_set.spawn(async move {
                             let send_result = send.await;
                             if let Err(error) = send_result {
                                 tracing::error!(%error, "fail to response message");
@@ -698,10 +1065,29 @@ where
                     }
                 }
                 Event::ProxyMessage(PeerSinkMessage::Request {
-                    request,
+                    mut request,
                     id,
                     responder,
                 }) => {
+                    if let Err(error) = shared_service.handle_outgoing_request(&mut request) {
+                        let _ = responder.send(Err(ServiceError::McpError(error)));
+                        continue;
+                    }
+                    if let Some(version) = &protocol_version {
+                        if !request.is_defined_in(version) {
+                            let error = McpError::new(
+                                ErrorCode::METHOD_NOT_FOUND,
+                                format!(
+                                    "{} is not defined by protocol version {version}",
+                                    request.method()
+                                ),
+                                None,
+                            );
+                            let _ = responder.send(Err(ServiceError::McpError(error)));
+                            continue;
+                        }
+                        request.downgrade(version);
+                    }
                     local_responder_pool.insert(id.clone(), responder);
                     let send = transport.send(JsonRpcMessage::request(request, id.clone()));
                     {
@@ -714,9 +1100,16 @@ where
                     }
                 }
                 Event::ProxyMessage(PeerSinkMessage::Notification {
-                    notification,
+                    mut notification,
                     responder,
                 }) => {
+                    if let Err(error) = shared_service.handle_outgoing_notification(&mut notification) {
+                        let _ = responder.send(Err(ServiceError::McpError(error)));
+                        continue;
+                    }
+                    if let Some(version) = &protocol_version {
+                        notification.downgrade(version);
+                    }
                     // catch cancellation notification
                     let mut cancellation_param = None;
                     let notification = match notification.try_into() {
@@ -740,18 +1133,53 @@ where
                     ..
                 })) => {
                     tracing::debug!(%id, ?request, "received request");
+                    if drain_deadline.is_some() {
+                        tracing::info!(%id, "request received while shutting down");
+                        let error = McpError::internal_error("service is shutting down", None);
+                        #[cfg(feature = "metrics")]
+                        crate::metrics::record_request(
+                            R::IS_CLIENT,
+                            request.method(),
+                            "rejected",
+                            std::time::Instant::now(),
+                            Some(error.code.0),
+                        );
+                        let sink = sink_proxy_tx.clone();
+                        let current_span = tracing::Span::current();
+                        request_task_set.spawn(async move {
+                            let _send_result = sink.send(JsonRpcMessage::error(error, id)).await;
+                        }.instrument(current_span));
+                        continue;
+                    }
                     {
                         let service = shared_service.clone();
                         let sink = sink_proxy_tx.clone();
+                        let limiter = limiter.clone();
                         let request_ct = serve_loop_ct.child_token();
                         let context_ct = request_ct.child_token();
-                        local_ct_pool.insert(id.clone(), request_ct);
+                        local_ct_pool.insert(id.clone(), request_ct.clone());
                         let mut extensions = Extensions::new();
                         let mut meta = Meta::new();
                         // avoid clone
                         // swap meta firstly, otherwise progress token will be lost
                         std::mem::swap(&mut meta, request.get_meta_mut());
                         std::mem::swap(&mut extensions, request.extensions_mut());
+                        // continue the trace of the peer in a span of the request
+                        let current_span = match &config.trace_propagator {
+                            Some(propagator) => {
+                                let span = tracing::info_span!(
+                                    "mcp_request",
+                                    method = request.method(),
+                                    %id
+                                );
+                                if let Some(trace_context) = meta.get_trace_context() {
+                                    propagator.extract(&trace_context, &span);
+                                }
+                                span
+                            }
+                            None => tracing::Span::current(),
+                        };
+                        let blob_limit = config.blob_limit;
                         let context = RequestContext {
                             ct: context_ct,
                             id: id.clone(),
@@ -759,18 +1187,53 @@ where
                             meta,
                             extensions,
                         };
-                        let current_span = tracing::Span::current();
-                        tokio::spawn(async move {
+                        request_task_set.spawn(async move {
+                            let method = request.method();
+                            #[cfg(feature = "metrics")]
+                            let start = std::time::Instant::now();
+                            let _permit = match limiter.acquire(method, &request_ct).await {
+                                Some(Ok(permit)) => permit,
+                                Some(Err(error)) => {
+                                    tracing::warn!(%id, ?error, "request rejected");
+                                    #[cfg(feature = "metrics")]
+                                    crate::metrics::record_request(R::IS_CLIENT, method, "rejected", start, Some(error.code.0));
+                                    let _send_result = sink.send(JsonRpcMessage::error(error, id)).await;
+                                    return;
+                                }
+                                // the response of a cancelled request is dropped anyway
+                                None => {
+                                    #[cfg(feature = "metrics")]
+                                    crate::metrics::record_request(R::IS_CLIENT, method, "cancelled", start, None);
+                                    return;
+                                }
+                            };
                             let result = service
                                 .handle_request(request, context)
                                 .await;
+                            // the receiver of a cancelled request should not send a response
+                            if request_ct.is_cancelled() {
+                                tracing::info!(%id, "request cancelled, response dropped");
+                                #[cfg(feature = "metrics")]
+                                crate::metrics::record_request(R::IS_CLIENT, method, "cancelled", start, None);
+                                return;
+                            }
+                            let result = match (blob_limit, result) {
+                                (Some(limit), Ok(mut result)) => {
+                                    result.limit_blobs(&limit).map(|()| result)
+                                }
+                                (_, result) => result,
+                            };
                             let response = match result {
                                 Ok(result) => {
                                     tracing::debug!(%id, ?result, "response message");
+                                    #[cfg(feature = "metrics")]
+                                    crate::metrics::record_request(R::IS_CLIENT, method, "ok", start, None);
                                     JsonRpcMessage::response(result, id)
                                 }
                                 Err(error) => {
                                     tracing::warn!(%id, ?error, "response error");
+                                    #[cfg(feature = "metrics")]
+                                    crate::metrics::record_request(R::IS_CLIENT, method, "error", start, Some(error.code.0));
                                     JsonRpcMessage::error(error, id)
                                 }
                             };
@@ -784,7 +1247,7 @@ where
                 })) => {
                     tracing::info!(?notification, "received notification");
                     // catch cancelled notification
-                    let mut notification = match notification.try_into() {
+                    let notification = match notification.try_into() {
                         Ok::<CancelledNotification, _>(cancelled) => {
                             if let Some(ct) = local_ct_pool.remove(&cancelled.params.request_id) {
                                 tracing::info!(id = %cancelled.params.request_id, reason = cancelled.params.reason, "cancelled");
@@ -794,6 +1257,14 @@ where
                         }
                         Err(notification) => notification,
                     };
+                    // dispatch progress notification to subscribers
+                    let mut notification = match notification.try_into() {
+                        Ok::<ProgressNotification, _>(progress) => {
+                            peer.dispatch_progress(&progress);
+                            progress.into()
+                        }
+                        Err(notification) => notification,
+                    };
                     {
                         let service = shared_service.clone();
                         let mut extensions = Extensions::new();
@@ -837,6 +1308,9 @@ where
                 }
             }
         };
+        // the handlers keep running unless the service was shut down
+        request_task_set.detach_all();
+        response_send_set.detach_all();
         let sink_close_result = transport.close().await;
         if let Err(e) = sink_close_result {
             tracing::error!(%e, "fail to close sink");
@@ -849,6 +1323,7 @@ where
         peer: peer_return,
         handle,
         cancellation_token: ct.clone(),
+        shutdown_tx,
         dg: ct.drop_guard(),
     }
 }
@@ -5,18 +5,21 @@ use thiserror::Error;
 use super::*;
 use crate::{
     model::{
-        ArgumentInfo, CallToolRequest, CallToolRequestParam, CallToolResult, CancelledNotification,
-        CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage, ClientNotification,
-        ClientRequest, ClientResult, CompleteRequest, CompleteRequestParam, CompleteResult,
-        CompletionContext, CompletionInfo, GetPromptRequest, GetPromptRequestParam,
-        GetPromptResult, InitializeRequest, InitializedNotification, JsonRpcResponse,
+        ArgumentInfo, CallToolRequest, CallToolRequestParam, CallToolResult, CancelTaskRequest,
+        CancelledNotification, CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage,
+        ClientNotification, ClientRequest, ClientResult, CompleteRequest, CompleteRequestParam,
+        CompleteResult, CompletionContext, CompletionInfo, Content, GetPromptRequest,
+        GetPromptRequestParam, GetPromptResult, GetTaskRequest, GetTaskResult,
+        GetTaskResultRequest, InitializeRequest, InitializedNotification, JsonRpcResponse,
         ListPromptsRequest, ListPromptsResult, ListResourceTemplatesRequest,
-        ListResourceTemplatesResult, ListResourcesRequest, ListResourcesResult, ListToolsRequest,
-        ListToolsResult, PaginatedRequestParam, ProgressNotification, ProgressNotificationParam,
-        ReadResourceRequest, ReadResourceRequestParam, ReadResourceResult, Reference, RequestId,
+        ListResourceTemplatesResult, ListResourcesRequest, ListResourcesResult, ListTasksRequest,
+        ListTasksResult, ListToolsRequest, ListToolsResult, PaginatedRequestParam,
+        ProgressNotification, ProgressNotificationParam, ProgressToken, ReadResourceRequest,
+        ReadResourceRequestParam, ReadResourceResult, Reference, RequestId,
         RootsListChangedNotification, ServerInfo, ServerJsonRpcMessage, ServerNotification,
         ServerRequest, ServerResult, SetLevelRequest, SetLevelRequestParam, SubscribeRequest,
-        SubscribeRequestParam, UnsubscribeRequest, UnsubscribeRequestParam,
+        SubscribeRequestParam, Task, TaskMetadata, TaskRequestParam, UnsubscribeRequest,
+        UnsubscribeRequestParam,
     },
     transport::DynamicTransportError,
 };
@@ -38,6 +41,9 @@ pub enum ClientInitializeError {
     #[error("connection closed: {0}")]
     ConnectionClosed(String),
 
+    #[error("unsupported protocol version: {0}")]
+    UnsupportedProtocolVersion(ProtocolVersion),
+
     #[error("Send message error {error}, when {context}")]
     TransportError {
         error: DynamicTransportError,
@@ -150,9 +156,10 @@ impl ServiceRole for RoleClient {
 pub type ServerSink = Peer<RoleClient>;
 
 impl<S: Service<RoleClient>> ServiceExt<RoleClient> for S {
-    fn serve_with_ct<T, E, A>(
+    fn serve_with_config<T, E, A>(
         self,
         transport: T,
+        config: ServeConfig,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<RoleClient, Self>, ClientInitializeError>> + Send
     where
@@ -160,7 +167,7 @@ impl<S: Service<RoleClient>> ServiceExt<RoleClient> for S {
         E: std::error::Error + Send + Sync + 'static,
         Self: Sized,
     {
-        serve_client_with_ct(self, transport, ct)
+        serve_client_with_config(self, transport, config, ct)
     }
 }
 
@@ -181,13 +188,27 @@ pub async fn serve_client_with_ct<S, T, E, A>(
     transport: T,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
+where
+    S: Service<RoleClient>,
+    T: IntoTransport<RoleClient, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    serve_client_with_config(service, transport, ServeConfig::default(), ct).await
+}
+
+pub async fn serve_client_with_config<S, T, E, A>(
+    service: S,
+    transport: T,
+    config: ServeConfig,
+    ct: CancellationToken,
+) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
 where
     S: Service<RoleClient>,
     T: IntoTransport<RoleClient, E, A>,
     E: std::error::Error + Send + Sync + 'static,
 {
     tokio::select! {
-        result = serve_client_with_ct_inner(service, transport.into_transport(), ct.clone()) => { result }
+        result = serve_client_with_ct_inner(service, transport.into_transport(), config, ct.clone()) => { result }
         _ = ct.cancelled() => {
             Err(ClientInitializeError::Cancelled)
         }
@@ -197,6 +218,7 @@ where
 async fn serve_client_with_ct_inner<S, T>(
     service: S,
     transport: T,
+    config: ServeConfig,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
 where
@@ -224,7 +246,7 @@ where
             context: "send initialize request".into(),
         })?;
 
-    let (peer, peer_rx) = Peer::new(id_provider, None);
+    let (peer, peer_rx) = Peer::new(id_provider, None, &config);
 
     let (response, response_id) = expect_response(
         &mut transport,
@@ -244,6 +266,13 @@ where
     let ServerResult::InitializeResult(initialize_result) = response else {
         return Err(ClientInitializeError::ExpectedInitResult(Some(response)));
     };
+    // the server answers with the requested version or with one it supports instead
+    if !initialize_result.protocol_version.is_known() {
+        return Err(ClientInitializeError::UnsupportedProtocolVersion(
+            initialize_result.protocol_version,
+        ));
+    }
+    peer.set_protocol_version(initialize_result.protocol_version.clone());
     peer.set_peer_info(initialize_result);
 
     // send notification
@@ -256,7 +285,7 @@ where
     transport.send(notification).await.map_err(|error| {
         ClientInitializeError::transport::<T>(error, "send initialized notification")
     })?;
-    Ok(serve_inner(service, transport, peer, peer_rx, ct))
+    Ok(serve_inner(service, transport, peer, peer_rx, config, ct))
 }
 
 macro_rules! method {
@@ -354,6 +383,10 @@ impl Peer<RoleClient> {
     method!(peer_req unsubscribe UnsubscribeRequest(UnsubscribeRequestParam));
     method!(peer_req call_tool CallToolRequest(CallToolRequestParam) => CallToolResult);
     method!(peer_req list_tools ListToolsRequest(PaginatedRequestParam)? => ListToolsResult);
+    method!(peer_req get_task GetTaskRequest(TaskRequestParam) => GetTaskResult);
+    method!(peer_req get_task_result GetTaskResultRequest(TaskRequestParam) => CallToolResult);
+    method!(peer_req cancel_task CancelTaskRequest(TaskRequestParam) => GetTaskResult);
+    method!(peer_req list_tasks ListTasksRequest(PaginatedRequestParam)? => ListTasksResult);
 
     method!(peer_not notify_cancelled CancelledNotification(CancelledNotificationParam));
     method!(peer_not notify_progress ProgressNotification(ProgressNotificationParam));
@@ -440,6 +473,25 @@ impl Peer<RoleClient> {
         Ok(resource_templates)
     }
 
+    /// A wrapper method for [`Peer<RoleClient>::list_tasks`].
+    ///
+    /// This function will call [`Peer<RoleClient>::list_tasks`] multiple times until all tasks are listed.
+    pub async fn list_all_tasks(&self) -> Result<Vec<Task>, ServiceError> {
+        let mut tasks = Vec::new();
+        let mut cursor = None;
+        loop {
+            let result = self
+                .list_tasks(Some(PaginatedRequestParam { cursor }))
+                .await?;
+            tasks.extend(result.tasks);
+            cursor = result.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(tasks)
+    }
+
     /// Convenient method to get completion suggestions for a prompt argument
     ///
     /// # Arguments
@@ -532,3 +584,474 @@ impl Peer<RoleClient> {
         Ok(completion.values)
     }
 }
+
+impl Peer<RoleClient> {
+    /// Call a tool and stream the progress notifications the server sends for it.
+    ///
+    /// A fresh progress token is attached to the request and subscribed to before
+    /// the request is sent. The returned [`CallToolWithProgress`] yields the progress
+    /// notifications as a [`Stream`](futures::Stream), which ends once the call has
+    /// completed; the result is then available from [`CallToolWithProgress::result`].
+    ///
+    /// ```rust,no_run
+    /// # use futures::StreamExt;
+    /// # use rmcp::{Peer, RoleClient, model::CallToolRequestParam};
+    /// # async fn example(peer: Peer<RoleClient>) -> Result<(), rmcp::ServiceError> {
+    /// let mut call = peer
+    ///     .call_tool_with_progress(CallToolRequestParam {
+    ///         name: "export".into(),
+    ///         arguments: None,
+    ///     })
+    ///     .await?;
+    /// while let Some(progress) = call.next().await {
+    ///     println!("{}/{:?}", progress.progress, progress.total);
+    /// }
+    /// let result = call.result().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_with_progress(
+        &self,
+        params: CallToolRequestParam,
+    ) -> Result<CallToolWithProgress, ServiceError> {
+        self.call_tool_with_meta(params, Meta::new()).await
+    }
+
+    async fn call_tool_with_meta(
+        &self,
+        params: CallToolRequestParam,
+        mut meta: Meta,
+    ) -> Result<CallToolWithProgress, ServiceError> {
+        let progress_token = self.next_progress_token();
+        let progress = self.subscribe_progress(progress_token.clone());
+        meta.set_progress_token(progress_token);
+        let handle = self
+            .send_request_with_option(
+                ClientRequest::CallToolRequest(CallToolRequest {
+                    method: Default::default(),
+                    params,
+                    extensions: Default::default(),
+                }),
+                PeerRequestOptions {
+                    meta: Some(meta),
+                    ..PeerRequestOptions::no_options()
+                },
+            )
+            .await?;
+        let id = handle.id.clone();
+        let response = async move {
+            match handle.await_response().await? {
+                ServerResult::CallToolResult(result) => Ok(result),
+                _ => Err(ServiceError::UnexpectedResponse),
+            }
+        }
+        .boxed();
+        Ok(CallToolWithProgress {
+            id,
+            peer: self.clone(),
+            progress,
+            state: CallToolState::Pending(response),
+        })
+    }
+}
+
+impl Peer<RoleClient> {
+    /// Call a tool and stream the chunks of output the server sends while it runs.
+    ///
+    /// The request asks for the output to be streamed in its `_meta`, servers which don't
+    /// support it send the whole output in the result. The returned [`CallToolStreaming`]
+    /// yields the chunks as a [`Stream`](futures::Stream), which ends once the call has
+    /// completed; [`CallToolStreaming::result`] then assembles the whole result.
+    ///
+    /// ```rust,no_run
+    /// # use futures::StreamExt;
+    /// # use rmcp::{Peer, RoleClient, model::CallToolRequestParam};
+    /// # async fn example(peer: Peer<RoleClient>) -> Result<(), rmcp::ServiceError> {
+    /// let mut call = peer
+    ///     .call_tool_streaming(CallToolRequestParam {
+    ///         name: "build".into(),
+    ///         arguments: None,
+    ///     })
+    ///     .await?;
+    /// while let Some(chunk) = call.next().await {
+    ///     if let Some(text) = chunk.as_text() {
+    ///         print!("{}", text.text);
+    ///     }
+    /// }
+    /// let result = call.result().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_streaming(
+        &self,
+        params: CallToolRequestParam,
+    ) -> Result<CallToolStreaming, ServiceError> {
+        let mut meta = Meta::new();
+        meta.set_stream_tool_output(true);
+        Ok(CallToolStreaming {
+            call: self.call_tool_with_meta(params, meta).await?,
+            pending: Default::default(),
+            received: Vec::new(),
+        })
+    }
+}
+
+impl Peer<RoleClient> {
+    /// Call a tool as a task running in the background on the server.
+    ///
+    /// The server answers as soon as the task is created, the returned [`ToolTask`]
+    /// is then used to follow it. `ttl` is how long the server should keep the task
+    /// and its result, the server picks it when `None`.
+    ///
+    /// Fails with [`ServiceError::UnexpectedResponse`] if the server ran the call right
+    /// away instead, which servers without task support do.
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use rmcp::{Peer, RoleClient, model::CallToolRequestParam};
+    /// # async fn example(peer: Peer<RoleClient>) -> Result<(), rmcp::ServiceError> {
+    /// let mut task = peer
+    ///     .call_tool_as_task(
+    ///         CallToolRequestParam {
+    ///             name: "export".into(),
+    ///             arguments: None,
+    ///         },
+    ///         Some(Duration::from_secs(600)),
+    ///     )
+    ///     .await?;
+    /// let result = task.poll_result().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_as_task(
+        &self,
+        params: CallToolRequestParam,
+        ttl: Option<std::time::Duration>,
+    ) -> Result<ToolTask, ServiceError> {
+        let mut meta = Meta::new();
+        meta.set_task(TaskMetadata {
+            ttl: ttl.map(|ttl| ttl.as_millis().try_into().unwrap_or(u64::MAX)),
+        });
+        let result = self
+            .send_request_with_option(
+                ClientRequest::CallToolRequest(CallToolRequest {
+                    method: Default::default(),
+                    params,
+                    extensions: Default::default(),
+                }),
+                PeerRequestOptions {
+                    meta: Some(meta),
+                    ..PeerRequestOptions::no_options()
+                },
+            )
+            .await?
+            .await_response()
+            .await?;
+        match result {
+            ServerResult::CreateTaskResult(result) => Ok(ToolTask {
+                peer: self.clone(),
+                task: result.task,
+            }),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+}
+
+/// Errors that can occur when calling a tool with typed arguments
+#[derive(Error, Debug)]
+pub enum TypedToolCallError {
+    /// The call failed at the service level
+    #[error("Service error: {0}")]
+    Service(#[from] ServiceError),
+
+    /// The arguments could not be serialized into a JSON object
+    #[error("Failed to serialize arguments: {0}")]
+    Arguments(serde_json::Error),
+
+    /// The tool returned a result flagged as an error
+    #[error("Tool returned an error: {0:?}")]
+    Tool(CallToolResult),
+
+    /// The result could not be parsed into the requested type
+    #[error("Failed to parse result: {0}")]
+    ParseError(serde_json::Error),
+}
+
+impl Peer<RoleClient> {
+    /// Call a tool with any arguments serializing into a JSON object.
+    ///
+    /// Arguments serializing into `null`, like `()`, are sent as no arguments.
+    pub async fn call_tool_with_arguments(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        arguments: impl serde::Serialize,
+    ) -> Result<CallToolResult, TypedToolCallError> {
+        let arguments = match serde_json::to_value(arguments) {
+            Ok(serde_json::Value::Object(arguments)) => Some(arguments),
+            Ok(serde_json::Value::Null) => None,
+            Ok(_) => {
+                return Err(TypedToolCallError::Arguments(serde::ser::Error::custom(
+                    "tool arguments must be an object",
+                )));
+            }
+            Err(error) => return Err(TypedToolCallError::Arguments(error)),
+        };
+        let result = self
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments,
+            })
+            .await?;
+        Ok(result)
+    }
+
+    /// Call a tool with typed arguments, and parse its result into `R`.
+    ///
+    /// The result is parsed with [`CallToolResult::into_typed`], unless the tool returned
+    /// an error.
+    ///
+    /// ```rust,no_run
+    /// # use rmcp::{Peer, RoleClient, service::TypedToolCallError};
+    /// # #[derive(serde::Deserialize)]
+    /// # struct Weather { temperature: f64 }
+    /// # async fn example(peer: Peer<RoleClient>) -> Result<(), TypedToolCallError> {
+    /// let weather: Weather = peer
+    ///     .call_tool_typed("get_weather", serde_json::json!({ "city": "Paris" }))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_typed<R: serde::de::DeserializeOwned>(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        arguments: impl serde::Serialize,
+    ) -> Result<R, TypedToolCallError> {
+        let result = self.call_tool_with_arguments(name, arguments).await?;
+        if result.is_error == Some(true) {
+            return Err(TypedToolCallError::Tool(result));
+        }
+        result.into_typed().map_err(TypedToolCallError::ParseError)
+    }
+}
+
+/// A tool call running as a task on the server, created by [`Peer::call_tool_as_task`].
+#[derive(Debug, Clone)]
+pub struct ToolTask {
+    peer: Peer<RoleClient>,
+    task: Task,
+}
+
+impl ToolTask {
+    /// The polling interval used when the server doesn't suggest one.
+    pub const DEFAULT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+    pub fn id(&self) -> &str {
+        &self.task.task_id
+    }
+
+    /// The task as last received from the server.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    fn param(&self) -> TaskRequestParam {
+        TaskRequestParam {
+            task_id: self.task.task_id.clone(),
+        }
+    }
+
+    /// Fetch the current status of the task.
+    pub async fn refresh(&mut self) -> Result<&Task, ServiceError> {
+        self.task = self.peer.get_task(self.param()).await?.task;
+        Ok(&self.task)
+    }
+
+    /// Wait for the task to finish with a single `tasks/result` request.
+    ///
+    /// The request stays pending on the server until the task finishes, prefer
+    /// [`ToolTask::poll_result`] when the connection can't hold it that long.
+    pub async fn result(&self) -> Result<CallToolResult, ServiceError> {
+        self.peer.get_task_result(self.param()).await
+    }
+
+    /// Poll the task at the interval suggested by the server until it finishes, then
+    /// get its result.
+    pub async fn poll_result(&mut self) -> Result<CallToolResult, ServiceError> {
+        while !self.task.status.is_terminal() {
+            let interval = self.task.poll_interval.map_or(
+                Self::DEFAULT_POLL_INTERVAL,
+                std::time::Duration::from_millis,
+            );
+            tokio::time::sleep(interval).await;
+            self.refresh().await?;
+        }
+        self.result().await
+    }
+
+    /// Cancel the task.
+    pub async fn cancel(&mut self) -> Result<&Task, ServiceError> {
+        self.task = self.peer.cancel_task(self.param()).await?.task;
+        Ok(&self.task)
+    }
+}
+
+enum CallToolState {
+    Pending(BoxFuture<'static, Result<CallToolResult, ServiceError>>),
+    Completed(Result<CallToolResult, ServiceError>),
+    Taken,
+}
+
+/// A tool call in flight, created by [`Peer::call_tool_with_progress`].
+///
+/// It is a [`Stream`](futures::Stream) of the progress notifications received for
+/// the call, which ends once the call has completed. The progress subscription
+/// is removed when the call completes, is cancelled or when this handle is dropped.
+pub struct CallToolWithProgress {
+    id: RequestId,
+    peer: Peer<RoleClient>,
+    progress: PeerProgressSubscription,
+    state: CallToolState,
+}
+
+impl std::fmt::Debug for CallToolWithProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallToolWithProgress")
+            .field("id", &self.id)
+            .field("progress_token", self.progress.progress_token())
+            .field(
+                "completed",
+                &!matches!(self.state, CallToolState::Pending(_)),
+            )
+            .finish()
+    }
+}
+
+impl CallToolWithProgress {
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+
+    pub fn progress_token(&self) -> &ProgressToken {
+        self.progress.progress_token()
+    }
+
+    /// Wait for the result of the call.
+    ///
+    /// Progress notifications which have not been consumed yet are discarded.
+    pub async fn result(mut self) -> Result<CallToolResult, ServiceError> {
+        match std::mem::replace(&mut self.state, CallToolState::Taken) {
+            CallToolState::Pending(response) => response.await,
+            CallToolState::Completed(result) => result,
+            CallToolState::Taken => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    /// Cancel the call, the server is notified with a [`CancelledNotification`].
+    pub async fn cancel(self, reason: Option<String>) -> Result<(), ServiceError> {
+        if !matches!(self.state, CallToolState::Pending(_)) {
+            return Ok(());
+        }
+        self.peer
+            .notify_cancelled(CancelledNotificationParam {
+                request_id: self.id.clone(),
+                reason,
+            })
+            .await
+    }
+}
+
+impl CallToolWithProgress {
+    fn poll_next_notification(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<ProgressNotification>> {
+        use std::task::Poll;
+        if let Poll::Ready(Some(progress)) = self.progress.poll_next_notification(cx) {
+            return Poll::Ready(Some(progress));
+        }
+        if let CallToolState::Pending(response) = &mut self.state {
+            let result = std::task::ready!(response.poll_unpin(cx));
+            self.state = CallToolState::Completed(result);
+            // no more notifications will be dispatched, drain the received ones
+            self.progress.unsubscribe();
+        }
+        self.progress.poll_next_notification(cx)
+    }
+}
+
+impl futures::Stream for CallToolWithProgress {
+    type Item = ProgressNotificationParam;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.poll_next_notification(cx)
+            .map(|progress| progress.map(|progress| progress.params))
+    }
+}
+
+/// A tool call in flight, created by [`Peer::call_tool_streaming`].
+///
+/// It is a [`Stream`](futures::Stream) of the chunks of output received for the call,
+/// which ends once the call has completed. The progress notifications without output
+/// are skipped.
+#[derive(Debug)]
+pub struct CallToolStreaming {
+    call: CallToolWithProgress,
+    pending: std::collections::VecDeque<Content>,
+    received: Vec<Content>,
+}
+
+impl CallToolStreaming {
+    pub fn id(&self) -> &RequestId {
+        self.call.id()
+    }
+
+    /// Wait for the result of the call.
+    ///
+    /// The content of the result starts with all the chunks received for the call,
+    /// whether they were consumed from the stream or not.
+    pub async fn result(mut self) -> Result<CallToolResult, ServiceError> {
+        use futures::StreamExt;
+        while self.next().await.is_some() {}
+        let mut result = self.call.result().await?;
+        if !self.received.is_empty() {
+            self.received.append(&mut result.content);
+            result.content = self.received;
+        }
+        Ok(result)
+    }
+
+    /// Cancel the call, the server is notified with a [`CancelledNotification`].
+    pub async fn cancel(self, reason: Option<String>) -> Result<(), ServiceError> {
+        self.call.cancel(reason).await
+    }
+}
+
+impl futures::Stream for CallToolStreaming {
+    type Item = Content;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(chunk) = this.pending.pop_front() {
+                return std::task::Poll::Ready(Some(chunk));
+            }
+            let Some(progress) = std::task::ready!(this.call.poll_next_notification(cx)) else {
+                return std::task::Poll::Ready(None);
+            };
+            if let Some(chunk) = progress
+                .extensions
+                .get::<Meta>()
+                .and_then(Meta::get_tool_output)
+            {
+                this.received.extend(chunk.iter().cloned());
+                this.pending.extend(chunk);
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use super::propagation::TracePropagator;
+use crate::{error::ErrorData as McpError, model::BlobLimit};
+
+/// What to do with a request received while a limit of [`ServeConfig`] is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until the request can be handled
+    #[default]
+    Queue,
+    /// Answer with an [`INTERNAL_ERROR`](crate::model::ErrorCode::INTERNAL_ERROR) whose data
+    /// has the `"reason": "tooManyRequests"` of [`OverflowPolicy::REJECTED_REASON`], the
+    /// specification has no error code for it
+    Reject,
+}
+
+impl OverflowPolicy {
+    /// The `reason` in the data of the errors rejecting the requests over a limit.
+    pub const REJECTED_REASON: &str = "tooManyRequests";
+}
+
+/// How a running service handles the messages of its peer.
+///
+/// ```rust
+/// # use rmcp::service::{OverflowPolicy, ServeConfig};
+/// let config = ServeConfig::default()
+///     .with_max_in_flight_requests(32)
+///     .with_method_limit("tools/call", 8)
+///     .with_overflow_policy(OverflowPolicy::Reject);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeConfig {
+    /// The maximum number of requests of the peer handled at the same time
+    pub max_in_flight_requests: Option<usize>,
+    /// The maximum number of requests handled at the same time, by method
+    pub method_limits: HashMap<String, usize>,
+    /// What happens to the requests over a limit
+    pub overflow_policy: OverflowPolicy,
+    /// The capacity of the channel of the responses waiting to be sent
+    pub sink_buffer_size: usize,
+    /// The capacity of the channel of the requests and notifications sent by the [`Peer`](super::Peer)
+    pub peer_channel_buffer_size: usize,
+    /// Continue the traces of the peer, and let the peer continue ours
+    pub trace_propagator: Option<Arc<dyn TracePropagator>>,
+    /// The limit of the binary content in the results sent to the peer
+    pub blob_limit: Option<BlobLimit>,
+}
+
+impl ServeConfig {
+    pub const DEFAULT_SINK_BUFFER_SIZE: usize = 64;
+    pub const DEFAULT_PEER_CHANNEL_BUFFER_SIZE: usize = 1024;
+
+    pub fn with_max_in_flight_requests(mut self, max: usize) -> Self {
+        self.max_in_flight_requests = Some(max);
+        self
+    }
+
+    pub fn with_method_limit(mut self, method: impl Into<String>, max: usize) -> Self {
+        self.method_limits.insert(method.into(), max);
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    pub fn with_sink_buffer_size(mut self, size: usize) -> Self {
+        self.sink_buffer_size = size;
+        self
+    }
+
+    pub fn with_peer_channel_buffer_size(mut self, size: usize) -> Self {
+        self.peer_channel_buffer_size = size;
+        self
+    }
+
+    pub fn with_trace_propagator(mut self, propagator: impl TracePropagator) -> Self {
+        self.trace_propagator = Some(Arc::new(propagator));
+        self
+    }
+
+    pub fn with_blob_limit(mut self, limit: BlobLimit) -> Self {
+        self.blob_limit = Some(limit);
+        self
+    }
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight_requests: None,
+            method_limits: HashMap::new(),
+            overflow_policy: OverflowPolicy::default(),
+            sink_buffer_size: Self::DEFAULT_SINK_BUFFER_SIZE,
+            peer_channel_buffer_size: Self::DEFAULT_PEER_CHANNEL_BUFFER_SIZE,
+            trace_propagator: None,
+            blob_limit: None,
+        }
+    }
+}
+
+/// The limits of a [`ServeConfig`], shared by the requests of a peer.
+#[derive(Debug)]
+pub(crate) struct RequestLimiter {
+    in_flight: Option<(usize, Arc<Semaphore>)>,
+    methods: HashMap<String, (usize, Arc<Semaphore>)>,
+    policy: OverflowPolicy,
+}
+
+/// Held while a request is handled.
+pub(crate) type RequestPermit = (Option<OwnedSemaphorePermit>, Option<OwnedSemaphorePermit>);
+
+impl RequestLimiter {
+    pub(crate) fn new(config: &ServeConfig) -> Self {
+        let limit = |max: usize| (max, Arc::new(Semaphore::new(max)));
+        Self {
+            in_flight: config.max_in_flight_requests.map(limit),
+            methods: config
+                .method_limits
+                .iter()
+                .map(|(method, max)| (method.clone(), limit(*max)))
+                .collect(),
+            policy: config.overflow_policy,
+        }
+    }
+
+    /// Wait for the request to be allowed, `None` if it was cancelled meanwhile.
+    pub(crate) async fn acquire(
+        &self,
+        method: &str,
+        ct: &CancellationToken,
+    ) -> Option<Result<RequestPermit, McpError>> {
+        let method_limit = self.methods.get(method);
+        match self.policy {
+            OverflowPolicy::Reject => {
+                let try_acquire = |limit: Option<&(usize, Arc<Semaphore>)>| match limit {
+                    Some((max, semaphore)) => semaphore
+                        .clone()
+                        .try_acquire_owned()
+                        .map(Some)
+                        .map_err(|_| *max),
+                    None => Ok(None),
+                };
+                let result = try_acquire(method_limit).and_then(|method_permit| {
+                    Ok((method_permit, try_acquire(self.in_flight.as_ref())?))
+                });
+                Some(result.map_err(|max| {
+                    McpError::internal_error(
+                        format!("too many requests in flight, at most {max} allowed"),
+                        Some(serde_json::json!({
+                            "reason": OverflowPolicy::REJECTED_REASON,
+                            "method": method,
+                            "limit": max,
+                        })),
+                    )
+                }))
+            }
+            OverflowPolicy::Queue => {
+                let acquire = async |limit: Option<&(usize, Arc<Semaphore>)>| match limit {
+                    // the semaphores are never closed
+                    Some((_, semaphore)) => semaphore.clone().acquire_owned().await.ok(),
+                    None => None,
+                };
+                tokio::select! {
+                    permit = async {
+                        let method_permit = acquire(method_limit).await;
+                        (method_permit, acquire(self.in_flight.as_ref()).await)
+                    } => Some(Ok(permit)),
+                    _ = ct.cancelled() => None,
+                }
+            }
+        }
+    }
+}
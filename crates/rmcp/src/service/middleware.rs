@@ -0,0 +1,311 @@
+//! Wrap a service with cross-cutting behaviors
+//!
+//! A [`Middleware`] sits in front of a [`Service`] and sees every request and
+//! notification received from the peer before the service does, along with the
+//! response or error the service produced. It can change any of them, answer a
+//! request by itself, or reject it. It also sees the requests and notifications the
+//! service sends to the peer through its [`Peer`](super::Peer), before they go out.
+//!
+//! Middlewares are stacked with [`ServiceExt::with_middleware`](super::ServiceExt::with_middleware),
+//! the last one added being the first to see a request:
+//!
+//! ```rust,no_run
+//! # use std::time::Duration;
+//! # use rmcp::{ServerHandler, ServiceExt, service::middleware::{ConcurrencyLimitMiddleware, TimeoutMiddleware, TracingMiddleware}};
+//! # #[derive(Clone)]
+//! # struct Server;
+//! # impl ServerHandler for Server {}
+//! # async fn run() -> anyhow::Result<()> {
+//! let service = Server
+//!     .with_middleware(TimeoutMiddleware::new(Duration::from_secs(30)))
+//!     .with_middleware(ConcurrencyLimitMiddleware::new(16))
+//!     .with_middleware(TracingMiddleware)
+//!     .serve((tokio::io::stdin(), tokio::io::stdout()))
+//!     .await?;
+//! service.waiting().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{sync::Arc, time::Duration};
+
+use futures::future::BoxFuture;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+
+use super::{DynService, NotificationContext, RequestContext, Service, ServiceRole};
+use crate::{error::ErrorData as McpError, model::GetMethod};
+
+/// The rest of the stack, down to the wrapped service.
+pub struct Next<'a, R: ServiceRole> {
+    service: &'a dyn DynService<R>,
+}
+
+impl<R: ServiceRole> std::fmt::Debug for Next<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Next").finish_non_exhaustive()
+    }
+}
+
+impl<'a, R: ServiceRole> Next<'a, R> {
+    /// Pass a request to the rest of the stack.
+    pub fn run(
+        self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> BoxFuture<'a, Result<R::Resp, McpError>> {
+        self.service.handle_request(request, context)
+    }
+
+    /// Pass a notification to the rest of the stack.
+    pub fn notify(
+        self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> BoxFuture<'a, Result<(), McpError>> {
+        self.service.handle_notification(notification, context)
+    }
+}
+
+/// Intercept what a service receives, responds and sends.
+///
+/// Every method defaults to passing through to the rest of the stack. The info a
+/// server sends to its client is its response to the `initialize` request, which
+/// goes through [`Middleware::handle_request`] like any other.
+///
+/// The outgoing requests and notifications are seen by the middleware added first,
+/// then by the others in turn, the reverse of the incoming ones.
+#[allow(unused_variables)]
+pub trait Middleware<R: ServiceRole>: Send + Sync + 'static {
+    fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> impl Future<Output = Result<R::Resp, McpError>> + Send + 'a {
+        next.run(request, context)
+    }
+
+    fn handle_notification<'a>(
+        &'a self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+        next: Next<'a, R>,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + 'a {
+        next.notify(notification, context)
+    }
+
+    /// See a request sent to the peer, an error fails it without sending it.
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        Ok(())
+    }
+
+    /// See a notification sent to the peer, an error fails it without sending it.
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        Ok(())
+    }
+}
+
+/// A service wrapped by a [`Middleware`], created by [`ServiceExt::with_middleware`].
+///
+/// [`ServiceExt::with_middleware`]: super::ServiceExt::with_middleware
+#[derive(Debug, Clone)]
+pub struct Layered<S, M> {
+    inner: S,
+    middleware: M,
+}
+
+impl<S, M> Layered<S, M> {
+    pub fn new(inner: S, middleware: M) -> Self {
+        Self { inner, middleware }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<R: ServiceRole, S: Service<R>, M: Middleware<R>> Service<R> for Layered<S, M> {
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> impl Future<Output = Result<R::Resp, McpError>> + Send + '_ {
+        self.middleware.handle_request(
+            request,
+            context,
+            Next {
+                service: &self.inner,
+            },
+        )
+    }
+
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
+        self.middleware.handle_notification(
+            notification,
+            context,
+            Next {
+                service: &self.inner,
+            },
+        )
+    }
+
+    fn get_info(&self) -> R::Info {
+        self.inner.get_info()
+    }
+
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        self.inner.handle_outgoing_request(request)?;
+        self.middleware.handle_outgoing_request(request)
+    }
+
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        self.inner.handle_outgoing_notification(notification)?;
+        self.middleware.handle_outgoing_notification(notification)
+    }
+}
+
+/// Handle each request in a span, and log how long it took and how it ended.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingMiddleware;
+
+impl<R: ServiceRole> Middleware<R> for TracingMiddleware {
+    async fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> Result<R::Resp, McpError> {
+        let span = tracing::info_span!("mcp_request", method = request.method(), id = %context.id);
+        async move {
+            let start = std::time::Instant::now();
+            let result = next.run(request, context).await;
+            let elapsed = start.elapsed();
+            match &result {
+                Ok(_) => tracing::info!(?elapsed, "request handled"),
+                Err(error) => {
+                    tracing::warn!(?elapsed, code = error.code.0, %error.message, "request failed")
+                }
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn handle_notification<'a>(
+        &'a self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+        next: Next<'a, R>,
+    ) -> Result<(), McpError> {
+        let span = tracing::info_span!("mcp_notification", method = notification.method());
+        async move {
+            let result = next.notify(notification, context).await;
+            if let Err(error) = &result {
+                tracing::warn!(code = error.code.0, %error.message, "notification failed");
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    fn handle_outgoing_request(&self, request: &mut R::Req) -> Result<(), McpError> {
+        tracing::debug!(method = request.method(), "sending request");
+        Ok(())
+    }
+
+    fn handle_outgoing_notification(&self, notification: &mut R::Not) -> Result<(), McpError> {
+        tracing::debug!(method = notification.method(), "sending notification");
+        Ok(())
+    }
+}
+
+/// Fail the requests which take longer than a timeout.
+///
+/// The request is stopped and its cancellation token is cancelled, and the peer
+/// receives an internal error.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeoutMiddleware {
+    pub timeout: Duration,
+}
+
+impl TimeoutMiddleware {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<R: ServiceRole> Middleware<R> for TimeoutMiddleware {
+    async fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        mut context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> Result<R::Resp, McpError> {
+        // a child token, cancelling the request's own would drop the error response
+        let ct = context.ct.child_token();
+        context.ct = ct.clone();
+        match tokio::time::timeout(self.timeout, next.run(request, context)).await {
+            Ok(result) => result,
+            Err(_) => {
+                ct.cancel();
+                Err(McpError::internal_error(
+                    format!("request timed out after {:?}", self.timeout),
+                    None,
+                ))
+            }
+        }
+    }
+}
+
+/// Handle at most a number of requests at the same time, the others wait for their turn.
+///
+/// Notifications are not limited. Clones share the same limit.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitMiddleware {
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitMiddleware {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_requests)),
+        }
+    }
+
+    /// The number of requests that can start right away.
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+impl<R: ServiceRole> Middleware<R> for ConcurrencyLimitMiddleware {
+    async fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> Result<R::Resp, McpError> {
+        let _permit = tokio::select! {
+            permit = self.semaphore.acquire() => permit
+                .map_err(|_| McpError::internal_error("concurrency limit closed", None))?,
+            _ = context.ct.cancelled() => {
+                return Err(McpError::internal_error("request cancelled while queued", None));
+            }
+        };
+        next.run(request, context).await
+    }
+}
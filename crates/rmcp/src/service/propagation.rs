@@ -0,0 +1,69 @@
+//! Continue traces across the peers
+//!
+//! With a [`TracePropagator`] set in the [`ServeConfig`](super::ServeConfig), the
+//! requests sent by a service carry the [`TraceContext`] of the span they are sent
+//! from in their `_meta`, and the requests received are handled in a span whose
+//! parent is the context they carry.
+//!
+//! The `trace-opentelemetry` feature provides [`OpenTelemetryPropagator`], for spans
+//! exported with [`tracing-opentelemetry`](https://docs.rs/tracing-opentelemetry).
+use crate::model::TraceContext;
+
+/// Convert between the spans of this process and the trace contexts sent to the peer.
+pub trait TracePropagator: Send + Sync + 'static {
+    /// The trace context to send along a request sent from `span`.
+    fn inject(&self, span: &tracing::Span) -> Option<TraceContext>;
+    /// Make the trace context received with a request the parent of `span`.
+    fn extract(&self, context: &TraceContext, span: &tracing::Span);
+}
+
+impl std::fmt::Debug for dyn TracePropagator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TracePropagator").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "trace-opentelemetry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "trace-opentelemetry")))]
+pub use self::opentelemetry::OpenTelemetryPropagator;
+
+#[cfg(feature = "trace-opentelemetry")]
+mod opentelemetry {
+    use std::collections::HashMap;
+
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    use super::TracePropagator;
+    use crate::model::TraceContext;
+
+    /// Propagate the OpenTelemetry context of the spans, with the global text map
+    /// propagator of [`opentelemetry::global`].
+    ///
+    /// The global propagator should be a `TraceContextPropagator`, it is a no-op by default.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct OpenTelemetryPropagator;
+
+    impl TracePropagator for OpenTelemetryPropagator {
+        fn inject(&self, span: &tracing::Span) -> Option<TraceContext> {
+            let context = span.context();
+            let mut carrier = HashMap::new();
+            ::opentelemetry::global::get_text_map_propagator(|propagator| {
+                propagator.inject_context(&context, &mut carrier)
+            });
+            let traceparent = carrier.remove("traceparent")?;
+            Some(TraceContext::new(traceparent, carrier.remove("tracestate")))
+        }
+
+        fn extract(&self, context: &TraceContext, span: &tracing::Span) {
+            let mut carrier =
+                HashMap::from([("traceparent".to_owned(), context.traceparent.clone())]);
+            if let Some(tracestate) = &context.tracestate {
+                carrier.insert("tracestate".to_owned(), tracestate.clone());
+            }
+            let parent = ::opentelemetry::global::get_text_map_propagator(|propagator| {
+                propagator.extract(&carrier)
+            });
+            let _ = span.set_parent(parent);
+        }
+    }
+}
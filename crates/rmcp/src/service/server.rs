@@ -16,7 +16,7 @@ use crate::{
         ProgressNotification, ProgressNotificationParam, PromptListChangedNotification,
         ProtocolVersion, ResourceListChangedNotification, ResourceUpdatedNotification,
         ResourceUpdatedNotificationParam, ServerInfo, ServerNotification, ServerRequest,
-        ServerResult, ToolListChangedNotification,
+        ServerResult, Task, TaskStatusNotification, ToolListChangedNotification,
     },
     transport::DynamicTransportError,
 };
@@ -85,9 +85,10 @@ impl ServerInitializeError {
 pub type ClientSink = Peer<RoleServer>;
 
 impl<S: Service<RoleServer>> ServiceExt<RoleServer> for S {
-    fn serve_with_ct<T, E, A>(
+    fn serve_with_config<T, E, A>(
         self,
         transport: T,
+        config: ServeConfig,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<RoleServer, Self>, ServerInitializeError>> + Send
     where
@@ -95,7 +96,7 @@ impl<S: Service<RoleServer>> ServiceExt<RoleServer> for S {
         E: std::error::Error + Send + Sync + 'static,
         Self: Sized,
     {
-        serve_server_with_ct(self, transport, ct)
+        serve_server_with_config(self, transport, config, ct)
     }
 }
 
@@ -162,13 +163,27 @@ pub async fn serve_server_with_ct<S, T, E, A>(
     transport: T,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
+where
+    S: Service<RoleServer>,
+    T: IntoTransport<RoleServer, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    serve_server_with_config(service, transport, ServeConfig::default(), ct).await
+}
+
+pub async fn serve_server_with_config<S, T, E, A>(
+    service: S,
+    transport: T,
+    config: ServeConfig,
+    ct: CancellationToken,
+) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
 where
     S: Service<RoleServer>,
     T: IntoTransport<RoleServer, E, A>,
     E: std::error::Error + Send + Sync + 'static,
 {
     tokio::select! {
-        result = serve_server_with_ct_inner(service, transport.into_transport(), ct.clone()) => { result }
+        result = serve_server_with_ct_inner(service, transport.into_transport(), config, ct.clone()) => { result }
         _ = ct.cancelled() => {
             Err(ServerInitializeError::Cancelled)
         }
@@ -178,6 +193,7 @@ where
 async fn serve_server_with_ct_inner<S, T>(
     service: S,
     transport: T,
+    config: ServeConfig,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
 where
@@ -195,7 +211,26 @@ where
             ClientJsonRpcMessage::request(request, id),
         )));
     };
-    let (peer, peer_rx) = Peer::new(id_provider, Some(peer_info.params.clone()));
+    let requested_version = peer_info.params.protocol_version.clone();
+    if !requested_version.is_known() {
+        let error = ErrorData::invalid_params(
+            "Unsupported protocol version",
+            Some(serde_json::json!({
+                "supported": ProtocolVersion::KNOWN,
+                "requested": requested_version,
+            })),
+        );
+        transport
+            .send(ServerJsonRpcMessage::error(error, id))
+            .await
+            .map_err(|error| {
+                ServerInitializeError::transport::<T>(error, "sending error response")
+            })?;
+        return Err(ServerInitializeError::UnsupportedProtocolVersion(
+            requested_version,
+        ));
+    }
+    let (peer, peer_rx) = Peer::new(id_provider, Some(peer_info.params.clone()), &config);
     let context = RequestContext {
         ct: ct.child_token(),
         id: id.clone(),
@@ -220,16 +255,14 @@ where
             return Err(ServerInitializeError::InitializeFailed(e));
         }
     };
-    let peer_protocol_version = peer_info.params.protocol_version.clone();
-    let protocol_version = match peer_protocol_version
-        .partial_cmp(&init_response.protocol_version)
-        .ok_or(ServerInitializeError::UnsupportedProtocolVersion(
-            peer_protocol_version,
-        ))? {
-        std::cmp::Ordering::Less => peer_info.params.protocol_version.clone(),
-        _ => init_response.protocol_version,
-    };
-    init_response.protocol_version = protocol_version;
+    let protocol_version =
+        ProtocolVersion::negotiate(&requested_version, &init_response.protocol_version)
+            .ok_or_else(|| {
+                ServerInitializeError::UnsupportedProtocolVersion(requested_version.clone())
+            })?;
+    init_response.protocol_version = protocol_version.clone();
+    init_response.downgrade(&protocol_version);
+    peer.set_protocol_version(protocol_version);
     transport
         .send(ServerJsonRpcMessage::response(
             ServerResult::InitializeResult(init_response),
@@ -254,7 +287,7 @@ where
     };
     let _ = service.handle_notification(notification, context).await;
     // Continue processing service
-    Ok(serve_inner(service, transport, peer, peer_rx, ct))
+    Ok(serve_inner(service, transport, peer, peer_rx, config, ct))
 }
 
 macro_rules! method {
@@ -341,7 +374,7 @@ macro_rules! method {
             });
             let options = crate::service::PeerRequestOptions {
                 timeout,
-                meta: None,
+                ..Default::default()
             };
             let result = self
                 .send_request_with_option(request, options)
@@ -368,7 +401,7 @@ macro_rules! method {
             });
             let options = crate::service::PeerRequestOptions {
                 timeout,
-                meta: None,
+                ..Default::default()
             };
             let result = self
                 .send_request_with_option(request, options)
@@ -413,6 +446,7 @@ impl Peer<RoleServer> {
     method!(peer_not notify_resource_list_changed ResourceListChangedNotification);
     method!(peer_not notify_tool_list_changed ToolListChangedNotification);
     method!(peer_not notify_prompt_list_changed PromptListChangedNotification);
+    method!(peer_not notify_task_status TaskStatusNotification(Task));
 }
 
 // =============================================================================
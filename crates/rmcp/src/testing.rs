@@ -0,0 +1,816 @@
+//! Check that a server conforms to the protocol
+//!
+//! A [`ConformanceSuite`] connects to a server as a client, runs a scripted set of
+//! checks against it and returns a [`ConformanceReport`]:
+//!
+//! - the initialize handshake and ping
+//! - listing tools, prompts, resources and resource templates, following their pages
+//! - the error codes of calls to unknown tools, prompts and resources
+//! - reading resources, getting prompts, completions, subscriptions and logging
+//!   levels, when advertised
+//! - the tool calls of the script, with their progress notifications
+//! - the cancellation of a call
+//! - the `list_changed` notifications, which should arrive if and only if advertised
+//!
+//! The suite doesn't call the tools of the server unless asked to, as they may have
+//! side effects.
+//!
+//! ```rust,ignore
+//! let report = ConformanceSuite::new()
+//!     .with_tool_call(CallToolRequestParam {
+//!         name: "sum".into(),
+//!         arguments: serde_json::json!({ "a": 1, "b": 2 }).as_object().cloned(),
+//!     })
+//!     .run(MyServer::new())
+//!     .await;
+//! assert!(report.is_conformant(), "{report}");
+//! ```
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::{
+    ClientHandler, Peer, RoleClient, RoleServer, Service, ServiceError, ServiceExt,
+    model::{
+        ArgumentInfo, CallToolRequestParam, ClientInfo, ErrorCode, GetPromptRequestParam,
+        LoggingLevel, PaginatedRequestParam, ProtocolVersion, ReadResourceRequestParam, Reference,
+        ServerInfo, SetLevelRequestParam, SubscribeRequestParam, UnsubscribeRequestParam,
+    },
+    service::{NotificationContext, RunningService},
+    transport::IntoTransport,
+};
+
+/// The lists of the server which may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListKind {
+    Tools,
+    Prompts,
+    Resources,
+}
+
+impl ListKind {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn notification(self) -> &'static str {
+        match self {
+            ListKind::Tools => "notifications/tools/list_changed",
+            ListKind::Prompts => "notifications/prompts/list_changed",
+            ListKind::Resources => "notifications/resources/list_changed",
+        }
+    }
+
+    fn is_advertised(self, info: &ServerInfo) -> bool {
+        let capabilities = &info.capabilities;
+        let list_changed = match self {
+            ListKind::Tools => capabilities.tools.as_ref().and_then(|c| c.list_changed),
+            ListKind::Prompts => capabilities.prompts.as_ref().and_then(|c| c.list_changed),
+            ListKind::Resources => capabilities.resources.as_ref().and_then(|c| c.list_changed),
+        };
+        list_changed == Some(true)
+    }
+}
+
+/// How a check ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum CheckOutcome {
+    Passed,
+    Failed {
+        reason: String,
+    },
+    /// The check doesn't apply to the server, or to the script
+    Skipped {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: CheckOutcome,
+}
+
+/// The checks run by a [`ConformanceSuite`], in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConformanceReport {
+    /// The result of the initialize handshake, if it succeeded
+    pub server_info: Option<ServerInfo>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConformanceReport {
+    /// Whether no check failed.
+    pub fn is_conformant(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, CheckOutcome::Failed { .. }))
+    }
+
+    /// The outcome of the check named `name`.
+    pub fn check(&self, name: &str) -> Option<&CheckOutcome> {
+        self.checks
+            .iter()
+            .find(|check| check.name == name)
+            .map(|check| &check.outcome)
+    }
+}
+
+impl std::fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            match &check.outcome {
+                CheckOutcome::Passed => writeln!(f, "[pass] {}", check.name)?,
+                CheckOutcome::Failed { reason } => writeln!(f, "[FAIL] {}: {reason}", check.name)?,
+                CheckOutcome::Skipped { reason } => writeln!(f, "[skip] {}: {reason}", check.name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A scripted set of checks of a server.
+#[derive(Debug, Clone)]
+pub struct ConformanceSuite {
+    timeout: Duration,
+    tool_calls: Vec<CallToolRequestParam>,
+    cancellable_tool_call: Option<CallToolRequestParam>,
+    list_changed_triggers: Vec<(ListKind, CallToolRequestParam)>,
+}
+
+impl Default for ConformanceSuite {
+    fn default() -> Self {
+        Self {
+            timeout: Self::DEFAULT_TIMEOUT,
+            tool_calls: Vec::new(),
+            cancellable_tool_call: None,
+            list_changed_triggers: Vec::new(),
+        }
+    }
+}
+
+impl ConformanceSuite {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long to wait for each response or notification of the server.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call a tool, which should succeed, report its progress in order, and return a
+    /// structured content if it has an output schema.
+    pub fn with_tool_call(mut self, call: CallToolRequestParam) -> Self {
+        self.tool_calls.push(call);
+        self
+    }
+
+    /// Call a tool and cancel the call right away, the server should keep answering
+    /// afterwards. The tool should last long enough to be cancelled.
+    pub fn with_cancellable_tool_call(mut self, call: CallToolRequestParam) -> Self {
+        self.cancellable_tool_call = Some(call);
+        self
+    }
+
+    /// Call a tool changing the list of `kind`, the server should then send a
+    /// `list_changed` notification if it advertises them, and none otherwise.
+    pub fn with_list_changed_trigger(mut self, kind: ListKind, call: CallToolRequestParam) -> Self {
+        self.list_changed_triggers.push((kind, call));
+        self
+    }
+
+    /// Run the suite against a service, served in process.
+    pub async fn run<S: Service<RoleServer>>(&self, server: S) -> ConformanceReport {
+        let (server_transport, client_transport) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(async move {
+            if let Ok(running) = server.serve(server_transport).await {
+                let _ = running.waiting().await;
+            }
+        });
+        let report = self.run_transport(client_transport).await;
+        server.abort();
+        report
+    }
+
+    /// Run the suite against the server of a command, spawned as a child process.
+    #[cfg(feature = "transport-child-process")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "transport-child-process")))]
+    pub async fn run_command(
+        &self,
+        command: tokio::process::Command,
+    ) -> std::io::Result<ConformanceReport> {
+        let transport = crate::transport::TokioChildProcess::new(command)?;
+        Ok(self.run_transport(transport).await)
+    }
+
+    /// Run the suite against the server at the other end of a transport.
+    pub async fn run_transport<T, E, A>(&self, transport: T) -> ConformanceReport
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let probe = Probe::default();
+        let mut run = Run {
+            suite: self,
+            checks: Vec::new(),
+        };
+        let client = match run.within(probe.clone().serve(transport)).await {
+            Ok(client) => client,
+            Err(reason) => {
+                run.fail("initialize", reason);
+                return run.report(None);
+            }
+        };
+        let info = client.peer_info().cloned();
+        match &info {
+            Some(info) => run.check_initialize(info),
+            None => run.fail("initialize", "the server info is missing"),
+        }
+        if let Some(info) = &info {
+            run.run_checks(&client, &probe, info).await;
+        }
+        let _ = client.cancel().await;
+        run.report(info)
+    }
+}
+
+/// The client of the suite, counting the notifications of the server.
+#[derive(Debug, Clone, Default)]
+struct Probe {
+    list_changed: Arc<[AtomicUsize; 3]>,
+    notified: Arc<Notify>,
+}
+
+impl Probe {
+    fn list_changed(&self, kind: ListKind) -> usize {
+        self.list_changed[kind.index()].load(Ordering::SeqCst)
+    }
+
+    fn on_list_changed(&self, kind: ListKind) {
+        self.list_changed[kind.index()].fetch_add(1, Ordering::SeqCst);
+        self.notified.notify_waiters();
+    }
+
+    /// Wait for the count of `kind` to exceed `count`, `false` after `timeout`.
+    async fn wait_list_changed(&self, kind: ListKind, count: usize, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                // created before checking the count, so no notification is missed
+                let notified = self.notified.notified();
+                if self.list_changed(kind) > count {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+impl ClientHandler for Probe {
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.on_list_changed(ListKind::Tools);
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.on_list_changed(ListKind::Prompts);
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.on_list_changed(ListKind::Resources);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+}
+
+fn error_code(error: &ServiceError) -> Option<ErrorCode> {
+    match error {
+        ServiceError::McpError(error) => Some(error.code),
+        _ => None,
+    }
+}
+
+/// The pages of a list should end, without repeating a cursor.
+const MAX_PAGES: usize = 1000;
+
+struct Run<'a> {
+    suite: &'a ConformanceSuite,
+    checks: Vec<CheckResult>,
+}
+
+impl Run<'_> {
+    fn report(self, server_info: Option<ServerInfo>) -> ConformanceReport {
+        ConformanceReport {
+            server_info,
+            checks: self.checks,
+        }
+    }
+
+    fn record(&mut self, name: impl Into<String>, outcome: CheckOutcome) {
+        self.checks.push(CheckResult {
+            name: name.into(),
+            outcome,
+        });
+    }
+
+    fn pass(&mut self, name: impl Into<String>) {
+        self.record(name, CheckOutcome::Passed);
+    }
+
+    fn fail(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.record(name, CheckOutcome::Failed { reason });
+    }
+
+    fn skip(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.record(name, CheckOutcome::Skipped { reason });
+    }
+
+    fn outcome(&mut self, name: impl Into<String>, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.pass(name),
+            Err(reason) => self.fail(name, reason),
+        }
+    }
+
+    async fn within<T, E: std::fmt::Display>(
+        &self,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, String> {
+        match tokio::time::timeout(self.suite.timeout, future).await {
+            Ok(result) => result.map_err(|error| error.to_string()),
+            Err(_) => Err(format!("no answer after {:?}", self.suite.timeout)),
+        }
+    }
+
+    /// Expect an error with `expected` code.
+    async fn expect_error<T: std::fmt::Debug>(
+        &self,
+        future: impl Future<Output = Result<T, ServiceError>>,
+        expected: ErrorCode,
+    ) -> Result<(), String> {
+        match tokio::time::timeout(self.suite.timeout, future).await {
+            Err(_) => Err(format!("no answer after {:?}", self.suite.timeout)),
+            Ok(Ok(result)) => Err(format!(
+                "expected an error with code {}, got {result:?}",
+                expected.0
+            )),
+            Ok(Err(error)) => match error_code(&error) {
+                Some(code) if code == expected => Ok(()),
+                _ => Err(format!(
+                    "expected an error with code {}, got {error}",
+                    expected.0
+                )),
+            },
+        }
+    }
+
+    /// Follow the pages of a list, checking that it ends and that the names are unique.
+    async fn list_all<T, F, Fut>(
+        &self,
+        list: F,
+        name: impl Fn(&T) -> String,
+    ) -> Result<Vec<T>, String>
+    where
+        F: Fn(Option<PaginatedRequestParam>) -> Fut,
+        Fut: Future<Output = Result<(Vec<T>, Option<String>), ServiceError>>,
+    {
+        let mut items = Vec::new();
+        let mut names = HashSet::new();
+        let mut cursors = HashSet::new();
+        let mut cursor = None;
+        for _ in 0..MAX_PAGES {
+            let params = cursor.clone().map(|cursor| PaginatedRequestParam {
+                cursor: Some(cursor),
+            });
+            let (page, next_cursor) = self.within(list(params)).await?;
+            for item in page {
+                let name = name(&item);
+                if !names.insert(name.clone()) {
+                    return Err(format!("{name} is listed twice"));
+                }
+                items.push(item);
+            }
+            match next_cursor {
+                None => return Ok(items),
+                Some(next_cursor) if !cursors.insert(next_cursor.clone()) => {
+                    return Err(format!("the cursor {next_cursor} is repeated"));
+                }
+                next_cursor => cursor = next_cursor,
+            }
+        }
+        Err(format!("more than {MAX_PAGES} pages"))
+    }
+
+    fn check_initialize(&mut self, info: &ServerInfo) {
+        let result = if !ProtocolVersion::KNOWN.contains(&info.protocol_version) {
+            Err(format!(
+                "unknown protocol version {}",
+                info.protocol_version
+            ))
+        } else if info.server_info.name.is_empty() {
+            Err("the server name is empty".to_owned())
+        } else {
+            Ok(())
+        };
+        self.outcome("initialize", result);
+    }
+
+    async fn run_checks(
+        &mut self,
+        client: &RunningService<RoleClient, Probe>,
+        probe: &Probe,
+        info: &ServerInfo,
+    ) {
+        let peer = client.peer();
+        let result = self
+            .within(peer.send_request(crate::model::ClientRequest::PingRequest(Default::default())))
+            .await
+            .map(drop);
+        self.outcome("ping", result);
+        self.check_tools(peer, info).await;
+        self.check_prompts(peer, info).await;
+        self.check_resources(peer, info).await;
+        self.check_logging(peer, info).await;
+        self.check_cancellation(peer).await;
+        self.check_list_changed(peer, probe, info).await;
+    }
+
+    async fn check_tools(&mut self, peer: &Peer<RoleClient>, info: &ServerInfo) {
+        if info.capabilities.tools.is_none() {
+            for name in ["tools/list", "tools/call unknown tool"] {
+                self.skip(name, "tools are not advertised");
+            }
+            for call in &self.suite.tool_calls {
+                self.skip(
+                    format!("tools/call {}", call.name),
+                    "tools are not advertised",
+                );
+            }
+            return;
+        }
+        let tools = self
+            .list_all(
+                |params| async move {
+                    let result = peer.list_tools(params).await?;
+                    Ok((result.tools, result.next_cursor))
+                },
+                |tool| tool.name.to_string(),
+            )
+            .await
+            .and_then(|tools| {
+                match tools
+                    .iter()
+                    .find(|tool| tool.input_schema.get("type") != Some(&"object".into()))
+                {
+                    Some(tool) => Err(format!(
+                        "the input schema of {} isn't of type object",
+                        tool.name
+                    )),
+                    None => Ok(tools),
+                }
+            });
+        let tools = match tools {
+            Ok(tools) => {
+                self.pass("tools/list");
+                tools
+            }
+            Err(reason) => {
+                self.fail("tools/list", reason);
+                Vec::new()
+            }
+        };
+
+        let result = self
+            .expect_error(
+                peer.call_tool(CallToolRequestParam {
+                    name: "conformance/unknown-tool".into(),
+                    arguments: None,
+                }),
+                ErrorCode::INVALID_PARAMS,
+            )
+            .await;
+        self.outcome("tools/call unknown tool", result);
+
+        for call in &self.suite.tool_calls {
+            let name = format!("tools/call {}", call.name);
+            let has_output_schema = tools
+                .iter()
+                .any(|tool| tool.name == call.name && tool.output_schema.is_some());
+            let result = self.call_tool(peer, call.clone(), has_output_schema).await;
+            self.outcome(name, result);
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        peer: &Peer<RoleClient>,
+        call: CallToolRequestParam,
+        has_output_schema: bool,
+    ) -> Result<(), String> {
+        let mut call = self.within(peer.call_tool_with_progress(call)).await?;
+        let mut progress = Vec::new();
+        while let Some(notification) = self
+            .within(async { Ok::<_, String>(call.next().await) })
+            .await?
+        {
+            progress.push(notification);
+        }
+        let result = self.within(call.result()).await?;
+        if result.is_error == Some(true) {
+            return Err(format!("the call failed: {:?}", result.content));
+        }
+        if has_output_schema && result.structured_content.is_none() {
+            return Err("the tool has an output schema but no structured content".to_owned());
+        }
+        if let Some(pair) = progress
+            .windows(2)
+            .find(|pair| pair[1].progress <= pair[0].progress)
+        {
+            return Err(format!(
+                "the progress went from {} to {}",
+                pair[0].progress, pair[1].progress
+            ));
+        }
+        if let Some(notification) = progress.iter().find(|notification| {
+            notification
+                .total
+                .is_some_and(|total| notification.progress > total)
+        }) {
+            return Err(format!(
+                "the progress {} is over the total {:?}",
+                notification.progress, notification.total
+            ));
+        }
+        Ok(())
+    }
+
+    async fn check_prompts(&mut self, peer: &Peer<RoleClient>, info: &ServerInfo) {
+        const CHECKS: [&str; 4] = [
+            "prompts/list",
+            "prompts/get",
+            "prompts/get unknown prompt",
+            "completion/complete",
+        ];
+        if info.capabilities.prompts.is_none() {
+            for name in CHECKS {
+                self.skip(name, "prompts are not advertised");
+            }
+            return;
+        }
+        let prompts = match self
+            .list_all(
+                |params| async move {
+                    let result = peer.list_prompts(params).await?;
+                    Ok((result.prompts, result.next_cursor))
+                },
+                |prompt| prompt.name.clone(),
+            )
+            .await
+        {
+            Ok(prompts) => {
+                self.pass("prompts/list");
+                prompts
+            }
+            Err(reason) => {
+                self.fail("prompts/list", reason);
+                Vec::new()
+            }
+        };
+
+        // the prompts without required arguments can be got without guessing them
+        let gettable: Vec<_> = prompts
+            .iter()
+            .filter(|prompt| {
+                prompt
+                    .arguments
+                    .iter()
+                    .flatten()
+                    .all(|argument| argument.required != Some(true))
+            })
+            .collect();
+        if gettable.is_empty() {
+            self.skip("prompts/get", "no prompt without required arguments");
+        } else {
+            let mut result = Ok(());
+            for prompt in gettable {
+                let get = peer.get_prompt(GetPromptRequestParam {
+                    name: prompt.name.clone(),
+                    arguments: None,
+                });
+                if let Err(reason) = self.within(get).await {
+                    result = Err(format!("{}: {reason}", prompt.name));
+                    break;
+                }
+            }
+            self.outcome("prompts/get", result);
+        }
+
+        let result = self
+            .expect_error(
+                peer.get_prompt(GetPromptRequestParam {
+                    name: "conformance/unknown-prompt".into(),
+                    arguments: None,
+                }),
+                ErrorCode::INVALID_PARAMS,
+            )
+            .await;
+        self.outcome("prompts/get unknown prompt", result);
+
+        let argument = prompts.iter().find_map(|prompt| {
+            let argument = prompt.arguments.as_ref()?.first()?;
+            Some((prompt.name.clone(), argument.name.clone()))
+        });
+        match (&info.capabilities.completions, argument) {
+            (None, _) => self.skip("completion/complete", "completions are not advertised"),
+            (Some(_), None) => self.skip("completion/complete", "no prompt with arguments"),
+            (Some(_), Some((prompt, argument))) => {
+                let complete = peer.complete(crate::model::CompleteRequestParam {
+                    r#ref: Reference::for_prompt(prompt),
+                    argument: ArgumentInfo {
+                        name: argument,
+                        value: String::new(),
+                    },
+                    context: None,
+                });
+                let result = self.within(complete).await.and_then(|result| {
+                    let values = result.completion.values.len();
+                    if values > crate::model::CompletionInfo::MAX_VALUES {
+                        Err(format!("{values} values, more than the maximum"))
+                    } else {
+                        Ok(())
+                    }
+                });
+                self.outcome("completion/complete", result);
+            }
+        }
+    }
+
+    async fn check_resources(&mut self, peer: &Peer<RoleClient>, info: &ServerInfo) {
+        const CHECKS: [&str; 5] = [
+            "resources/list",
+            "resources/templates/list",
+            "resources/read",
+            "resources/read unknown resource",
+            "resources/subscribe",
+        ];
+        let Some(capability) = &info.capabilities.resources else {
+            for name in CHECKS {
+                self.skip(name, "resources are not advertised");
+            }
+            return;
+        };
+        let resources = match self
+            .list_all(
+                |params| async move {
+                    let result = peer.list_resources(params).await?;
+                    Ok((result.resources, result.next_cursor))
+                },
+                |resource| resource.uri.clone(),
+            )
+            .await
+        {
+            Ok(resources) => {
+                self.pass("resources/list");
+                resources
+            }
+            Err(reason) => {
+                self.fail("resources/list", reason);
+                Vec::new()
+            }
+        };
+        let result = self
+            .list_all(
+                |params| async move {
+                    let result = peer.list_resource_templates(params).await?;
+                    Ok((result.resource_templates, result.next_cursor))
+                },
+                |template| template.uri_template.clone(),
+            )
+            .await
+            .map(drop);
+        self.outcome("resources/templates/list", result);
+
+        let first = resources.first().map(|resource| resource.uri.clone());
+        match &first {
+            None => self.skip("resources/read", "no resource listed"),
+            Some(uri) => {
+                let read = peer.read_resource(ReadResourceRequestParam { uri: uri.clone() });
+                let result = self.within(read).await.and_then(|result| {
+                    if result.contents.is_empty() {
+                        Err(format!("{uri} has no contents"))
+                    } else {
+                        Ok(())
+                    }
+                });
+                self.outcome("resources/read", result);
+            }
+        }
+
+        let result = self
+            .expect_error(
+                peer.read_resource(ReadResourceRequestParam {
+                    uri: "conformance://unknown-resource".into(),
+                }),
+                ErrorCode::RESOURCE_NOT_FOUND,
+            )
+            .await;
+        self.outcome("resources/read unknown resource", result);
+
+        match (capability.subscribe, first) {
+            (Some(true), Some(uri)) => {
+                let subscribe = async {
+                    peer.subscribe(SubscribeRequestParam { uri: uri.clone() })
+                        .await?;
+                    peer.unsubscribe(UnsubscribeRequestParam { uri }).await
+                };
+                let result = self.within(subscribe).await;
+                self.outcome("resources/subscribe", result);
+            }
+            (Some(true), None) => self.skip("resources/subscribe", "no resource listed"),
+            _ => self.skip("resources/subscribe", "subscriptions are not advertised"),
+        }
+    }
+
+    async fn check_logging(&mut self, peer: &Peer<RoleClient>, info: &ServerInfo) {
+        if info.capabilities.logging.is_none() {
+            self.skip("logging/setLevel", "logging is not advertised");
+            return;
+        }
+        let result = self
+            .within(peer.set_level(SetLevelRequestParam {
+                level: LoggingLevel::Info,
+            }))
+            .await;
+        self.outcome("logging/setLevel", result);
+    }
+
+    async fn check_cancellation(&mut self, peer: &Peer<RoleClient>) {
+        let Some(call) = self.suite.cancellable_tool_call.clone() else {
+            self.skip("cancellation", "no cancellable tool call in the script");
+            return;
+        };
+        let cancel = async {
+            let call = peer.call_tool_with_progress(call).await?;
+            call.cancel(Some("conformance check".to_owned())).await?;
+            // the server should still answer
+            peer.send_request(crate::model::ClientRequest::PingRequest(Default::default()))
+                .await
+        };
+        let result = self.within(cancel).await.map(drop);
+        self.outcome("cancellation", result);
+    }
+
+    async fn check_list_changed(
+        &mut self,
+        peer: &Peer<RoleClient>,
+        probe: &Probe,
+        info: &ServerInfo,
+    ) {
+        for (kind, call) in &self.suite.list_changed_triggers {
+            let name = kind.notification();
+            let count = probe.list_changed(*kind);
+            if let Err(reason) = self.within(peer.call_tool(call.clone())).await {
+                self.fail(name, format!("the call to {} failed: {reason}", call.name));
+                continue;
+            }
+            let advertised = kind.is_advertised(info);
+            // without the capability, wait as long for a notification which shouldn't come
+            let arrived = probe
+                .wait_list_changed(*kind, count, self.suite.timeout)
+                .await;
+            match (advertised, arrived) {
+                (true, true) | (false, false) => self.pass(name),
+                (true, false) => self.fail(
+                    name,
+                    format!("advertised, but not sent after calling {}", call.name),
+                ),
+                (false, true) => self.fail(name, "sent, but not advertised"),
+            }
+        }
+    }
+}
@@ -23,6 +23,11 @@
 //!
 //! This could be very helpful when you want to create a transport from a byte stream, such as a file or a tcp connection.
 //!
+//! ### [Record Transport](`record::RecordTransport`) and [Replay Transport](`record::ReplayTransport`)
+//! Record the messages of a transport to a JSONL file, and replay the file to a client or server under test.
+//!
+//! This could be very helpful when you want to turn a session seen in production into a regression test.
+//!
 //! ### [Sink/Stream Transport](`sink_stream::SinkStreamTransport`)
 //! This transport is used to create a transport from a sink and a stream.
 //!
@@ -70,6 +75,9 @@ use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
 
 pub mod sink_stream;
 
+pub mod record;
+pub use record::{RecordTransport, ReplayTransport};
+
 #[cfg(feature = "transport-async-rw")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-async-rw")))]
 pub mod async_rw;
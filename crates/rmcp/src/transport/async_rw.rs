@@ -342,6 +342,8 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
                     let newline_index = offset + self.next_index;
                     self.next_index = 0;
                     let line = buf.split_to(newline_index + 1);
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::record_transport_bytes("async_rw", "in", line.len());
                     let line = &line[..line.len() - 1];
                     let line = without_carriage_return(line);
 
@@ -397,8 +399,12 @@ impl<T: Serialize> Encoder<T> for JsonRpcMessageCodec<T> {
     type Error = JsonRpcMessageCodecError;
 
     fn encode(&mut self, item: T, buf: &mut BytesMut) -> Result<(), JsonRpcMessageCodecError> {
+        #[cfg(feature = "metrics")]
+        let start = buf.len();
         serde_json::to_writer(buf.writer(), &item)?;
         buf.put_u8(b'\n');
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_transport_bytes("async_rw", "out", buf.len() - start);
         Ok(())
     }
 }
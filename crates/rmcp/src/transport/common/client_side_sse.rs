@@ -228,6 +228,15 @@ where
                 retrying,
             } => {
                 let retry_result = ready!(retrying.poll(cx));
+                #[cfg(feature = "metrics")]
+                crate::metrics::increment_counter(
+                    crate::metrics::names::SSE_RECONNECTS,
+                    1,
+                    &[crate::metrics::Label::new(
+                        crate::metrics::labels::OUTCOME,
+                        if retry_result.is_ok() { "ok" } else { "error" },
+                    )],
+                );
                 match retry_result {
                     Ok(new_stream) => SseAutoReconnectStreamState::Connected { stream: new_stream },
                     Err(e) => {
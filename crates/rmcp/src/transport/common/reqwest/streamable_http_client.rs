@@ -6,10 +6,11 @@ use reqwest::header::ACCEPT;
 use sse_stream::{Sse, SseStream};
 
 use crate::{
-    model::{ClientJsonRpcMessage, ServerJsonRpcMessage},
+    model::{ClientJsonRpcMessage, GetMeta, ProtocolVersion, ServerJsonRpcMessage},
     transport::{
         common::http_header::{
-            EVENT_STREAM_MIME_TYPE, HEADER_LAST_EVENT_ID, HEADER_SESSION_ID, JSON_MIME_TYPE,
+            EVENT_STREAM_MIME_TYPE, HEADER_LAST_EVENT_ID, HEADER_MCP_PROTOCOL_VERSION,
+            HEADER_SESSION_ID, HEADER_TRACEPARENT, HEADER_TRACESTATE, JSON_MIME_TYPE,
         },
         streamable_http_client::*,
     },
@@ -30,6 +31,7 @@ impl StreamableHttpClient for reqwest::Client {
         session_id: Arc<str>,
         last_event_id: Option<String>,
         auth_token: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> Result<BoxStream<'static, Result<Sse, SseError>>, StreamableHttpError<Self::Error>> {
         let mut request_builder = self
             .get(uri.as_ref())
@@ -38,6 +40,10 @@ impl StreamableHttpClient for reqwest::Client {
         if let Some(last_event_id) = last_event_id {
             request_builder = request_builder.header(HEADER_LAST_EVENT_ID, last_event_id);
         }
+        if let Some(protocol_version) = protocol_version {
+            request_builder =
+                request_builder.header(HEADER_MCP_PROTOCOL_VERSION, protocol_version.as_str());
+        }
         if let Some(auth_header) = auth_token {
             request_builder = request_builder.bearer_auth(auth_header);
         }
@@ -58,7 +64,7 @@ impl StreamableHttpClient for reqwest::Client {
                 return Err(StreamableHttpError::UnexpectedContentType(None));
             }
         }
-        let event_stream = SseStream::from_byte_stream(response.bytes_stream()).boxed();
+        let event_stream = SseStream::from_bytes_stream(response.bytes_stream()).boxed();
         Ok(event_stream)
     }
 
@@ -67,11 +73,16 @@ impl StreamableHttpClient for reqwest::Client {
         uri: Arc<str>,
         session: Arc<str>,
         auth_token: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> Result<(), StreamableHttpError<Self::Error>> {
         let mut request_builder = self.delete(uri.as_ref());
         if let Some(auth_header) = auth_token {
             request_builder = request_builder.bearer_auth(auth_header);
         }
+        if let Some(protocol_version) = protocol_version {
+            request_builder =
+                request_builder.header(HEADER_MCP_PROTOCOL_VERSION, protocol_version.as_str());
+        }
         let response = request_builder
             .header(HEADER_SESSION_ID, session.as_ref())
             .send()
@@ -92,6 +103,7 @@ impl StreamableHttpClient for reqwest::Client {
         message: ClientJsonRpcMessage,
         session_id: Option<Arc<str>>,
         auth_token: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> Result<StreamableHttpPostResponse, StreamableHttpError<Self::Error>> {
         let mut request = self
             .post(uri.as_ref())
@@ -102,6 +114,17 @@ impl StreamableHttpClient for reqwest::Client {
         if let Some(session_id) = session_id {
             request = request.header(HEADER_SESSION_ID, session_id.as_ref());
         }
+        if let Some(protocol_version) = protocol_version {
+            request = request.header(HEADER_MCP_PROTOCOL_VERSION, protocol_version.as_str());
+        }
+        if let ClientJsonRpcMessage::Request(req) = &message {
+            if let Some(context) = req.request.get_meta().get_trace_context() {
+                request = request.header(HEADER_TRACEPARENT, context.traceparent);
+                if let Some(tracestate) = context.tracestate {
+                    request = request.header(HEADER_TRACESTATE, tracestate);
+                }
+            }
+        }
         let response = request.json(&message).send().await?;
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
             if let Some(header) = response.headers().get(WWW_AUTHENTICATE) {
@@ -129,7 +152,7 @@ impl StreamableHttpClient for reqwest::Client {
             .map(|s| s.to_string());
         match content_type {
             Some(ct) if ct.as_bytes().starts_with(EVENT_STREAM_MIME_TYPE.as_bytes()) => {
-                let event_stream = SseStream::from_byte_stream(response.bytes_stream()).boxed();
+                let event_stream = SseStream::from_bytes_stream(response.bytes_stream()).boxed();
                 Ok(StreamableHttpPostResponse::Sse(event_stream, session_id))
             }
             Some(ct) if ct.as_bytes().starts_with(JSON_MIME_TYPE.as_bytes()) => {
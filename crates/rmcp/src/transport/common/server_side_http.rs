@@ -69,6 +69,8 @@ pub(crate) fn sse_stream_response(
     use futures::StreamExt;
     let stream = SseBody::new(stream.map(|message| {
         let data = serde_json::to_string(&message.message).expect("valid message");
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_transport_bytes("streamable_http", "out", data.len());
         let mut sse = Sse::default().data(data);
         sse.id = message.event_id;
         Result::<Sse, Infallible>::Ok(sse)
@@ -120,7 +122,10 @@ where
 {
     match body.collect().await {
         Ok(bytes) => {
-            match serde_json::from_reader::<_, ClientJsonRpcMessage>(bytes.aggregate().reader()) {
+            let bytes = bytes.aggregate();
+            #[cfg(feature = "metrics")]
+            crate::metrics::record_transport_bytes("streamable_http", "in", bytes.remaining());
+            match serde_json::from_reader::<_, ClientJsonRpcMessage>(bytes.reader()) {
                 Ok(message) => Ok(message),
                 Err(e) => {
                     let response = Response::builder()
@@ -0,0 +1,341 @@
+//! Record the messages of a transport, and replay them in tests
+//!
+//! A [`RecordTransport`] wraps another transport and writes every message it sends or
+//! receives to a JSONL file, one [`RecordedMessage`] per line:
+//!
+//! ```json
+//! {"timestamp":"2025-06-18T12:00:00Z","direction":"sent","message":{"jsonrpc":"2.0","id":0,"method":"initialize","params":{...}}}
+//! ```
+//!
+//! A [`ReplayTransport`] serves such a recording back to a service under test, without
+//! running the peer it was recorded with:
+//!
+//! ```rust,ignore
+//! // record a session in production, `transport` being any `Transport<RoleClient>`
+//! let client = ().serve(RecordTransport::create(transport, "session.jsonl")?).await?;
+//!
+//! // replay it in a regression test
+//! let client = ().serve(ReplayTransport::from_file("session.jsonl")?).await?;
+//! ```
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Transport;
+use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
+
+/// Whether a message was sent or received by the recording transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+impl Direction {
+    pub fn reversed(self) -> Self {
+        match self {
+            Direction::Sent => Direction::Received,
+            Direction::Received => Direction::Sent,
+        }
+    }
+}
+
+/// A line of a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    pub message: Value,
+}
+
+/// Read the lines of a recording, skipping the blank ones.
+pub fn read_recording(reader: impl BufRead) -> std::io::Result<Vec<RecordedMessage>> {
+    let mut messages = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        messages.push(serde_json::from_str(&line)?);
+    }
+    Ok(messages)
+}
+
+/// A transport writing the messages of another transport to a recording.
+///
+/// Failing to write the recording is logged, and doesn't fail the transport.
+pub struct RecordTransport<T> {
+    inner: T,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl<T> RecordTransport<T> {
+    pub fn new(inner: T, writer: impl Write + Send + 'static) -> Self {
+        Self {
+            inner,
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// Record to the file at `path`, which is truncated.
+    pub fn create(inner: T, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Ok(Self::new(inner, std::io::LineWriter::new(file)))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+fn record(writer: &Mutex<Box<dyn Write + Send>>, direction: Direction, message: &impl Serialize) {
+    let result = serde_json::to_value(message)
+        .and_then(|message| {
+            serde_json::to_string(&RecordedMessage {
+                timestamp: Utc::now(),
+                direction,
+                message,
+            })
+        })
+        .map_err(std::io::Error::from)
+        .and_then(|line| {
+            let mut writer = writer.lock().expect("recording writer poisoned");
+            writeln!(writer, "{line}")
+        });
+    if let Err(error) = result {
+        tracing::warn!(%error, "fail to record message");
+    }
+}
+
+impl<R, T> Transport<R> for RecordTransport<T>
+where
+    R: ServiceRole,
+    T: Transport<R>,
+{
+    type Error = T::Error;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        record(&self.writer, Direction::Sent, &item);
+        self.inner.send(item)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<R>> {
+        let message = self.inner.receive().await?;
+        record(&self.writer, Direction::Received, &message);
+        Some(message)
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.inner.close().await
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("message not in the recording: {0}")]
+    Unexpected(Value),
+    #[error("fail to serialize message: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    /// The recorded messages, `None` once replayed or matched
+    messages: Vec<Option<RecordedMessage>>,
+    /// The ids of the recorded requests, to the ids of the requests they matched
+    request_ids: HashMap<Value, Value>,
+}
+
+/// A transport playing the peer of a recording.
+///
+/// A sent request or notification matches a recorded one with the same method and
+/// params, ignoring their `_meta`, and a sent response matches the recorded response
+/// with the same id. The response to a recorded request is delivered once the request
+/// is matched, with the id of the request it matched. The other received messages of
+/// the recording are delivered in order, each once the sent messages before it are
+/// matched.
+///
+/// The transport closes once the whole recording is replayed.
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    state: Arc<Mutex<ReplayState>>,
+    matched: Arc<tokio::sync::Notify>,
+}
+
+impl ReplayTransport {
+    /// Replay the peer of the recording, to a service of the same role as the recorder.
+    pub fn new(messages: impl IntoIterator<Item = RecordedMessage>) -> Self {
+        let state = ReplayState {
+            messages: messages.into_iter().map(Some).collect(),
+            request_ids: HashMap::new(),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            matched: Default::default(),
+        }
+    }
+
+    /// Replay the recorder itself, to a service of the role of its peer.
+    pub fn mirrored(messages: impl IntoIterator<Item = RecordedMessage>) -> Self {
+        Self::new(messages.into_iter().map(|mut message| {
+            message.direction = message.direction.reversed();
+            message
+        }))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(Self::new(read_recording(std::io::BufReader::new(file))?))
+    }
+
+    /// The messages of the recording not replayed or matched yet.
+    pub fn remaining(&self) -> Vec<RecordedMessage> {
+        let state = self.state.lock().expect("replay state poisoned");
+        state.messages.iter().flatten().cloned().collect()
+    }
+}
+
+/// The params without their `_meta`, which holds progress tokens and trace contexts.
+fn params_without_meta(message: &Value) -> Option<Value> {
+    let mut params = message.get("params")?.clone();
+    if let Some(object) = params.as_object_mut() {
+        object.remove("_meta");
+        if object.is_empty() {
+            return None;
+        }
+    }
+    Some(params)
+}
+
+fn is_response(message: &Value) -> bool {
+    message.get("result").is_some() || message.get("error").is_some()
+}
+
+enum Next {
+    Received(Value),
+    /// A sent message is expected first
+    Waiting,
+    Over,
+}
+
+impl ReplayState {
+    fn accept(&mut self, sent: &Value) -> bool {
+        let matches = |recorded: &Value| {
+            if is_response(sent) {
+                is_response(recorded) && recorded.get("id") == sent.get("id")
+            } else {
+                recorded.get("method").is_some()
+                    && recorded.get("method") == sent.get("method")
+                    && params_without_meta(recorded) == params_without_meta(sent)
+            }
+        };
+        let Some(slot) = self.messages.iter_mut().find(|slot| {
+            slot.as_ref().is_some_and(|recorded| {
+                recorded.direction == Direction::Sent && matches(&recorded.message)
+            })
+        }) else {
+            return false;
+        };
+        let recorded = slot.take().expect("slot is some");
+        if let (Some(recorded_id), Some(id), false) = (
+            recorded.message.get("id"),
+            sent.get("id"),
+            is_response(sent),
+        ) {
+            self.request_ids.insert(recorded_id.clone(), id.clone());
+        }
+        true
+    }
+
+    fn next_received(&mut self) -> Next {
+        // whether a sent message is expected before the next received one
+        let mut waiting = false;
+        for slot in &mut self.messages {
+            let Some(recorded) = slot else {
+                continue;
+            };
+            if recorded.direction == Direction::Sent {
+                waiting = true;
+                continue;
+            }
+            // the response to a matched request doesn't wait for the other requests
+            let matched_id = recorded
+                .message
+                .get("id")
+                .filter(|_| is_response(&recorded.message))
+                .and_then(|id| self.request_ids.get(id));
+            if waiting && matched_id.is_none() {
+                continue;
+            }
+            let matched_id = matched_id.cloned();
+            let mut message = slot.take().expect("slot is some").message;
+            if let Some(id) = matched_id {
+                message["id"] = id;
+            }
+            return Next::Received(message);
+        }
+        if waiting { Next::Waiting } else { Next::Over }
+    }
+}
+
+impl<R: ServiceRole> Transport<R> for ReplayTransport {
+    type Error = ReplayError;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        let result = serde_json::to_value(&item)
+            .map_err(ReplayError::from)
+            .and_then(|message| {
+                let mut state = self.state.lock().expect("replay state poisoned");
+                if state.accept(&message) {
+                    Ok(())
+                } else {
+                    tracing::warn!(%message, "message not in the recording");
+                    Err(ReplayError::Unexpected(message))
+                }
+            });
+        if result.is_ok() {
+            self.matched.notify_waiters();
+        }
+        std::future::ready(result)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<R>> {
+        loop {
+            // created before checking the state, so no match is missed
+            let matched = self.matched.notified();
+            let next = self
+                .state
+                .lock()
+                .expect("replay state poisoned")
+                .next_received();
+            match next {
+                Next::Received(message) => match serde_json::from_value(message) {
+                    Ok(message) => return Some(message),
+                    Err(error) => {
+                        tracing::error!(%error, "fail to deserialize recorded message");
+                    }
+                },
+                Next::Over => return None,
+                Next::Waiting => matched.await,
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
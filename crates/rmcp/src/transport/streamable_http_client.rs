@@ -9,7 +9,7 @@ use tokio_util::sync::CancellationToken;
 use super::common::client_side_sse::{ExponentialBackoff, SseRetryPolicy, SseStreamReconnect};
 use crate::{
     RoleClient,
-    model::{ClientJsonRpcMessage, ServerJsonRpcMessage},
+    model::{ClientJsonRpcMessage, ProtocolVersion, ServerJsonRpcMessage, ServerResult},
     transport::{
         common::client_side_sse::SseAutoReconnectStream,
         worker::{Worker, WorkerQuitReason, WorkerSendRequest, WorkerTransport},
@@ -140,6 +140,7 @@ pub trait StreamableHttpClient: Clone + Send + 'static {
         message: ClientJsonRpcMessage,
         session_id: Option<Arc<str>>,
         auth_header: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> impl Future<Output = Result<StreamableHttpPostResponse, StreamableHttpError<Self::Error>>>
     + Send
     + '_;
@@ -148,6 +149,7 @@ pub trait StreamableHttpClient: Clone + Send + 'static {
         uri: Arc<str>,
         session_id: Arc<str>,
         auth_header: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> impl Future<Output = Result<(), StreamableHttpError<Self::Error>>> + Send + '_;
     fn get_stream(
         &self,
@@ -155,6 +157,7 @@ pub trait StreamableHttpClient: Clone + Send + 'static {
         session_id: Arc<str>,
         last_event_id: Option<String>,
         auth_header: Option<String>,
+        protocol_version: Option<ProtocolVersion>,
     ) -> impl Future<
         Output = Result<
             BoxStream<'static, Result<Sse, SseError>>,
@@ -173,6 +176,7 @@ struct StreamableHttpClientReconnect<C> {
     pub client: C,
     pub session_id: Arc<str>,
     pub uri: Arc<str>,
+    pub protocol_version: Option<ProtocolVersion>,
 }
 
 impl<C: StreamableHttpClient> SseStreamReconnect for StreamableHttpClientReconnect<C> {
@@ -182,10 +186,11 @@ impl<C: StreamableHttpClient> SseStreamReconnect for StreamableHttpClientReconne
         let client = self.client.clone();
         let uri = self.uri.clone();
         let session_id = self.session_id.clone();
+        let protocol_version = self.protocol_version.clone();
         let last_event_id = last_event_id.map(|s| s.to_owned());
         Box::pin(async move {
             client
-                .get_stream(uri, session_id, last_event_id, None)
+                .get_stream(uri, session_id, last_event_id, None, protocol_version)
                 .await
         })
     }
@@ -289,6 +294,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                 initialize_request,
                 None,
                 self.config.auth_header,
+                None,
             )
             .await
         {
@@ -307,6 +313,14 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                 ));
             }
         };
+        // sent in the `MCP-Protocol-Version` header of every following request
+        let protocol_version = match &message {
+            ServerJsonRpcMessage::Response(response) => match &response.result {
+                ServerResult::InitializeResult(result) => Some(result.protocol_version.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
         let session_id: Option<Arc<str>> = if let Some(session_id) = session_id {
             Some(session_id.into())
         } else {
@@ -324,10 +338,12 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
             let client = self.client.clone();
             let session_id = session_id.clone();
             let url = config.uri.clone();
+            let protocol_version = protocol_version.clone();
             tokio::spawn(async move {
                 ct.cancelled().await;
-                let delete_session_result =
-                    client.delete_session(url, session_id.clone(), None).await;
+                let delete_session_result = client
+                    .delete_session(url, session_id.clone(), None, protocol_version)
+                    .await;
                 match delete_session_result {
                     Ok(_) => {
                         tracing::info!(session_id = session_id.as_ref(), "delete session success")
@@ -357,6 +373,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                 initialized_notification.message,
                 session_id.clone(),
                 config.auth_header.clone(),
+                protocol_version.clone(),
             )
             .await
             .map_err(WorkerQuitReason::fatal_context(
@@ -376,7 +393,13 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
         if let Some(session_id) = &session_id {
             match self
                 .client
-                .get_stream(config.uri.clone(), session_id.clone(), None, None)
+                .get_stream(
+                    config.uri.clone(),
+                    session_id.clone(),
+                    None,
+                    None,
+                    protocol_version.clone(),
+                )
                 .await
             {
                 Ok(stream) => {
@@ -386,6 +409,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                             client: self.client.clone(),
                             session_id: session_id.clone(),
                             uri: config.uri.clone(),
+                            protocol_version: protocol_version.clone(),
                         },
                         self.config.retry_config.clone(),
                     );
@@ -448,6 +472,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                             message,
                             session_id.clone(),
                             config.auth_header.clone(),
+                            protocol_version.clone(),
                         )
                         .await;
                     let send_result = match response {
@@ -468,6 +493,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                                         client: self.client.clone(),
                                         session_id: session_id.clone(),
                                         uri: config.uri.clone(),
+                                        protocol_version: protocol_version.clone(),
                                     },
                                     self.config.retry_config.clone(),
                                 );
@@ -541,7 +567,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
 /// };
 /// use std::sync::Arc;
 /// use futures::stream::BoxStream;
-/// use rmcp::model::ClientJsonRpcMessage;
+/// use rmcp::model::{ClientJsonRpcMessage, ProtocolVersion};
 /// use sse_stream::{Sse, Error as SseError};
 ///
 /// #[derive(Clone)]
@@ -565,6 +591,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
 ///         _message: ClientJsonRpcMessage,
 ///         _session_id: Option<Arc<str>>,
 ///         _auth_header: Option<String>,
+///         _protocol_version: Option<ProtocolVersion>,
 ///     ) -> Result<rmcp::transport::streamable_http_client::StreamableHttpPostResponse, rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
 ///         todo!()
 ///     }
@@ -574,6 +601,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
 ///         _uri: Arc<str>,
 ///         _session_id: Arc<str>,
 ///         _auth_header: Option<String>,
+///         _protocol_version: Option<ProtocolVersion>,
 ///     ) -> Result<(), rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
 ///         todo!()
 ///     }
@@ -584,6 +612,7 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
 ///         _session_id: Arc<str>,
 ///         _last_event_id: Option<String>,
 ///         _auth_header: Option<String>,
+///         _protocol_version: Option<ProtocolVersion>,
 ///     ) -> Result<BoxStream<'static, Result<Sse, SseError>>, rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
 ///         todo!()
 ///     }
@@ -622,7 +651,7 @@ impl<C: StreamableHttpClient> StreamableHttpClientTransport<C> {
     /// };
     /// use std::sync::Arc;
     /// use futures::stream::BoxStream;
-    /// use rmcp::model::ClientJsonRpcMessage;
+    /// use rmcp::model::{ClientJsonRpcMessage, ProtocolVersion};
     /// use sse_stream::{Sse, Error as SseError};
     ///
     /// // Define your custom client
@@ -647,6 +676,7 @@ impl<C: StreamableHttpClient> StreamableHttpClientTransport<C> {
     ///         _message: ClientJsonRpcMessage,
     ///         _session_id: Option<Arc<str>>,
     ///         _auth_header: Option<String>,
+    ///         _protocol_version: Option<ProtocolVersion>,
     ///     ) -> Result<rmcp::transport::streamable_http_client::StreamableHttpPostResponse, rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
     ///         todo!()
     ///     }
@@ -656,6 +686,7 @@ impl<C: StreamableHttpClient> StreamableHttpClientTransport<C> {
     ///         _uri: Arc<str>,
     ///         _session_id: Arc<str>,
     ///         _auth_header: Option<String>,
+    ///         _protocol_version: Option<ProtocolVersion>,
     ///     ) -> Result<(), rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
     ///         todo!()
     ///     }
@@ -666,6 +697,7 @@ impl<C: StreamableHttpClient> StreamableHttpClientTransport<C> {
     ///         _session_id: Arc<str>,
     ///         _last_event_id: Option<String>,
     ///         _auth_header: Option<String>,
+    ///         _protocol_version: Option<ProtocolVersion>,
     ///     ) -> Result<BoxStream<'static, Result<Sse, SseError>>, rmcp::transport::streamable_http_client::StreamableHttpError<Self::Error>> {
     ///         todo!()
     ///     }
@@ -50,6 +50,8 @@ impl SessionManager for LocalSessionManager {
         let id = session_id();
         let (handle, worker) = create_local_session(id.clone(), self.session_config.clone());
         self.sessions.write().await.insert(id.clone(), handle);
+        #[cfg(feature = "metrics")]
+        crate::metrics::add_to_gauge(crate::metrics::names::SESSIONS_ACTIVE, 1.0, &[]);
         Ok((id, WorkerTransport::spawn(worker)))
     }
     async fn initialize_session(
@@ -67,6 +69,8 @@ impl SessionManager for LocalSessionManager {
     async fn close_session(&self, id: &SessionId) -> Result<(), Self::Error> {
         let mut sessions = self.sessions.write().await;
         if let Some(handle) = sessions.remove(id) {
+            #[cfg(feature = "metrics")]
+            crate::metrics::add_to_gauge(crate::metrics::names::SESSIONS_ACTIVE, -1.0, &[]);
             handle.close().await?;
         }
         Ok(())
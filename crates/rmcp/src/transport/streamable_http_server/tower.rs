@@ -1,4 +1,10 @@
-use std::{convert::Infallible, fmt::Display, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt::Display,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use bytes::Bytes;
 use futures::{StreamExt, future::BoxFuture};
@@ -6,18 +12,22 @@ use http::{Method, Request, Response, header::ALLOW};
 use http_body::Body;
 use http_body_util::{BodyExt, Full, combinators::BoxBody};
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
 
-use super::session::SessionManager;
+use super::session::{SessionId, SessionManager};
 use crate::{
     RoleServer,
-    model::{ClientJsonRpcMessage, ClientRequest, GetExtensions},
-    serve_server,
-    service::serve_directly,
+    model::{
+        ClientJsonRpcMessage, ClientRequest, GetExtensions, GetMeta, ProtocolVersion,
+        ServerJsonRpcMessage, ServerResult, TraceContext,
+    },
+    service::{ServeConfig, serve_directly, serve_server_with_config},
     transport::{
         OneshotTransport, TransportAdapterIdentity,
         common::{
             http_header::{
-                EVENT_STREAM_MIME_TYPE, HEADER_LAST_EVENT_ID, HEADER_SESSION_ID, JSON_MIME_TYPE,
+                EVENT_STREAM_MIME_TYPE, HEADER_LAST_EVENT_ID, HEADER_MCP_PROTOCOL_VERSION,
+                HEADER_SESSION_ID, HEADER_TRACEPARENT, HEADER_TRACESTATE, JSON_MIME_TYPE,
             },
             server_side_http::{
                 BoxResponse, ServerSseMessage, accepted_response, expect_json,
@@ -57,10 +67,22 @@ impl Default for StreamableHttpServerConfig {
 ///     tracing::info!("http parts:{parts:?}")
 /// }
 /// ```
+///
+/// ## Protocol version header
+///
+/// Every request after initialization may carry the `MCP-Protocol-Version` header. A request
+/// is rejected with `400 Bad Request` if the header names a version this crate doesn't know,
+/// or one different from the version negotiated for the session.
+///
+/// Clients which negotiated `2025-06-18` or later must send the header. For sessions on an
+/// older version, and in stateless mode where there is no session, a missing header is
+/// accepted and the version is assumed to be `2025-03-26`.
 pub struct StreamableHttpService<S, M = super::session::local::LocalSessionManager> {
     pub config: StreamableHttpServerConfig,
     session_manager: Arc<M>,
     service_factory: Arc<dyn Fn() -> Result<S, std::io::Error> + Send + Sync>,
+    session_versions: Arc<RwLock<HashMap<SessionId, ProtocolVersion>>>,
+    serve_config: ServeConfig,
 }
 
 impl<S, M> Clone for StreamableHttpService<S, M> {
@@ -69,10 +91,67 @@ impl<S, M> Clone for StreamableHttpService<S, M> {
             config: self.config.clone(),
             session_manager: self.session_manager.clone(),
             service_factory: self.service_factory.clone(),
+            session_versions: self.session_versions.clone(),
+            serve_config: self.serve_config.clone(),
         }
     }
 }
 
+fn bad_request_response(message: String) -> BoxResponse {
+    Response::builder()
+        .status(http::StatusCode::BAD_REQUEST)
+        .body(Full::new(Bytes::from(format!("Bad Request: {message}"))).boxed())
+        .expect("valid response")
+}
+
+/// Validate the `MCP-Protocol-Version` header against the version negotiated for the session,
+/// returning the response rejecting the request if it's invalid.
+///
+/// A request without the header is accepted: the specification asks servers to assume a
+/// version then, and the session already has the negotiated one.
+fn reject_protocol_version(
+    headers: &http::HeaderMap,
+    negotiated: Option<&ProtocolVersion>,
+) -> Option<BoxResponse> {
+    let header = headers.get(HEADER_MCP_PROTOCOL_VERSION)?;
+    let header = String::from_utf8_lossy(header.as_bytes());
+    let Some(version) = ProtocolVersion::KNOWN
+        .iter()
+        .find(|version| version.as_str() == header)
+    else {
+        return Some(bad_request_response(format!(
+            "unsupported {HEADER_MCP_PROTOCOL_VERSION}: {header}"
+        )));
+    };
+    match negotiated {
+        Some(negotiated) if negotiated != version => Some(bad_request_response(format!(
+            "{HEADER_MCP_PROTOCOL_VERSION} {version} doesn't match the negotiated version {negotiated}"
+        ))),
+        _ => None,
+    }
+}
+
+/// Carry the trace context of the `traceparent` and `tracestate` headers in the `_meta`
+/// of the message, unless it already has its own.
+fn inherit_trace_context(headers: &http::HeaderMap, message: &mut ClientJsonRpcMessage) {
+    let meta = match message {
+        ClientJsonRpcMessage::Request(req) => req.request.get_meta_mut(),
+        ClientJsonRpcMessage::Notification(not) => not.notification.get_meta_mut(),
+        _ => return,
+    };
+    if meta.get_trace_context().is_some() {
+        return;
+    }
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let Some(traceparent) = header(HEADER_TRACEPARENT) else {
+        return;
+    };
+    let context = TraceContext::new(traceparent, header(HEADER_TRACESTATE).map(str::to_owned));
+    if context.is_valid() {
+        meta.set_trace_context(context);
+    }
+}
+
 impl<RequestBody, S, M> tower_service::Service<Request<RequestBody>> for StreamableHttpService<S, M>
 where
     RequestBody: Body + Send + 'static,
@@ -113,11 +192,32 @@ where
             config,
             session_manager,
             service_factory: Arc::new(service_factory),
+            session_versions: Default::default(),
+            serve_config: ServeConfig::default(),
         }
     }
+
+    /// Set how each session handles its requests, such as the number of requests in flight.
+    pub fn with_serve_config(mut self, serve_config: ServeConfig) -> Self {
+        self.serve_config = serve_config;
+        self
+    }
     fn get_service(&self) -> Result<S, std::io::Error> {
         (self.service_factory)()
     }
+    fn session_version(&self, session_id: &SessionId) -> Option<ProtocolVersion> {
+        self.session_versions
+            .read()
+            .expect("session versions lock")
+            .get(session_id)
+            .cloned()
+    }
+    fn forget_session_version(&self, session_id: &SessionId) {
+        self.session_versions
+            .write()
+            .expect("session versions lock")
+            .remove(session_id);
+    }
     pub async fn handle<B>(&self, request: Request<B>) -> Response<BoxBody<Bytes, Infallible>>
     where
         B: Body + Send + 'static,
@@ -196,6 +296,12 @@ where
                 .body(Full::new(Bytes::from("Unauthorized: Session not found")).boxed())
                 .expect("valid response"));
         }
+        if let Some(response) = reject_protocol_version(
+            request.headers(),
+            self.session_version(&session_id).as_ref(),
+        ) {
+            return Ok(response);
+        }
         // check if last event id is provided
         let last_event_id = request
             .headers()
@@ -265,6 +371,7 @@ where
             Ok(message) => message,
             Err(response) => return Ok(response),
         };
+        inherit_trace_context(&part.headers, &mut message);
 
         if self.config.stateful_mode {
             // do we have a session id?
@@ -286,6 +393,12 @@ where
                         .body(Full::new(Bytes::from("Unauthorized: Session not found")).boxed())
                         .expect("valid response"));
                 }
+                if let Some(response) = reject_protocol_version(
+                    &part.headers,
+                    self.session_version(&session_id).as_ref(),
+                ) {
+                    return Ok(response);
+                }
 
                 // inject request part to extensions
                 match &mut message {
@@ -340,11 +453,18 @@ where
                     .map_err(internal_error_response("get service"))?;
                 // spawn a task to serve the session
                 tokio::spawn({
+                    let session_versions = self.session_versions.clone();
                     let session_manager = self.session_manager.clone();
                     let session_id = session_id.clone();
+                    let serve_config = self.serve_config.clone();
                     async move {
-                        let service = serve_server::<S, M::Transport, _, TransportAdapterIdentity>(
-                            service, transport,
+                        let service = serve_server_with_config::<
+                            S,
+                            M::Transport,
+                            _,
+                            TransportAdapterIdentity,
+                        >(
+                            service, transport, serve_config, CancellationToken::new()
                         )
                         .await;
                         match service {
@@ -356,6 +476,10 @@ where
                                 tracing::error!("Failed to create service: {e}");
                             }
                         }
+                        session_versions
+                            .write()
+                            .expect("session versions lock")
+                            .remove(&session_id);
                         let _ = session_manager
                             .close_session(&session_id)
                             .await
@@ -370,6 +494,14 @@ where
                     .initialize_session(&session_id, message)
                     .await
                     .map_err(internal_error_response("create stream"))?;
+                if let ServerJsonRpcMessage::Response(response) = &response {
+                    if let ServerResult::InitializeResult(result) = &response.result {
+                        self.session_versions
+                            .write()
+                            .expect("session versions lock")
+                            .insert(session_id.clone(), result.protocol_version.clone());
+                    }
+                }
                 let mut response = sse_stream_response(
                     futures::stream::once({
                         async move {
@@ -391,6 +523,9 @@ where
                 Ok(response)
             }
         } else {
+            if let Some(response) = reject_protocol_version(&part.headers, None) {
+                return Ok(response);
+            }
             let service = self
                 .get_service()
                 .map_err(internal_error_response("get service"))?;
@@ -443,7 +578,14 @@ where
                 .body(Full::new(Bytes::from("Unauthorized: Session ID is required")).boxed())
                 .expect("valid response"));
         };
+        if let Some(response) = reject_protocol_version(
+            request.headers(),
+            self.session_version(&session_id).as_ref(),
+        ) {
+            return Ok(response);
+        }
         // close session
+        self.forget_session_version(&session_id);
         self.session_manager
             .close_session(&session_id)
             .await
@@ -183,6 +183,8 @@ impl<W: Worker> Transport<W::Role> for WorkerTransport<W> {
     ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
         let tx = self.send_service.clone();
         let (responder, receiver) = tokio::sync::oneshot::channel();
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_transport_message(crate::metrics::short_type_name::<W>(), "out");
         let request = WorkerSendRequest {
             message: item,
             responder,
@@ -194,7 +196,12 @@ impl<W: Worker> Transport<W::Role> for WorkerTransport<W> {
         }
     }
     async fn receive(&mut self) -> Option<RxJsonRpcMessage<W::Role>> {
-        self.rx.recv().await
+        let message = self.rx.recv().await;
+        #[cfg(feature = "metrics")]
+        if message.is_some() {
+            crate::metrics::record_transport_message(crate::metrics::short_type_name::<W>(), "in");
+        }
+        message
     }
     async fn close(&mut self) -> Result<(), Self::Error> {
         if let Some(handle) = self.join_handle.take() {
@@ -4,12 +4,14 @@ use std::{
 };
 
 use rmcp::{
-    ClientHandler, ErrorData as McpError, RoleClient, RoleServer, ServerHandler,
+    ClientHandler, ErrorData as McpError, RoleClient, RoleServer, ServerHandler, Service,
+    ServiceExt,
     model::*,
-    service::{NotificationContext, RequestContext},
+    service::{NotificationContext, RequestContext, RunningService, ServeConfig},
 };
 use serde_json::json;
 use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 pub struct TestClientHandler {
@@ -98,6 +100,34 @@ impl ClientHandler for TestClientHandler {
     }
 }
 
+/// Serve `server` over an in-memory stream and connect a [`TestClientHandler`] to it.
+#[allow(dead_code)]
+pub async fn serve_with_test_client<S: Service<RoleServer>>(
+    server: S,
+) -> anyhow::Result<RunningService<RoleClient, TestClientHandler>> {
+    serve_with_test_client_and_config(server, ServeConfig::default()).await
+}
+
+/// Like [`serve_with_test_client`], serving `server` with `config`.
+#[allow(dead_code)]
+pub async fn serve_with_test_client_and_config<S: Service<RoleServer>>(
+    server: S,
+    config: ServeConfig,
+) -> anyhow::Result<RunningService<RoleClient, TestClientHandler>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server
+            .serve_with_config(server_transport, config, CancellationToken::new())
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    Ok(TestClientHandler::new(false, false)
+        .serve(client_transport)
+        .await?)
+}
+
 pub struct TestServer {}
 
 impl TestServer {
@@ -0,0 +1,158 @@
+//cargo test --test test_blob_limit --features "client server macros base64"
+mod common;
+
+use common::handlers::{TestClientHandler, serve_with_test_client_and_config};
+use rmcp::{
+    ErrorData, RoleServer, ServerHandler,
+    handler::server::router::tool::ToolRouter,
+    model::{
+        BlobLimit, BlobOverflow, CallToolRequestParam, CallToolResult, Content, RawContent,
+        ReadResourceRequestParam, ReadResourceResult, ResourceContents, ServerCapabilities,
+        ServerInfo,
+    },
+    service::{RequestContext, RunningService, ServeConfig},
+    tool, tool_handler, tool_router,
+};
+
+const MAX_SIZE: usize = 64;
+
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[derive(Debug, Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Export the data
+    #[tool]
+    async fn export(&self) -> Result<CallToolResult, ErrorData> {
+        Ok(CallToolResult::success(vec![
+            Content::text("exported"),
+            Content::blob(data(200), "mem://export.bin"),
+        ]))
+    }
+
+    /// Take a screenshot
+    #[tool]
+    async fn screenshot(&self) -> Content {
+        Content::image_bytes(data(100), "image/png")
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::blob(data(200), request.uri)],
+        })
+    }
+}
+
+async fn serve(
+    overflow: BlobOverflow,
+) -> anyhow::Result<RunningService<rmcp::RoleClient, TestClientHandler>> {
+    let limit = BlobLimit::new(MAX_SIZE).with_overflow(overflow);
+    serve_with_test_client_and_config(Server::new(), ServeConfig::default().with_blob_limit(limit))
+        .await
+}
+
+fn call(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_blob_limit_reject() -> anyhow::Result<()> {
+    let client = serve(BlobOverflow::Reject).await?;
+    let error = client.call_tool(call("export")).await.unwrap_err();
+    assert!(
+        error.to_string().contains("exceeds the limit of 64 bytes"),
+        "{error}"
+    );
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blob_limit_chunk() -> anyhow::Result<()> {
+    let client = serve(BlobOverflow::Chunk).await?;
+    let result = client.call_tool(call("export")).await?;
+    // the text, then the blob in chunks of at most 63 bytes
+    assert_eq!(result.content.len(), 5);
+    let mut decoded = Vec::new();
+    for content in &result.content[1..] {
+        let resource = &content.as_resource().unwrap().resource;
+        let chunk = resource.decode()?;
+        assert!(chunk.len() <= MAX_SIZE);
+        decoded.extend(chunk);
+    }
+    assert_eq!(decoded, data(200));
+
+    let result = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "mem://export.bin".into(),
+        })
+        .await?;
+    assert_eq!(result.contents.len(), 4);
+
+    // an image can't be chunked
+    assert!(client.call_tool(call("screenshot")).await.is_err());
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blob_limit_link() -> anyhow::Result<()> {
+    let client = serve(BlobOverflow::Link).await?;
+    let result = client.call_tool(call("export")).await?;
+    assert_eq!(result.content.len(), 2);
+    match &result.content[1].raw {
+        RawContent::ResourceLink(link) => {
+            assert_eq!(link.uri, "mem://export.bin");
+            assert_eq!(link.name, "export.bin");
+            assert_eq!(link.size, Some(200));
+            assert_eq!(link.mime_type.as_deref(), Some("application/octet-stream"));
+        }
+        content => panic!("unexpected content {content:?}"),
+    }
+
+    // the resource itself can't be linked
+    let error = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "mem://export.bin".into(),
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("blob mem://export.bin"),
+        "{error}"
+    );
+    client.cancel().await?;
+    Ok(())
+}
@@ -125,6 +125,19 @@ fn test_completion_info_bounds() {
     assert!(CompletionInfo::new(over_limit).is_err());
 }
 
+#[test]
+fn test_completion_info_truncated() {
+    let values: Vec<String> = (0..150).map(|i| format!("value_{i}")).collect();
+    let info = CompletionInfo::truncated(values);
+    assert_eq!(info.values.len(), CompletionInfo::MAX_VALUES);
+    assert_eq!(info.total, Some(150));
+    assert!(info.has_more_results());
+
+    let info = CompletionInfo::truncated(vec!["one".to_string()]);
+    assert_eq!(info.total, Some(1));
+    assert!(!info.has_more_results());
+}
+
 #[test]
 fn test_reference_convenience_methods() {
     let prompt_ref = Reference::for_prompt("test_prompt");
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use rmcp::{
+    ErrorData, Json, Peer, RoleServer, ServerHandler,
+    handler::server::{progress::Progress, tool::ToolRouter, wrapper::Parameters},
+    model::{
+        AnnotateAble, CallToolRequestParam, GetPromptRequestParam, GetPromptResult,
+        ListPromptsResult, ListResourcesResult, PaginatedRequestParam, Prompt, PromptMessage,
+        PromptMessageRole, RawResource, ReadResourceRequestParam, ReadResourceResult,
+        ResourceContents, ServerCapabilities, ServerInfo,
+    },
+    schemars,
+    service::RequestContext,
+    testing::{CheckOutcome, ConformanceSuite, ListKind},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SumRequest {
+    a: i32,
+    b: i32,
+}
+
+#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
+struct SumResult {
+    sum: i32,
+}
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+    /// Whether the server keeps the promises of its capabilities
+    conformant: bool,
+}
+
+#[tool_router]
+impl Server {
+    fn new(conformant: bool) -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+            conformant,
+        }
+    }
+
+    #[tool(description = "Add two numbers")]
+    async fn sum(
+        &self,
+        Parameters(SumRequest { a, b }): Parameters<SumRequest>,
+    ) -> Json<SumResult> {
+        Json(SumResult { sum: a + b })
+    }
+
+    #[tool(description = "Count to three")]
+    async fn count(&self, progress: Progress) -> String {
+        let progress = progress.with_min_interval(Duration::ZERO);
+        for step in 1..=3 {
+            let _ = progress.report(step as f64, Some(3.0), None).await;
+        }
+        "done".to_string()
+    }
+
+    #[tool(description = "Take a while")]
+    async fn slow(&self) -> String {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        "done".to_string()
+    }
+
+    #[tool(description = "Add a tool")]
+    async fn add_tool(&self, peer: Peer<RoleServer>) -> String {
+        if self.conformant {
+            let _ = peer.notify_tool_list_changed().await;
+        }
+        "added".to_string()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_prompts()
+                .enable_resources()
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        Ok(ListPromptsResult {
+            prompts: vec![Prompt::new("greeting", Some("Say hello"), None)],
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        if request.name != "greeting" {
+            return Err(ErrorData::invalid_params("unknown prompt", None));
+        }
+        Ok(GetPromptResult {
+            description: None,
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, "hello")],
+        })
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        // one resource per page
+        let (uri, next_cursor) = match request.and_then(|request| request.cursor) {
+            None => ("memo://first", Some("second".to_owned())),
+            Some(_) => ("memo://second", None),
+        };
+        Ok(ListResourcesResult {
+            resources: vec![RawResource::new(uri, uri).no_annotation()],
+            next_cursor,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        if request.uri.starts_with("memo://") {
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::text("memo", request.uri)],
+            });
+        }
+        if self.conformant {
+            Err(ErrorData::resource_not_found("unknown resource", None))
+        } else {
+            Err(ErrorData::invalid_params("unknown resource", None))
+        }
+    }
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.to_owned().into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn suite() -> ConformanceSuite {
+    ConformanceSuite::new()
+        .with_timeout(Duration::from_millis(500))
+        .with_tool_call(call("sum", serde_json::json!({ "a": 1, "b": 2 })))
+        .with_tool_call(call("count", serde_json::json!({})))
+        .with_cancellable_tool_call(call("slow", serde_json::json!({})))
+        .with_list_changed_trigger(ListKind::Tools, call("add_tool", serde_json::json!({})))
+}
+
+#[tokio::test]
+async fn test_conformant_server() {
+    let report = suite().run(Server::new(true)).await;
+    assert!(report.is_conformant(), "{report}");
+    assert!(report.server_info.is_some());
+    for name in [
+        "initialize",
+        "ping",
+        "tools/list",
+        "tools/call unknown tool",
+        "tools/call sum",
+        "tools/call count",
+        "prompts/list",
+        "prompts/get",
+        "prompts/get unknown prompt",
+        "resources/list",
+        "resources/read",
+        "resources/read unknown resource",
+        "cancellation",
+        "notifications/tools/list_changed",
+    ] {
+        assert_eq!(report.check(name), Some(&CheckOutcome::Passed), "{name}");
+    }
+    assert!(matches!(
+        report.check("logging/setLevel"),
+        Some(CheckOutcome::Skipped { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_failures_are_reported() {
+    let report = suite().run(Server::new(false)).await;
+    assert!(!report.is_conformant());
+    let failures: Vec<_> = report.failures().map(|check| check.name.as_str()).collect();
+    assert_eq!(
+        failures,
+        [
+            "resources/read unknown resource",
+            "notifications/tools/list_changed"
+        ],
+        "{report}"
+    );
+
+    let report = serde_json::to_value(&report).unwrap();
+    let check = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == "notifications/tools/list_changed")
+        .unwrap();
+    assert_eq!(check["outcome"], "failed");
+    assert!(check["reason"].as_str().unwrap().contains("add_tool"));
+}
@@ -0,0 +1,282 @@
+//cargo test --test test_filesystem_resources --features "client server base64"
+use std::{path::PathBuf, time::Duration};
+
+use rmcp::{
+    ClientHandler, ErrorData, RoleClient, RoleServer, ServerHandler, ServiceExt,
+    handler::server::resource::filesystem::{FileSystemResources, FileSystemResourcesConfig},
+    model::{
+        ClientCapabilities, ClientInfo, ListResourcesResult, ListRootsResult,
+        PaginatedRequestParam, ReadResourceRequestParam, ReadResourceResult, ResourceContents,
+        ResourceUpdatedNotificationParam, Root, ServerCapabilities, ServerInfo,
+        SubscribeRequestParam, file_uri,
+    },
+    service::{NotificationContext, RequestContext, RunningService},
+};
+use tokio::sync::mpsc;
+
+#[derive(Clone)]
+struct Server {
+    files: FileSystemResources,
+}
+
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        self.files.list(request, &context).await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.files.read(request, &context).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.files.subscribe(request, &context).await
+    }
+}
+
+#[derive(Clone)]
+struct Client {
+    roots: Option<Vec<PathBuf>>,
+    changes: mpsc::UnboundedSender<Option<String>>,
+}
+
+impl ClientHandler for Client {
+    fn get_info(&self) -> ClientInfo {
+        let mut info = ClientInfo::default();
+        if self.roots.is_some() {
+            info.capabilities = ClientCapabilities::builder().enable_roots().build();
+        }
+        info
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        Ok(ListRootsResult {
+            roots: self
+                .roots
+                .iter()
+                .flatten()
+                .map(|path| Root {
+                    uri: file_uri(path).unwrap(),
+                    name: None,
+                })
+                .collect(),
+        })
+    }
+
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        let _ = self.changes.send(Some(params.uri));
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        let _ = self.changes.send(None);
+    }
+}
+
+/// A directory tree, removed when dropped
+struct Tree(PathBuf);
+
+impl Tree {
+    fn new(name: &str) -> std::io::Result<Self> {
+        let base = std::env::temp_dir().join(format!("rmcp-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("root/sub"))?;
+        std::fs::create_dir_all(base.join("outside"))?;
+        std::fs::write(base.join("root/readme.md"), "# Hello")?;
+        std::fs::write(base.join("root/data.bin"), [0u8, 1, 2, 255])?;
+        std::fs::write(base.join("root/sub/notes.txt"), "notes")?;
+        std::fs::write(base.join("root/.secret"), "hidden")?;
+        std::fs::write(base.join("outside/secret.txt"), "secret")?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(base.join("outside/secret.txt"), base.join("root/link.txt"))?;
+        Ok(Self(base))
+    }
+
+    fn root(&self) -> PathBuf {
+        self.0.join("root").canonicalize().unwrap()
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn serve(
+    files: FileSystemResources,
+    roots: Option<Vec<PathBuf>>,
+) -> anyhow::Result<(
+    RunningService<RoleClient, Client>,
+    mpsc::UnboundedReceiver<Option<String>>,
+)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        Server { files }
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let (changes, receiver) = mpsc::unbounded_channel();
+    let client = Client { roots, changes }.serve(client_transport).await?;
+    Ok((client, receiver))
+}
+
+fn read(uri: String) -> ReadResourceRequestParam {
+    ReadResourceRequestParam { uri }
+}
+
+#[tokio::test]
+async fn test_list_and_read_files() -> anyhow::Result<()> {
+    let tree = Tree::new("list")?;
+    let root = tree.root();
+    let files = FileSystemResources::with_config(
+        [&root],
+        FileSystemResourcesConfig {
+            page_size: 2,
+            ..Default::default()
+        },
+    )?;
+    let (client, _) = serve(files, None).await?;
+
+    let resources = client.list_all_resources().await?;
+    let names: Vec<_> = resources
+        .iter()
+        .map(|resource| resource.name.as_str())
+        .collect();
+    assert_eq!(names, ["data.bin", "readme.md", "sub/notes.txt"]);
+
+    let result = client
+        .read_resource(read(file_uri(&root.join("readme.md"))?))
+        .await?;
+    match &result.contents[0] {
+        ResourceContents::TextResourceContents {
+            text, mime_type, ..
+        } => {
+            assert_eq!(text, "# Hello");
+            assert_eq!(mime_type.as_deref(), Some("text/markdown"));
+        }
+        contents => panic!("unexpected contents {contents:?}"),
+    }
+    let result = client
+        .read_resource(read(file_uri(&root.join("data.bin"))?))
+        .await?;
+    assert!(matches!(
+        result.contents[0],
+        ResourceContents::BlobResourceContents { .. }
+    ));
+    assert_eq!(result.contents[0].decode()?, [0, 1, 2, 255]);
+
+    // nothing outside of the root, nor hidden
+    for uri in [
+        format!("{}/../outside/secret.txt", file_uri(&root)?),
+        file_uri(&root.join("link.txt"))?,
+        file_uri(&root.join(".secret"))?,
+        "https://example.com/readme.md".to_string(),
+    ] {
+        assert!(
+            client.read_resource(read(uri.clone())).await.is_err(),
+            "{uri}"
+        );
+    }
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_roots() -> anyhow::Result<()> {
+    let tree = Tree::new("roots")?;
+    let root = tree.root();
+    let files = FileSystemResources::new([&root])?;
+    let (client, _) = serve(files, Some(vec![root.join("sub")])).await?;
+
+    let resources = client.list_all_resources().await?;
+    let names: Vec<_> = resources
+        .iter()
+        .map(|resource| resource.name.as_str())
+        .collect();
+    assert_eq!(names, ["sub/notes.txt"]);
+    assert!(
+        client
+            .read_resource(read(file_uri(&root.join("readme.md"))?))
+            .await
+            .is_err()
+    );
+    assert!(
+        client
+            .subscribe(SubscribeRequestParam {
+                uri: file_uri(&root.join("readme.md"))?,
+            })
+            .await
+            .is_err()
+    );
+    client
+        .subscribe(SubscribeRequestParam {
+            uri: file_uri(&root.join("sub/notes.txt"))?,
+        })
+        .await?;
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_files() -> anyhow::Result<()> {
+    let tree = Tree::new("watch")?;
+    let root = tree.root();
+    let files = FileSystemResources::with_config(
+        [&root],
+        FileSystemResourcesConfig {
+            poll_interval: Duration::from_millis(20),
+            ..Default::default()
+        },
+    )?;
+    let (client, mut changes) = serve(files, None).await?;
+
+    let readme = file_uri(&root.join("readme.md"))?;
+    client
+        .subscribe(SubscribeRequestParam {
+            uri: readme.clone(),
+        })
+        .await?;
+    // let the first scan happen
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    std::fs::write(root.join("readme.md"), "# Hello, world")?;
+    let change = tokio::time::timeout(Duration::from_secs(5), changes.recv()).await?;
+    assert_eq!(change, Some(Some(readme)));
+
+    std::fs::write(root.join("sub/new.txt"), "new")?;
+    let change = tokio::time::timeout(Duration::from_secs(5), changes.recv()).await?;
+    assert_eq!(change, Some(None));
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, RoleClient, ServerHandler, ServiceExt,
+    handler::server::logging::{LoggingLayerConfig, McpLogging, RateLimit},
+    model::{LoggingLevel, LoggingMessageNotificationParam, SetLevelRequestParam},
+    service::{NotificationContext, RunningService},
+};
+use tracing_subscriber::layer::SubscriberExt;
+
+#[derive(Clone)]
+struct Server;
+
+impl ServerHandler for Server {}
+
+#[derive(Clone, Default)]
+struct LogCollector {
+    received: Arc<Mutex<Vec<LoggingMessageNotificationParam>>>,
+}
+
+impl LogCollector {
+    fn take(&self) -> Vec<LoggingMessageNotificationParam> {
+        std::mem::take(&mut *self.received.lock().unwrap())
+    }
+}
+
+impl ClientHandler for LogCollector {
+    async fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.received.lock().unwrap().push(params);
+    }
+}
+
+async fn connect(
+    logging: &McpLogging,
+) -> anyhow::Result<(RunningService<RoleClient, LogCollector>, LogCollector)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = logging.wrap(Server);
+    tokio::spawn(async move {
+        let service = server.serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let collector = LogCollector::default();
+    let client = collector.clone().serve(client_transport).await?;
+    Ok((client, collector))
+}
+
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn test_events_respect_client_level() -> anyhow::Result<()> {
+    let logging = McpLogging::new(LoggingLayerConfig {
+        redact_fields: vec!["password".into()],
+        include_span_fields: true,
+        ..Default::default()
+    });
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(logging.layer()));
+    let (client, collector) = connect(&logging).await?;
+    assert!(
+        client
+            .peer_info()
+            .and_then(|info| info.capabilities.logging.as_ref())
+            .is_some()
+    );
+
+    // nothing is sent before the client sets a level
+    tracing::error!("too early");
+    settle().await;
+    assert!(collector.take().is_empty());
+
+    client
+        .set_level(SetLevelRequestParam {
+            level: LoggingLevel::Warning,
+        })
+        .await?;
+    let span = tracing::info_span!("request", user = "alice");
+    let _entered = span.enter();
+    tracing::info!("below the level");
+    tracing::warn!(password = "hunter2", attempts = 3, "login failed");
+    settle().await;
+
+    let received = collector.take();
+    assert_eq!(received.len(), 1, "{received:?}");
+    let message = &received[0];
+    assert_eq!(message.level, LoggingLevel::Warning);
+    assert_eq!(message.logger.as_deref(), Some("test_logging_layer"));
+    assert_eq!(message.data["message"], "login failed");
+    assert_eq!(message.data["password"], McpLogging::REDACTED);
+    assert_eq!(message.data["attempts"], 3);
+    assert_eq!(message.data["user"], "alice");
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_events_are_rate_limited() -> anyhow::Result<()> {
+    let logging = McpLogging::new(LoggingLayerConfig {
+        default_level: Some(LoggingLevel::Debug),
+        rate_limit: Some(RateLimit {
+            max_messages: 2,
+            interval: Duration::from_millis(300),
+        }),
+        ..Default::default()
+    });
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(logging.layer()));
+    let (client, collector) = connect(&logging).await?;
+    settle().await;
+
+    for index in 0..5 {
+        tracing::debug!(index, "burst");
+    }
+    settle().await;
+    let received = collector.take();
+    assert_eq!(received.len(), 2, "{received:?}");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    tracing::info!("after the window");
+    settle().await;
+    let received = collector.take();
+    assert_eq!(received.len(), 2, "{received:?}");
+    assert_eq!(received[0].level, LoggingLevel::Warning);
+    assert_eq!(
+        received[0].data["message"],
+        "3 log messages were dropped by the rate limit"
+    );
+    assert_eq!(received[1].data["message"], "after the window");
+
+    client.cancel().await?;
+    Ok(())
+}
+
+/// Counts how many times it is formatted.
+struct Formatted(Arc<AtomicUsize>);
+
+impl std::fmt::Debug for Formatted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        f.write_str("formatted")
+    }
+}
+
+#[tokio::test]
+async fn test_disconnected_peers_are_forgotten() -> anyhow::Result<()> {
+    let logging = McpLogging::new(LoggingLayerConfig {
+        default_level: Some(LoggingLevel::Debug),
+        ..Default::default()
+    });
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(logging.layer()));
+    let (client, collector) = connect(&logging).await?;
+    settle().await;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    tracing::warn!(value = ?Formatted(count.clone()), "connected");
+    settle().await;
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    assert_eq!(collector.take().len(), 1);
+
+    client.cancel().await?;
+    settle().await;
+    // nobody receives the events, they are no longer formatted
+    tracing::warn!(value = ?Formatted(count.clone()), "disconnected");
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    Ok(())
+}
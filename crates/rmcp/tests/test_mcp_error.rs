@@ -0,0 +1,143 @@
+//cargo test --test test_mcp_error --features "client server macros"
+mod common;
+
+use common::handlers::serve_with_test_client;
+use rmcp::{
+    McpError, ServerHandler,
+    handler::server::{
+        router::tool::ToolRouter,
+        tool::{IntoCallToolResult, IntoToolError},
+        wrapper::{Json, Parameters},
+    },
+    model::{CallToolRequestParam, ErrorCode, RawContent},
+    schemars, tool, tool_handler, tool_router,
+};
+
+#[derive(Debug, thiserror::Error, McpError)]
+enum DbError {
+    #[error("no row {id}")]
+    #[mcp(code = INVALID_PARAMS, data = serde_json::json!({ "id": id }))]
+    NotFound { id: u64 },
+    #[error("database busy")]
+    #[mcp(code = -32000)]
+    Busy,
+    #[error("query timed out after {0}s")]
+    #[mcp(tool_error, data = serde_json::json!({ "timeout": _0 }))]
+    Timeout(u64),
+    #[error("connection lost")]
+    ConnectionLost,
+}
+
+#[derive(Debug, thiserror::Error, McpError)]
+#[error("quota exceeded")]
+#[mcp(tool_error)]
+struct QuotaExceeded;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+struct Row {
+    id: u64,
+}
+
+#[test]
+fn test_protocol_errors() {
+    let error = Err::<String, _>(DbError::NotFound { id: 7 })
+        .into_call_tool_result()
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert_eq!(error.message, "no row 7");
+    assert_eq!(error.data, Some(serde_json::json!({ "id": 7 })));
+
+    let error = Err::<String, _>(DbError::Busy)
+        .into_call_tool_result()
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode(-32000));
+    assert_eq!(error.data, None);
+
+    let error = Err::<String, _>(DbError::ConnectionLost)
+        .into_call_tool_result()
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::INTERNAL_ERROR);
+}
+
+#[test]
+fn test_tool_errors() {
+    let result = Err::<Json<Row>, _>(DbError::Timeout(30))
+        .into_call_tool_result()
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(
+        result.content[0].raw.as_text().unwrap().text,
+        "query timed out after 30s"
+    );
+    assert_eq!(
+        result.structured_content,
+        Some(serde_json::json!({ "timeout": 30 }))
+    );
+
+    let result = QuotaExceeded.into_tool_error().unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(result.structured_content, None);
+
+    // contents are still tool errors
+    let result = Err::<String, _>("bad input".to_owned())
+        .into_call_tool_result()
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+}
+
+#[derive(Debug, Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Get a row by id
+    #[tool]
+    async fn get_row(&self, Parameters(row): Parameters<Row>) -> Result<Json<Row>, DbError> {
+        match row.id {
+            0 => Err(DbError::NotFound { id: 0 }),
+            1 => Err(DbError::Timeout(5)),
+            id => Ok(Json(Row { id })),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tool_returning_mcp_error() -> anyhow::Result<()> {
+    assert!(Server::get_row_tool_attr().output_schema.is_some());
+
+    let client = serve_with_test_client(Server::new()).await?;
+    let call = |id: u64| CallToolRequestParam {
+        name: "get_row".into(),
+        arguments: serde_json::json!({ "id": id }).as_object().cloned(),
+    };
+
+    let result = client.call_tool(call(2)).await?;
+    assert_eq!(
+        result.structured_content,
+        Some(serde_json::json!({ "id": 2 }))
+    );
+
+    let result = client.call_tool(call(1)).await?;
+    assert_eq!(result.is_error, Some(true));
+    match &result.content[0].raw {
+        RawContent::Text(text) => assert_eq!(text.text, "query timed out after 5s"),
+        content => panic!("unexpected content {content:?}"),
+    }
+
+    let error = client.call_tool(call(0)).await.unwrap_err();
+    assert!(error.to_string().contains("no row 0"), "{error}");
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use rmcp::{
+    ErrorData, ServerHandler, ServiceExt,
+    handler::server::tool::ToolRouter,
+    metrics::{Label, MetricsRecorder, labels, names},
+    model::CallToolRequestParam,
+    tool, tool_handler, tool_router,
+};
+
+type Key = (&'static str, Vec<(&'static str, String)>);
+
+#[derive(Clone, Default)]
+struct Collector {
+    counters: Arc<Mutex<HashMap<Key, u64>>>,
+    histograms: Arc<Mutex<HashMap<Key, usize>>>,
+}
+
+fn key(name: &'static str, labels: &[Label]) -> Key {
+    let mut labels: Vec<_> = labels
+        .iter()
+        .map(|label| (label.key, label.value.to_string()))
+        .collect();
+    labels.sort();
+    (name, labels)
+}
+
+impl MetricsRecorder for Collector {
+    fn increment_counter(&self, name: &'static str, value: u64, labels: &[Label]) {
+        *self
+            .counters
+            .lock()
+            .unwrap()
+            .entry(key(name, labels))
+            .or_default() += value;
+    }
+
+    fn add_to_gauge(&self, _name: &'static str, _delta: f64, _labels: &[Label]) {}
+
+    fn record_histogram(&self, name: &'static str, _value: f64, labels: &[Label]) {
+        *self
+            .histograms
+            .lock()
+            .unwrap()
+            .entry(key(name, labels))
+            .or_default() += 1;
+    }
+}
+
+impl Collector {
+    fn counter(&self, name: &'static str, labels: &[(&'static str, &str)]) -> u64 {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|(key, value)| Label::new(key, value.to_string()))
+            .collect();
+        self.counters
+            .lock()
+            .unwrap()
+            .get(&key(name, &labels))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The sum of a counter over all its labels matching `filter`.
+    fn sum(&self, name: &'static str, filter: (&'static str, &str)) -> u64 {
+        self.counters
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((counter, labels), _)| {
+                *counter == name
+                    && labels
+                        .iter()
+                        .any(|(key, value)| *key == filter.0 && value == filter.1)
+            })
+            .map(|(_, value)| value)
+            .sum()
+    }
+}
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Say hello")]
+    async fn hello(&self) -> String {
+        "hello".to_string()
+    }
+
+    #[tool(description = "Always fail")]
+    async fn fail(&self) -> Result<String, ErrorData> {
+        Err(ErrorData::invalid_params("broken", None))
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+fn call(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_requests_and_tools_are_recorded() -> anyhow::Result<()> {
+    let collector = Collector::default();
+    rmcp::metrics::set_recorder(collector.clone())?;
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Server::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+    client.call_tool(call("hello")).await?;
+    client.call_tool(call("hello")).await?;
+    assert!(client.call_tool(call("fail")).await.is_err());
+    client.cancel().await?;
+
+    let request = |outcome| {
+        collector.counter(
+            names::REQUESTS,
+            &[
+                (labels::ROLE, "server"),
+                (labels::METHOD, "tools/call"),
+                (labels::OUTCOME, outcome),
+            ],
+        )
+    };
+    assert_eq!(request("ok"), 2);
+    assert_eq!(request("error"), 1);
+    assert_eq!(
+        collector.counter(
+            names::REQUEST_ERRORS,
+            &[
+                (labels::ROLE, "server"),
+                (labels::METHOD, "tools/call"),
+                (labels::ERROR_CODE, "-32602"),
+            ],
+        ),
+        1
+    );
+    assert_eq!(
+        collector.counter(
+            names::TOOL_CALLS,
+            &[(labels::TOOL, "hello"), (labels::OUTCOME, "ok")]
+        ),
+        2
+    );
+    assert_eq!(
+        collector.counter(
+            names::TOOL_CALLS,
+            &[(labels::TOOL, "fail"), (labels::OUTCOME, "error")]
+        ),
+        1
+    );
+    assert_eq!(
+        collector.histograms.lock().unwrap()[&key(
+            names::TOOL_DURATION,
+            &[
+                Label::new(labels::TOOL, "hello"),
+                Label::new(labels::OUTCOME, "ok")
+            ]
+        )],
+        2
+    );
+    // both sides use the async read/write transport
+    let bytes_in = collector.sum(names::TRANSPORT_BYTES, (labels::DIRECTION, "in"));
+    let bytes_out = collector.sum(names::TRANSPORT_BYTES, (labels::DIRECTION, "out"));
+    assert!(bytes_in > 0);
+    assert_eq!(bytes_in, bytes_out);
+    Ok(())
+}
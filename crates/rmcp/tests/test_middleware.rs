@@ -0,0 +1,323 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, ErrorData, RoleClient, RoleServer, ServerHandler, ServiceExt,
+    handler::server::{tool::ToolRouter, wrapper::Parameters},
+    model::{
+        CallToolRequestParam, CallToolResult, ClientRequest, ClientResult, Content, GetMethod,
+        ListRootsResult, RawContent, Root, ServerRequest, ServerResult,
+    },
+    schemars,
+    service::{
+        NotificationContext, RequestContext, RunningService,
+        middleware::{ConcurrencyLimitMiddleware, Middleware, Next, TimeoutMiddleware},
+    },
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Clone, Default)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+    roots_changed: Arc<AtomicUsize>,
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+struct EchoArgs {
+    text: String,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+            ..Default::default()
+        }
+    }
+
+    #[tool(description = "Return the text")]
+    async fn echo(&self, Parameters(args): Parameters<EchoArgs>) -> String {
+        args.text
+    }
+
+    #[tool(description = "Return the first root of the client")]
+    async fn first_root(&self, context: RequestContext<RoleServer>) -> Result<String, ErrorData> {
+        let roots = context
+            .peer
+            .list_roots()
+            .await
+            .map_err(|error| ErrorData::internal_error(error.to_string(), None))?;
+        Ok(roots
+            .roots
+            .first()
+            .map(|root| root.uri.clone())
+            .unwrap_or_default())
+    }
+
+    #[tool(description = "Finish after a while")]
+    async fn slow(&self) -> String {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        "done".to_string()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {
+    async fn on_roots_list_changed(&self, _context: NotificationContext<RoleServer>) {
+        self.roots_changed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Replace the `text` argument of tool calls and tag their results.
+#[derive(Clone, Default)]
+struct Redact {
+    seen: Arc<Mutex<Vec<&'static str>>>,
+    sent: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Middleware<RoleServer> for Redact {
+    async fn handle_request<'a>(
+        &'a self,
+        mut request: ClientRequest,
+        context: RequestContext<RoleServer>,
+        next: Next<'a, RoleServer>,
+    ) -> Result<ServerResult, ErrorData> {
+        self.seen.lock().unwrap().push(request.method());
+        if let ClientRequest::CallToolRequest(call) = &mut request {
+            if call.params.name == "forbidden" {
+                return Err(ErrorData::invalid_request("forbidden tool", None));
+            }
+            if let Some(text) = call
+                .params
+                .arguments
+                .as_mut()
+                .and_then(|arguments| arguments.get_mut("text"))
+            {
+                *text = "[redacted]".into();
+            }
+        }
+        let mut result = next.run(request, context).await?;
+        match &mut result {
+            ServerResult::CallToolResult(result) => result.content.push(Content::text("checked")),
+            ServerResult::InitializeResult(info) => info.instructions = Some("redacted".into()),
+            _ => {}
+        }
+        Ok(result)
+    }
+
+    async fn handle_notification<'a>(
+        &'a self,
+        notification: rmcp::model::ClientNotification,
+        context: NotificationContext<RoleServer>,
+        next: Next<'a, RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.seen.lock().unwrap().push(notification.method());
+        next.notify(notification, context).await
+    }
+
+    fn handle_outgoing_request(&self, request: &mut ServerRequest) -> Result<(), ErrorData> {
+        self.sent.lock().unwrap().push(request.method());
+        Ok(())
+    }
+}
+
+/// Fail the requests the server sends to its client.
+struct DenyOutgoing;
+
+impl Middleware<RoleServer> for DenyOutgoing {
+    fn handle_outgoing_request(&self, _request: &mut ServerRequest) -> Result<(), ErrorData> {
+        Err(ErrorData::invalid_request(
+            "outgoing requests are denied",
+            None,
+        ))
+    }
+}
+
+#[derive(Clone)]
+struct Client;
+
+impl ClientHandler for Client {
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        Ok(ListRootsResult {
+            roots: vec![Root {
+                uri: "file:///home".into(),
+                name: None,
+            }],
+        })
+    }
+}
+
+/// Hide the roots of the client from the server.
+struct HideRoots;
+
+impl Middleware<RoleClient> for HideRoots {
+    async fn handle_request<'a>(
+        &'a self,
+        request: ServerRequest,
+        context: RequestContext<RoleClient>,
+        next: Next<'a, RoleClient>,
+    ) -> Result<ClientResult, ErrorData> {
+        let mut result = next.run(request, context).await?;
+        if let ClientResult::ListRootsResult(result) = &mut result {
+            for root in &mut result.roots {
+                root.uri = "file:///hidden".into();
+            }
+        }
+        Ok(result)
+    }
+}
+
+async fn connect<S: rmcp::Service<RoleServer>>(
+    server: S,
+) -> anyhow::Result<RunningService<RoleClient, rmcp::service::middleware::Layered<Client, HideRoots>>>
+{
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = server.serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(Client
+        .with_middleware(HideRoots)
+        .serve(client_transport)
+        .await?)
+}
+
+fn call(name: &'static str, arguments: serde_json::Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn texts(result: &CallToolResult) -> Vec<&str> {
+    result
+        .content
+        .iter()
+        .map(|content| match &content.raw {
+            RawContent::Text(text) => text.text.as_str(),
+            other => panic!("expected text content, got {other:?}"),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_middlewares_see_both_directions() -> anyhow::Result<()> {
+    let server = Server::new();
+    let redact = Redact::default();
+    let client = connect(server.clone().with_middleware(redact.clone())).await?;
+    assert_eq!(
+        client
+            .peer_info()
+            .and_then(|info| info.instructions.as_deref()),
+        Some("redacted")
+    );
+
+    let result = client
+        .call_tool(call("echo", serde_json::json!({ "text": "secret" })))
+        .await?;
+    assert_eq!(texts(&result), ["[redacted]", "checked"]);
+
+    let error = client
+        .call_tool(call("forbidden", serde_json::json!({})))
+        .await
+        .expect_err("rejected by the middleware");
+    assert!(error.to_string().contains("forbidden tool"), "{error}");
+
+    let result = client
+        .call_tool(call("first_root", serde_json::json!({})))
+        .await?;
+    assert_eq!(texts(&result), ["file:///hidden", "checked"]);
+
+    client.notify_roots_list_changed().await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(server.roots_changed.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *redact.seen.lock().unwrap(),
+        [
+            "initialize",
+            "notifications/initialized",
+            "tools/call",
+            "tools/call",
+            "tools/call",
+            "notifications/roots/list_changed"
+        ]
+    );
+    assert_eq!(*redact.sent.lock().unwrap(), ["roots/list"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_outgoing_requests_can_be_rejected() -> anyhow::Result<()> {
+    let client = connect(Server::new().with_middleware(DenyOutgoing)).await?;
+
+    let error = client
+        .call_tool(call("first_root", serde_json::json!({})))
+        .await
+        .expect_err("the roots request is rejected");
+    assert!(
+        error.to_string().contains("outgoing requests are denied"),
+        "{error}"
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timeout() -> anyhow::Result<()> {
+    let client = connect(
+        Server::new()
+            .with_middleware(TimeoutMiddleware::new(Duration::from_millis(20)))
+            .with_middleware(Redact::default()),
+    )
+    .await?;
+
+    let error = client
+        .call_tool(call("slow", serde_json::json!({})))
+        .await
+        .expect_err("the call times out");
+    assert!(error.to_string().contains("timed out"), "{error}");
+    // fast requests are unaffected
+    let result = client
+        .call_tool(call("echo", serde_json::json!({ "text": "hi" })))
+        .await?;
+    assert_eq!(texts(&result), ["[redacted]", "checked"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrency_limit() -> anyhow::Result<()> {
+    let server = Server::new();
+    let limit = ConcurrencyLimitMiddleware::new(2);
+    let client = connect(server.clone().with_middleware(limit.clone())).await?;
+
+    let calls = (0..5).map(|_| client.call_tool(call("slow", serde_json::json!({}))));
+    let results = futures::future::join_all(calls).await;
+    for result in results {
+        assert_eq!(texts(&result?), ["done"]);
+    }
+    assert_eq!(server.max_running.load(Ordering::SeqCst), 2);
+    assert_eq!(limit.available(), 2);
+
+    client.cancel().await?;
+    Ok(())
+}
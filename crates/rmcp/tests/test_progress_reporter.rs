@@ -0,0 +1,124 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, RoleClient, ServerHandler, ServiceExt,
+    handler::server::{progress::Progress, tool::ToolRouter},
+    model::{CallToolRequestParam, ProgressNotificationParam},
+    service::{NotificationContext, serve_directly},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Clone, Default)]
+struct ProgressCollector {
+    received: Arc<Mutex<Vec<ProgressNotificationParam>>>,
+}
+
+impl ClientHandler for ProgressCollector {
+    async fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.received.lock().unwrap().push(params);
+    }
+}
+
+#[derive(Clone)]
+struct ProgressServer {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl ProgressServer {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Report every step without rate limiting")]
+    async fn every_step(&self, progress: Progress) -> String {
+        let progress = progress.with_min_interval(Duration::ZERO);
+        for step in 1..=5 {
+            progress
+                .report(step as f64, Some(5.0), Some(format!("step {step}")))
+                .await
+                .unwrap();
+        }
+        // going backwards is ignored
+        progress.report(2.0, Some(5.0), None).await.unwrap();
+        "done".to_string()
+    }
+
+    #[tool(description = "Report in a tight loop")]
+    async fn tight_loop(&self, progress: Progress) -> String {
+        let progress = progress.with_min_interval(Duration::from_secs(60));
+        for step in 1..=100 {
+            progress
+                .report(step as f64, Some(100.0), None)
+                .await
+                .unwrap();
+        }
+        "done".to_string()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for ProgressServer {}
+
+async fn call_and_collect(tool: &'static str) -> anyhow::Result<Vec<f64>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = ProgressServer::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let collector = ProgressCollector::default();
+    let client = collector.clone().serve(client_transport).await?;
+    client
+        .call_tool(CallToolRequestParam {
+            name: tool.into(),
+            arguments: None,
+        })
+        .await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    client.cancel().await?;
+    let mut values = collector
+        .received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|param| param.progress)
+        .collect::<Vec<_>>();
+    values.sort_by(f64::total_cmp);
+    Ok(values)
+}
+
+#[tokio::test]
+async fn test_progress_reports_are_monotonic() -> anyhow::Result<()> {
+    let values = call_and_collect("every_step").await?;
+    assert_eq!(values, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_progress_reports_are_rate_limited_and_flushed() -> anyhow::Result<()> {
+    let values = call_and_collect("tight_loop").await?;
+    // the first report goes out immediately, the last one is flushed when the handler returns
+    assert_eq!(values, vec![1.0, 100.0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_progress_without_token_is_noop() -> anyhow::Result<()> {
+    let (server_transport, _client_transport) = tokio::io::duplex(64);
+    let running = serve_directly(ProgressServer::new(), server_transport, None);
+    let progress = Progress::new(None, running.peer().clone());
+    assert!(!progress.is_enabled());
+    progress.report(1.0, None, None).await?;
+    progress.finish().await?;
+    Ok(())
+}
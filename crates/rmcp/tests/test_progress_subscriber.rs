@@ -1,9 +1,14 @@
+use std::time::Duration;
+
 use futures::StreamExt;
 use rmcp::{
     ClientHandler, Peer, RoleServer, ServerHandler, ServiceExt,
-    handler::{client::progress::ProgressDispatcher, server::tool::ToolRouter},
+    handler::{
+        client::progress::ProgressDispatcher,
+        server::{progress::Progress, tool::ToolRouter},
+    },
     model::{CallToolRequestParam, ClientRequest, Meta, ProgressNotificationParam, Request},
-    service::PeerRequestOptions,
+    service::{PeerRequestOptions, ServiceError},
     tool, tool_handler, tool_router,
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -81,6 +86,33 @@ impl MyServer {
         }
         Ok(())
     }
+
+    #[tool]
+    pub async fn counted_progress(progress: Progress) -> String {
+        let progress = progress.with_min_interval(Duration::ZERO);
+        for step in 0..10 {
+            let _ = progress
+                .report(step as f64, Some(10.0), Some(format!("step {step}")))
+                .await;
+        }
+        "done".to_string()
+    }
+
+    #[tool]
+    pub async fn slow_with_progress(progress: Progress) -> String {
+        let progress = progress.with_min_interval(Duration::ZERO);
+        for step in 0..6 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = progress.report(step as f64, None, None).await;
+        }
+        "done".to_string()
+    }
+
+    #[tool]
+    pub async fn slow_silent() -> String {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        "done".to_string()
+    }
 }
 
 #[tool_handler]
@@ -130,3 +162,90 @@ async fn test_progress_subscriber() -> anyhow::Result<()> {
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_call_tool_with_progress() -> anyhow::Result<()> {
+    let (transport_server, transport_client) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = MyServer::new().serve(transport_server).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client_service = ().serve(transport_client).await?;
+    let mut call = client_service
+        .call_tool_with_progress(CallToolRequestParam {
+            name: "counted_progress".into(),
+            arguments: None,
+        })
+        .await?;
+    let mut received = Vec::new();
+    while let Some(notification) = call.next().await {
+        assert_eq!(&notification.progress_token, call.progress_token());
+        received.push(notification.progress);
+    }
+    assert_eq!(received, (0..10).map(f64::from).collect::<Vec<_>>());
+    let result = call.result().await?;
+    assert_eq!(result.is_error, Some(false));
+    client_service.cancel().await?;
+    Ok(())
+}
+
+async fn call_with_options(
+    name: &'static str,
+    options: PeerRequestOptions,
+    idle_timeout: Duration,
+) -> anyhow::Result<Result<rmcp::model::ServerResult, ServiceError>> {
+    let (transport_server, transport_client) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = MyServer::new().serve(transport_server).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client_service = ().serve(transport_client).await?;
+    let response = client_service
+        .send_request_with_option(
+            ClientRequest::CallToolRequest(Request::new(CallToolRequestParam {
+                name: name.into(),
+                arguments: None,
+            })),
+            options,
+        )
+        .await?
+        .with_idle_timeout(idle_timeout)
+        .await_response()
+        .await;
+    client_service.cancel().await?;
+    Ok(response)
+}
+
+#[tokio::test]
+async fn test_idle_timeout_is_reset_by_progress() -> anyhow::Result<()> {
+    let options = PeerRequestOptions::no_options();
+    let response =
+        call_with_options("slow_with_progress", options, Duration::from_millis(150)).await?;
+    assert!(response.is_ok(), "{response:?}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idle_timeout_without_progress() -> anyhow::Result<()> {
+    let options = PeerRequestOptions::no_options();
+    let response = call_with_options("slow_silent", options, Duration::from_millis(100)).await?;
+    assert!(matches!(
+        response,
+        Err(ServiceError::Timeout { timeout }) if timeout == Duration::from_millis(100)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_absolute_timeout_despite_progress() -> anyhow::Result<()> {
+    let options = PeerRequestOptions::no_options().with_timeout(Duration::from_millis(120));
+    let response =
+        call_with_options("slow_with_progress", options, Duration::from_millis(150)).await?;
+    assert!(matches!(
+        response,
+        Err(ServiceError::Timeout { timeout }) if timeout == Duration::from_millis(120)
+    ));
+    Ok(())
+}
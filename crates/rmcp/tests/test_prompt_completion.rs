@@ -0,0 +1,248 @@
+//cargo test --test test_prompt_completion --features "client server macros"
+mod common;
+
+use std::collections::HashMap;
+
+use common::handlers::serve_with_test_client;
+use rmcp::{
+    ErrorData as McpError, RoleServer, ServerHandler,
+    handler::server::{
+        completion::{Completer, CompletionMatcher, CompletionQuery},
+        router::{
+            Router,
+            completion::CompletionRouter,
+            prompt::{PromptRoute, PromptRouter},
+        },
+        wrapper::Parameters,
+    },
+    model::*,
+    prompt, prompt_handler, prompt_router, schemars,
+    service::RequestContext,
+};
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+struct QueryArgs {
+    table: String,
+    column: String,
+}
+
+#[derive(Debug, Clone)]
+struct Server {
+    prompt_router: PromptRouter<Self>,
+    columns: HashMap<&'static str, Vec<&'static str>>,
+}
+
+#[prompt_handler]
+impl ServerHandler for Server {}
+
+#[prompt_router]
+impl Server {
+    fn new() -> Self {
+        let dynamic = PromptRoute::new_dyn(
+            Prompt::new(
+                "greet",
+                Some("Greet someone"),
+                Some(vec![PromptArgument {
+                    name: "name".into(),
+                    title: None,
+                    description: None,
+                    required: Some(true),
+                }]),
+            ),
+            |_context| {
+                Box::pin(async {
+                    Ok(GetPromptResult {
+                        description: None,
+                        messages: vec![],
+                    })
+                })
+            },
+        )
+        .with_completion(
+            "name",
+            Completer::values((0..150).map(|i| format!("user{i}")))
+                .with_matcher(CompletionMatcher::Prefix),
+        );
+        Self {
+            prompt_router: Self::prompt_router().with_route(dynamic),
+            columns: HashMap::from([
+                ("users", vec!["id", "name", "email"]),
+                ("orders", vec!["id", "user_id", "total"]),
+            ]),
+        }
+    }
+
+    /// Query a table
+    #[prompt(complete(table = ["orders", "products", "users"], column = Self::complete_column))]
+    async fn query(&self, Parameters(args): Parameters<QueryArgs>) -> Vec<PromptMessage> {
+        vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!("SELECT {} FROM {}", args.column, args.table),
+        )]
+    }
+
+    async fn complete_column(&self, query: CompletionQuery) -> Result<Vec<&'static str>, McpError> {
+        let table = query
+            .get_argument("table")
+            .ok_or_else(|| McpError::invalid_params("the table is not filled", None))?;
+        Ok(self.columns.get(table).cloned().unwrap_or_default())
+    }
+}
+
+/// A server completing the variables of a resource template besides its prompts.
+#[derive(Debug, Clone)]
+struct TemplateServer {
+    prompt_router: PromptRouter<Self>,
+    completion_router: CompletionRouter<Self>,
+}
+
+impl TemplateServer {
+    fn new() -> Self {
+        Self {
+            prompt_router: PromptRouter::new(),
+            completion_router: CompletionRouter::new().with_resource_variable(
+                "db://{table}/schema",
+                "table",
+                ["users", "orders", "products"],
+            ),
+        }
+    }
+}
+
+#[prompt_handler(completion_router = self.completion_router)]
+impl ServerHandler for TemplateServer {}
+
+fn context(arguments: &[(&str, &str)]) -> Option<CompletionContext> {
+    Some(CompletionContext::with_arguments(
+        arguments
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    ))
+}
+
+#[tokio::test]
+async fn test_prompt_argument_completion() -> anyhow::Result<()> {
+    let client = serve_with_test_client(Server::new()).await?;
+
+    let tables = client
+        .complete_prompt_simple("query", "table", "ord")
+        .await?;
+    assert_eq!(tables, ["orders"]);
+    // fuzzy matching puts the prefix matches first
+    let tables = client.complete_prompt_simple("query", "table", "u").await?;
+    assert_eq!(tables, ["users", "products"]);
+
+    let columns = client
+        .complete_prompt_argument("query", "column", "", context(&[("table", "orders")]))
+        .await?;
+    assert_eq!(columns.values, ["id", "user_id", "total"]);
+    assert_eq!(columns.has_more, Some(false));
+    let columns = client
+        .complete_prompt_argument("query", "column", "id", context(&[("table", "orders")]))
+        .await?;
+    assert_eq!(columns.values, ["id", "user_id"]);
+    // the errors of the completer are returned
+    assert!(
+        client
+            .complete_prompt_argument("query", "column", "", None)
+            .await
+            .is_err()
+    );
+
+    // the arguments without completer have no values
+    let values = client.complete_prompt_simple("query", "limit", "").await?;
+    assert!(values.is_empty());
+    let values = client
+        .complete_resource_simple("db://{table}/schema", "table", "")
+        .await?;
+    assert!(values.is_empty());
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dynamic_prompt_completion_truncated() -> anyhow::Result<()> {
+    let client = serve_with_test_client(Server::new()).await?;
+
+    let names = client
+        .complete_prompt_argument("greet", "name", "user", None)
+        .await?;
+    assert_eq!(names.values.len(), CompletionInfo::MAX_VALUES);
+    assert_eq!(names.values[0], "user0");
+    assert_eq!(names.total, Some(150));
+    assert_eq!(names.has_more, Some(true));
+
+    let names = client
+        .complete_prompt_simple("greet", "name", "USER12")
+        .await?;
+    assert_eq!(names.len(), 11);
+    assert_eq!(names[..2], ["user12", "user120"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resource_variable_completion() -> anyhow::Result<()> {
+    let client = serve_with_test_client(TemplateServer::new()).await?;
+
+    let tables = client
+        .complete_resource_simple("db://{table}/schema", "table", "pro")
+        .await?;
+    assert_eq!(tables, ["products"]);
+    let values = client
+        .complete_resource_simple("db://{table}/rows", "table", "")
+        .await?;
+    assert!(values.is_empty());
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_router_completion() -> anyhow::Result<()> {
+    let server = Server::new();
+    let router = Router::new(server.clone())
+        .with_prompts(server.prompt_router.clone())
+        .with_completions(CompletionRouter::new().with_resource_variable(
+            "db://{table}/schema",
+            "table",
+            ["users", "orders", "products"],
+        ));
+    let client = serve_with_test_client(router).await?;
+
+    let tables = client
+        .complete_prompt_simple("query", "table", "ord")
+        .await?;
+    assert_eq!(tables, ["orders"]);
+    let tables = client
+        .complete_resource_simple("db://{table}/schema", "table", "pro")
+        .await?;
+    assert_eq!(tables, ["products"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[test]
+fn test_completion_router_from_prompt_router() {
+    let server = Server::new();
+    let router = CompletionRouter::new().with_prompt_router(&server.prompt_router);
+    assert!(
+        router
+            .completer(&Reference::for_prompt("query"), "column")
+            .is_some()
+    );
+    assert!(
+        router
+            .completer(&Reference::for_prompt("greet"), "name")
+            .is_some()
+    );
+    assert!(
+        router
+            .completer(&Reference::for_prompt("query"), "limit")
+            .is_none()
+    );
+}
@@ -0,0 +1,197 @@
+//cargo test --test test_prompt_template --features "client server macros prompt-template"
+mod common;
+
+use common::handlers::{TestClientHandler, serve_with_test_client};
+use rmcp::{
+    ErrorData, RoleServer, ServerHandler,
+    handler::server::{
+        prompt::template::{PromptTemplate, PromptTemplateError},
+        router::prompt::PromptRouter,
+    },
+    model::*,
+    prompt_handler,
+    service::{RequestContext, RunningService},
+};
+
+const REVIEW: &str = "\
+@user
+Review the following {{language}} code{{focus| for correctness}}.
+@resource mem://src/{{path}}
+@image mem://{{screenshot?}}
+@assistant
+I will review `{{path}}`.
+";
+
+#[derive(Debug, Clone)]
+struct Server {
+    prompt_router: PromptRouter<Self>,
+}
+
+#[prompt_handler]
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_resources()
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let contents = match request.uri.as_str() {
+            "mem://src/main.rs" => ResourceContents::text("fn main() {}", request.uri),
+            "mem://screen.png" => ResourceContents::BlobResourceContents {
+                uri: request.uri,
+                mime_type: Some("image/png".into()),
+                blob: "iVBORw0KGgo=".into(),
+                meta: None,
+            },
+            _ => return Err(ErrorData::resource_not_found("no such resource", None)),
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+}
+
+async fn serve() -> anyhow::Result<RunningService<rmcp::RoleClient, TestClientHandler>> {
+    // as registered from a database
+    let templates = [
+        ("review", REVIEW, "Review some code"),
+        ("greet", "Say hello to {{name}}.", "Greet someone"),
+    ];
+    let mut prompt_router = PromptRouter::new();
+    for (name, source, description) in templates {
+        let template = PromptTemplate::parse(name, source)?.with_description(description);
+        prompt_router.add_route(template.into_route());
+    }
+    serve_with_test_client(Server { prompt_router }).await
+}
+
+fn arguments(value: serde_json::Value) -> Option<serde_json::Map<String, serde_json::Value>> {
+    value.as_object().cloned()
+}
+
+#[tokio::test]
+async fn test_prompt_template() -> anyhow::Result<()> {
+    let client = serve().await?;
+
+    let prompts = client.list_all_prompts().await?;
+    assert_eq!(prompts.len(), 2);
+    let review = prompts
+        .iter()
+        .find(|prompt| prompt.name == "review")
+        .unwrap();
+    assert_eq!(review.description.as_deref(), Some("Review some code"));
+    let required: Vec<_> = review
+        .arguments
+        .iter()
+        .flatten()
+        .map(|argument| (argument.name.as_str(), argument.required))
+        .collect();
+    assert_eq!(
+        required,
+        [
+            ("language", Some(true)),
+            ("focus", Some(false)),
+            ("path", Some(true)),
+            ("screenshot", Some(false)),
+        ]
+    );
+
+    let result = client
+        .get_prompt(GetPromptRequestParam {
+            name: "review".into(),
+            arguments: arguments(serde_json::json!({
+                "language": "Rust",
+                "path": "main.rs",
+                "screenshot": "screen.png",
+            })),
+        })
+        .await?;
+    assert_eq!(result.description.as_deref(), Some("Review some code"));
+    let [text, resource, image, answer] = result.messages.as_slice() else {
+        panic!("unexpected messages: {:?}", result.messages);
+    };
+    assert_eq!(
+        text.content,
+        PromptMessageContent::text("Review the following Rust code for correctness.")
+    );
+    let PromptMessageContent::Resource { resource } = &resource.content else {
+        panic!("not a resource");
+    };
+    assert_eq!(resource.get_text(), "fn main() {}");
+    let PromptMessageContent::Image { image } = &image.content else {
+        panic!("not an image");
+    };
+    assert_eq!(image.mime_type, "image/png");
+    assert_eq!(answer.role, PromptMessageRole::Assistant);
+    assert_eq!(
+        answer.content,
+        PromptMessageContent::text("I will review `main.rs`.")
+    );
+
+    // the image is skipped without its optional argument
+    let result = client
+        .get_prompt(GetPromptRequestParam {
+            name: "review".into(),
+            arguments: arguments(serde_json::json!({
+                "language": "Rust",
+                "path": "main.rs",
+                "focus": " for performance",
+            })),
+        })
+        .await?;
+    assert_eq!(result.messages.len(), 3);
+    assert_eq!(
+        result.messages[0].content,
+        PromptMessageContent::text("Review the following Rust code for performance.")
+    );
+
+    // missing, unknown and unreadable arguments are rejected, as well as the ones leaving
+    // their part of the uri
+    for invalid in [
+        serde_json::json!({ "language": "Rust" }),
+        serde_json::json!({ "language": "Rust", "path": "main.rs", "lines": 10 }),
+        serde_json::json!({ "language": "Rust", "path": "lib.rs" }),
+        serde_json::json!({ "language": "Rust", "path": "main.rs", "screenshot": "src/main.rs" }),
+        serde_json::json!({ "language": "Rust", "path": "..", "screenshot": "screen.png" }),
+        serde_json::json!({ "language": "Rust", "path": "../screen.png" }),
+    ] {
+        let result = client
+            .get_prompt(GetPromptRequestParam {
+                name: "review".into(),
+                arguments: arguments(invalid),
+            })
+            .await;
+        assert!(result.is_err());
+    }
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[test]
+fn test_prompt_template_from_path() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("rmcp-prompt-template-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("code_review.prompt");
+    std::fs::write(&path, REVIEW)?;
+    let template = PromptTemplate::from_path(&path)?;
+    assert_eq!(template.name(), "code_review");
+    assert_eq!(template.prompt().arguments.as_ref().map(Vec::len), Some(4));
+
+    std::fs::write(&path, "@user\n{{path")?;
+    assert!(matches!(
+        PromptTemplate::from_path(&path),
+        Err(PromptTemplateError::UnclosedPlaceholder(2))
+    ));
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use rmcp::{
+    ClientHandler, ErrorData, Json, ServerHandler, ServiceExt,
+    handler::server::tool::ToolRouter,
+    model::{
+        AnnotateAble, CallToolRequestParam, CallToolResult, ClientInfo, Content,
+        CreateElicitationRequest, CreateElicitationRequestParam, ErrorCode, ProtocolVersion,
+        RawAudioContent, RawContent, RawResource, ServerRequest,
+    },
+    service::ServiceError,
+    tool, tool_handler, tool_router,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+#[derive(Serialize, JsonSchema)]
+struct Sum {
+    value: i64,
+}
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(title = "Add", description = "Add one and two")]
+    async fn add(&self) -> Json<Sum> {
+        Json(Sum { value: 3 })
+    }
+
+    #[tool(description = "Play a recording")]
+    async fn play(&self) -> Result<CallToolResult, ErrorData> {
+        Ok(CallToolResult::success(vec![
+            Content::resource_link(RawResource::new("file:///take.wav", "take")),
+            RawContent::Audio(RawAudioContent {
+                data: "UklGRg==".into(),
+                mime_type: "audio/wav".into(),
+            })
+            .no_annotation(),
+        ]))
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+#[derive(Clone, Default)]
+struct Client {
+    protocol_version: ProtocolVersion,
+}
+
+impl ClientHandler for Client {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            protocol_version: self.protocol_version.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[test]
+fn test_known_versions_are_ordered() {
+    assert!(ProtocolVersion::V_2024_11_05 < ProtocolVersion::V_2025_03_26);
+    assert!(ProtocolVersion::V_2025_03_26 < ProtocolVersion::V_2025_06_18);
+    assert_eq!(ProtocolVersion::LATEST, ProtocolVersion::V_2025_06_18);
+    let unknown: ProtocolVersion = serde_json::from_value(json!("2099-01-01")).unwrap();
+    assert!(!unknown.is_known());
+    assert_eq!(unknown.partial_cmp(&ProtocolVersion::LATEST), None);
+    assert_eq!(
+        unknown.partial_cmp(&unknown.clone()),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert_eq!(
+        ProtocolVersion::negotiate(&ProtocolVersion::V_2025_03_26, &ProtocolVersion::LATEST),
+        Some(ProtocolVersion::V_2025_03_26)
+    );
+    assert_eq!(
+        ProtocolVersion::negotiate(&ProtocolVersion::LATEST, &ProtocolVersion::V_2024_11_05),
+        Some(ProtocolVersion::V_2024_11_05)
+    );
+    assert_eq!(
+        ProtocolVersion::negotiate(&unknown, &ProtocolVersion::LATEST),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_latest_version_is_negotiated_by_default() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = tokio::spawn(async move {
+        let service = Server::new().serve(server_transport).await?;
+        let version = service.peer().protocol_version().cloned();
+        service.waiting().await?;
+        anyhow::Ok(version)
+    });
+    let client = Client::default().serve(client_transport).await?;
+    assert_eq!(
+        client.peer().protocol_version(),
+        Some(&ProtocolVersion::LATEST)
+    );
+
+    let tools = client.list_all_tools().await?;
+    let add = tools.iter().find(|tool| tool.name == "add").unwrap();
+    assert_eq!(add.title.as_deref(), Some("Add"));
+    assert!(add.output_schema.is_some());
+
+    client.cancel().await?;
+    assert_eq!(server.await??, Some(ProtocolVersion::LATEST));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fields_are_downgraded_for_older_versions() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Server::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = Client {
+        protocol_version: ProtocolVersion::V_2024_11_05,
+    }
+    .serve(client_transport)
+    .await?;
+    assert_eq!(
+        client.peer().protocol_version(),
+        Some(&ProtocolVersion::V_2024_11_05)
+    );
+
+    let tools = client.list_all_tools().await?;
+    let add = tools.iter().find(|tool| tool.name == "add").unwrap();
+    assert_eq!(add.title, None);
+    assert_eq!(add.output_schema, None);
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "add".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.structured_content, None);
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("expected text content, got {:?}", result.content);
+    };
+    assert_eq!(
+        serde_json::from_str::<Value>(&text.text)?,
+        json!({ "value": 3 })
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_contents_are_downgraded_for_older_versions() -> anyhow::Result<()> {
+    for (version, expected) in [
+        (
+            ProtocolVersion::V_2024_11_05,
+            ["file:///take.wav", "[audio/wav audio omitted]"],
+        ),
+        (ProtocolVersion::V_2025_03_26, ["file:///take.wav", ""]),
+    ] {
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let service = Server::new().serve(server_transport).await?;
+            service.waiting().await?;
+            anyhow::Ok(())
+        });
+        let client = Client {
+            protocol_version: version.clone(),
+        }
+        .serve(client_transport)
+        .await?;
+
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: "play".into(),
+                arguments: None,
+            })
+            .await?;
+        let [link, audio] = result.content.as_slice() else {
+            panic!("unexpected content: {:?}", result.content);
+        };
+        // resource links were introduced by 2025-06-18, audio by 2025-03-26
+        assert_eq!(
+            link.as_text().map(|text| text.text.as_str()),
+            Some(expected[0])
+        );
+        match &audio.raw {
+            RawContent::Text(text) => assert_eq!(text.text, expected[1], "{version}"),
+            RawContent::Audio(audio) => {
+                assert!(expected[1].is_empty(), "{version}");
+                assert_eq!(audio.mime_type, "audio/wav");
+            }
+            other => panic!("unexpected content: {other:?}"),
+        }
+
+        client.cancel().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_newer_requests_are_rejected_for_older_versions() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = tokio::spawn(Server::new().serve(server_transport));
+    let client = Client {
+        protocol_version: ProtocolVersion::V_2025_03_26,
+    }
+    .serve(client_transport)
+    .await?;
+    let server = server.await??;
+
+    let request = ServerRequest::CreateElicitationRequest(CreateElicitationRequest::new(
+        CreateElicitationRequestParam {
+            message: "Your name?".into(),
+            requested_schema: Default::default(),
+        },
+    ));
+    let result = server.peer().send_request(request).await;
+    assert!(matches!(
+        result,
+        Err(ServiceError::McpError(error)) if error.code == ErrorCode::METHOD_NOT_FOUND
+    ));
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_version_is_rejected() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = tokio::spawn(Server::new().serve(server_transport));
+    let (reader, mut writer) = tokio::io::split(client_transport);
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "1.0.0",
+            "capabilities": {},
+            "clientInfo": { "name": "raw", "version": "0.0.0" }
+        }
+    });
+    writer.write_all(format!("{request}\n").as_bytes()).await?;
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .expect("an error response");
+    let response: Value = serde_json::from_str(&line)?;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Unsupported protocol version");
+    assert_eq!(response["error"]["data"]["requested"], "1.0.0");
+    assert_eq!(
+        response["error"]["data"]["supported"],
+        json!(["2024-11-05", "2025-03-26", "2025-06-18"])
+    );
+    assert!(server.await?.is_err());
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceError, ServiceExt,
+    handler::server::{tool::ToolRouter, wrapper::Parameters},
+    model::{CallToolRequestParam, RawContent},
+    schemars, tool, tool_handler, tool_router,
+    transport::{
+        IntoTransport, RecordTransport, ReplayTransport,
+        record::{Direction, RecordedMessage, read_recording},
+    },
+};
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SumRequest {
+    a: i32,
+    b: i32,
+}
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Add two numbers")]
+    async fn sum(&self, Parameters(SumRequest { a, b }): Parameters<SumRequest>) -> String {
+        (a + b).to_string()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+fn sum(a: i32, b: i32) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "sum".into(),
+        arguments: serde_json::json!({ "a": a, "b": b }).as_object().cloned(),
+    }
+}
+
+fn text(result: rmcp::model::CallToolResult) -> String {
+    match &result.content[0].raw {
+        RawContent::Text(text) => text.text.clone(),
+        content => panic!("unexpected content {content:?}"),
+    }
+}
+
+/// Record a client calling `sum(1, 2)` and `sum(3, 4)`.
+async fn record_session() -> anyhow::Result<Vec<RecordedMessage>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Server::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let buffer = Buffer::default();
+    let transport = IntoTransport::<RoleClient, _, _>::into_transport(client_transport);
+    let client = ().serve(RecordTransport::new(transport, buffer.clone())).await?;
+    assert_eq!(text(client.call_tool(sum(1, 2)).await?), "3");
+    assert_eq!(text(client.call_tool(sum(3, 4)).await?), "7");
+    client.cancel().await?;
+
+    let bytes = buffer.0.lock().unwrap().clone();
+    Ok(read_recording(bytes.as_slice())?)
+}
+
+#[tokio::test]
+async fn test_recording_has_both_directions() -> anyhow::Result<()> {
+    let recording = record_session().await?;
+    let summary: Vec<_> = recording
+        .iter()
+        .map(|recorded| {
+            let message = &recorded.message;
+            let kind = message["method"].as_str().unwrap_or("response");
+            (recorded.direction, kind.to_owned())
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Direction::Sent, "initialize".to_owned()),
+            (Direction::Received, "response".to_owned()),
+            (Direction::Sent, "notifications/initialized".to_owned()),
+            (Direction::Sent, "tools/call".to_owned()),
+            (Direction::Received, "response".to_owned()),
+            (Direction::Sent, "tools/call".to_owned()),
+            (Direction::Received, "response".to_owned()),
+        ]
+    );
+    assert!(
+        recording
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_to_client_rewrites_ids() -> anyhow::Result<()> {
+    let mut recording = record_session().await?;
+    // the ids of the recorded session don't have to be the ones of the replay
+    for recorded in &mut recording {
+        if let Some(id) = recorded.message["id"].as_u64() {
+            recorded.message["id"] = (id + 100).into();
+        }
+    }
+    let replay = ReplayTransport::new(recording);
+    let client = ().serve(replay.clone()).await?;
+    // in another order than the recording
+    assert_eq!(text(client.call_tool(sum(3, 4)).await?), "7");
+    assert_eq!(text(client.call_tool(sum(1, 2)).await?), "3");
+    assert!(replay.remaining().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_rejects_unknown_requests() -> anyhow::Result<()> {
+    let replay = ReplayTransport::new(record_session().await?);
+    let client = ().serve(replay.clone()).await?;
+    let result = client.call_tool(sum(5, 6)).await;
+    assert!(matches!(result, Err(ServiceError::TransportSend(_))));
+    assert_eq!(replay.remaining().len(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mirrored_replay_to_server() -> anyhow::Result<()> {
+    let replay = ReplayTransport::mirrored(record_session().await?);
+    let server = Server::new().serve(replay.clone()).await?;
+    // the server closes once it answered the whole recording
+    server.waiting().await?;
+    assert!(replay.remaining().is_empty());
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceError, ServiceExt,
+    handler::server::tool::ToolRouter,
+    model::{CallToolRequestParam, ErrorCode},
+    service::{OverflowPolicy, RunningService, ServeConfig},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Clone, Default)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+            ..Default::default()
+        }
+    }
+
+    #[tool(description = "Finish after a while")]
+    async fn slow(&self) -> String {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        "done".to_string()
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+async fn connect(
+    server: Server,
+    config: ServeConfig,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = server
+            .serve_with_config(server_transport, config, Default::default())
+            .await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+fn slow() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "slow".into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_requests_over_the_limit_are_queued() -> anyhow::Result<()> {
+    let server = Server::new();
+    let config = ServeConfig::default()
+        .with_max_in_flight_requests(2)
+        .with_sink_buffer_size(1)
+        .with_peer_channel_buffer_size(1);
+    let client = connect(server.clone(), config).await?;
+
+    let calls = (0..5).map(|_| client.call_tool(slow()));
+    for result in futures::future::join_all(calls).await {
+        result?;
+    }
+    assert_eq!(server.max_running.load(Ordering::SeqCst), 2);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_requests_over_the_method_limit_are_rejected() -> anyhow::Result<()> {
+    let server = Server::new();
+    let config = ServeConfig::default()
+        .with_method_limit("tools/call", 1)
+        .with_overflow_policy(OverflowPolicy::Reject);
+    let client = connect(server.clone(), config).await?;
+
+    let (first, second, tools) = tokio::join!(
+        client.call_tool(slow()),
+        async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            client.call_tool(slow()).await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            client.list_all_tools().await
+        },
+    );
+    first?;
+    // other methods are not limited
+    assert_eq!(tools?.len(), 1);
+    let Err(ServiceError::McpError(error)) = second else {
+        panic!("expected a rejection, got {second:?}");
+    };
+    assert_eq!(error.code, ErrorCode::INTERNAL_ERROR);
+    let data = error.data.unwrap();
+    assert_eq!(data["reason"], OverflowPolicy::REJECTED_REASON);
+    assert_eq!(data["method"], "tools/call");
+
+    // the limit is released once the call is done
+    client.call_tool(slow()).await?;
+    assert_eq!(server.max_running.load(Ordering::SeqCst), 1);
+
+    client.cancel().await?;
+    Ok(())
+}
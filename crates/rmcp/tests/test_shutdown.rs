@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use rmcp::{
+    RoleClient, RoleServer, ServerHandler, ServiceError, ServiceExt,
+    handler::server::tool::ToolRouter,
+    model::CallToolRequestParam,
+    service::{QuitReason, RunningService},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Finish after a while")]
+    async fn slow(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "done".to_string()
+    }
+
+    #[tool(description = "Never finish")]
+    async fn forever(&self) -> String {
+        std::future::pending().await
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+async fn connect() -> anyhow::Result<(
+    RunningService<RoleServer, Server>,
+    RunningService<RoleClient, ()>,
+)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        Server::new().serve(server_transport),
+        ().serve(client_transport)
+    );
+    Ok((server?, client?))
+}
+
+fn call(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_answers_in_flight_requests() -> anyhow::Result<()> {
+    let (server, client) = connect().await?;
+
+    let (in_flight, late, quit_reason) = tokio::join!(
+        client.call_tool(call("slow")),
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            client.call_tool(call("slow")).await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            server.shutdown(Duration::from_secs(5)).await
+        },
+    );
+    assert!(!in_flight?.content.is_empty());
+    let Err(ServiceError::McpError(error)) = late else {
+        panic!("expected a rejection, got {late:?}");
+    };
+    assert_eq!(error.message, "service is shutting down");
+    assert!(matches!(
+        quit_reason?,
+        QuitReason::Shutdown { abandoned: 0 }
+    ));
+
+    // the transport is closed
+    assert!(matches!(client.waiting().await?, QuitReason::Closed));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_abandons_requests_after_grace_period() -> anyhow::Result<()> {
+    let (server, client) = connect().await?;
+
+    let (result, late, quit_reason) = tokio::join!(
+        client.call_tool(call("forever")),
+        async {
+            tokio::time::sleep(Duration::from_millis(75)).await;
+            client.call_tool(call("slow")).await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            server.shutdown(Duration::from_millis(50)).await
+        },
+    );
+    assert!(result.is_err());
+    // rejected requests are not counted as abandoned
+    assert!(late.is_err());
+    assert!(matches!(
+        quit_reason?,
+        QuitReason::Shutdown { abandoned: 1 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_when_idle() -> anyhow::Result<()> {
+    let (server, client) = connect().await?;
+    client.list_all_tools().await?;
+    assert!(matches!(
+        server.shutdown(Duration::from_secs(5)).await?,
+        QuitReason::Shutdown { abandoned: 0 }
+    ));
+    Ok(())
+}
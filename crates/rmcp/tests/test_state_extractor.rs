@@ -0,0 +1,259 @@
+//cargo test --test test_state_extractor --features "client server macros"
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use common::handlers::{TestClientHandler, serve_with_test_client};
+use rmcp::{
+    ErrorData, ServerHandler,
+    handler::server::{
+        common::AsRequestContext,
+        router::{Router, tool::ToolRouter},
+        tool::{AsyncFromContextPart, State},
+        wrapper::Parameters,
+    },
+    model::{
+        CallToolRequest, CallToolRequestParam, ClientRequest, GetPromptRequestParam, Meta,
+        PromptMessage, PromptMessageContent, PromptMessageRole, RawContent, ServerResult,
+    },
+    prompt, prompt_router, schemars,
+    service::PeerRequestOptions,
+    tool, tool_handler, tool_router,
+};
+
+type Database = Arc<Mutex<Vec<String>>>;
+
+/// The databases of the tenants
+#[derive(Debug, Clone, Default)]
+struct Databases(Arc<HashMap<String, Database>>);
+
+impl Databases {
+    fn new(tenants: &[&str]) -> Self {
+        Self(Arc::new(
+            tenants
+                .iter()
+                .map(|tenant| (tenant.to_string(), Database::default()))
+                .collect(),
+        ))
+    }
+
+    fn rows(&self, tenant: &str) -> Vec<String> {
+        self.0[tenant].lock().unwrap().clone()
+    }
+}
+
+/// The database of the tenant named in the `tenant` field of the request meta
+struct TenantDb(Database);
+
+impl<C: AsRequestContext + Send> AsyncFromContextPart<C> for TenantDb {
+    async fn from_context_part_async(context: &mut C) -> Result<Self, ErrorData> {
+        let State(databases) = State::<Databases>::from_context_part_async(context).await?;
+        let tenant = context
+            .as_request_context()
+            .meta
+            .get("tenant")
+            .and_then(|tenant| tenant.as_str())
+            .ok_or_else(|| ErrorData::invalid_request("missing tenant", None))?
+            .to_owned();
+        // as a connection pool would
+        tokio::task::yield_now().await;
+        let database =
+            databases.0.get(&tenant).cloned().ok_or_else(|| {
+                ErrorData::invalid_request(format!("unknown tenant {tenant}"), None)
+            })?;
+        Ok(TenantDb(database))
+    }
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct InsertRequest {
+    row: String,
+}
+
+#[derive(Debug, Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+#[tool_router]
+impl Server {
+    fn new(databases: Databases) -> Self {
+        Self {
+            tool_router: Self::tool_router().with_state(databases),
+        }
+    }
+
+    /// Count the rows of every tenant
+    #[tool]
+    async fn count(&self, State(databases): State<Databases>) -> String {
+        let count: usize = databases
+            .0
+            .values()
+            .map(|db| db.lock().unwrap().len())
+            .sum();
+        count.to_string()
+    }
+
+    /// Insert a row in the database of the tenant
+    #[tool]
+    async fn insert(&self, Parameters(request): Parameters<InsertRequest>, db: TenantDb) -> String {
+        db.0.lock().unwrap().push(request.row);
+        "inserted".to_owned()
+    }
+
+    /// Insert a row in the database of the tenant
+    #[tool]
+    fn insert_flattened(#[arg] row: String, db: TenantDb) -> String {
+        db.0.lock().unwrap().push(row);
+        "inserted".to_owned()
+    }
+
+    /// Read a state which is never registered
+    #[tool]
+    fn missing(State(_count): State<u64>) -> String {
+        "unreachable".to_owned()
+    }
+}
+
+fn call(name: &'static str, arguments: serde_json::Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn text(result: &rmcp::model::CallToolResult) -> &str {
+    match &result.content[0].raw {
+        RawContent::Text(text) => &text.text,
+        content => panic!("unexpected content {content:?}"),
+    }
+}
+
+async fn call_as_tenant(
+    client: &rmcp::service::RunningService<rmcp::RoleClient, TestClientHandler>,
+    tenant: &str,
+    param: CallToolRequestParam,
+) -> Result<rmcp::model::CallToolResult, rmcp::ServiceError> {
+    let mut meta = Meta::new();
+    meta.insert("tenant".to_owned(), tenant.into());
+    let options = PeerRequestOptions {
+        meta: Some(meta),
+        ..PeerRequestOptions::no_options()
+    };
+    let request = ClientRequest::CallToolRequest(CallToolRequest::new(param));
+    let response = client
+        .send_request_with_option(request, options)
+        .await?
+        .await_response()
+        .await?;
+    match response {
+        ServerResult::CallToolResult(result) => Ok(result),
+        response => panic!("unexpected response {response:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_state_and_async_extractors() -> anyhow::Result<()> {
+    let databases = Databases::new(&["acme", "globex"]);
+    let client = serve_with_test_client(Server::new(databases.clone())).await?;
+
+    let result = call_as_tenant(
+        &client,
+        "acme",
+        call("insert", serde_json::json!({ "row": "a" })),
+    )
+    .await?;
+    assert_eq!(text(&result), "inserted");
+    let params = call("insert_flattened", serde_json::json!({ "row": "b" }));
+    call_as_tenant(&client, "globex", params).await?;
+    assert_eq!(databases.rows("acme"), ["a"]);
+    assert_eq!(databases.rows("globex"), ["b"]);
+
+    let result = client
+        .call_tool(call("count", serde_json::json!({})))
+        .await?;
+    assert_eq!(text(&result), "2");
+
+    // the extractors fail the call before the tool runs
+    let params = call("insert", serde_json::json!({ "row": "c" }));
+    let error = call_as_tenant(&client, "initech", params)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("unknown tenant initech"),
+        "{error}"
+    );
+    let error = client
+        .call_tool(call("insert_flattened", serde_json::json!({ "row": "c" })))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("missing tenant"), "{error}");
+    let error = client
+        .call_tool(call("missing", serde_json::json!({})))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("missing state u64"), "{error}");
+    assert_eq!(databases.rows("acme"), ["a"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+struct Greeter;
+
+impl ServerHandler for Greeter {}
+
+#[prompt_router]
+impl Greeter {
+    /// Greet the user
+    #[prompt]
+    async fn greeting(&self, State(greeting): State<&'static str>) -> Vec<PromptMessage> {
+        vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!("{greeting}, user"),
+        )]
+    }
+}
+
+#[tool_router]
+impl Greeter {
+    /// Tell the greeting
+    #[tool]
+    fn greeting_tool(State(greeting): State<&'static str>) -> String {
+        greeting.to_owned()
+    }
+}
+
+#[tokio::test]
+async fn test_router_state() -> anyhow::Result<()> {
+    let router = Router::new(Greeter)
+        .with_tools(Greeter::tool_router())
+        .with_prompts(Greeter::prompt_router())
+        .with_state("Howdy");
+    let client = serve_with_test_client(router).await?;
+
+    let result = client
+        .call_tool(call("greeting_tool", serde_json::json!({})))
+        .await?;
+    assert_eq!(text(&result), "Howdy");
+    let prompt = client
+        .get_prompt(GetPromptRequestParam {
+            name: "greeting".to_owned(),
+            arguments: None,
+        })
+        .await?;
+    match &prompt.messages[0].content {
+        PromptMessageContent::Text { text } => assert_eq!(text, "Howdy, user"),
+        content => panic!("unexpected content {content:?}"),
+    }
+
+    client.cancel().await?;
+    Ok(())
+}
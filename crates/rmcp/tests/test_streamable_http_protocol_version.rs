@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Request, StatusCode};
+use http_body_util::Full;
+use rmcp::{
+    ServerHandler, ServiceExt,
+    model::ProtocolVersion,
+    transport::{
+        StreamableHttpClientTransport, StreamableHttpServerConfig,
+        streamable_http_server::{
+            session::local::LocalSessionManager, tower::StreamableHttpService,
+        },
+    },
+};
+use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+struct Server;
+
+impl ServerHandler for Server {}
+
+fn service() -> StreamableHttpService<Server, LocalSessionManager> {
+    StreamableHttpService::new(
+        || Ok(Server),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    )
+}
+
+fn post(message: Value, session_id: Option<&str>, version: Option<&str>) -> Request<Full<Bytes>> {
+    let mut builder = Request::post("/mcp")
+        .header("Accept", "application/json, text/event-stream")
+        .header("Content-Type", "application/json");
+    if let Some(session_id) = session_id {
+        builder = builder.header("Mcp-Session-Id", session_id);
+    }
+    if let Some(version) = version {
+        builder = builder.header("MCP-Protocol-Version", version);
+    }
+    builder
+        .body(Full::new(Bytes::from(message.to_string())))
+        .expect("valid request")
+}
+
+/// Initialize a session with `version`, returning its id.
+async fn initialize(
+    service: &StreamableHttpService<Server, LocalSessionManager>,
+    version: &str,
+) -> String {
+    let response = service
+        .handle(post(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": version,
+                    "capabilities": {},
+                    "clientInfo": { "name": "raw", "version": "0.0.0" }
+                }
+            }),
+            None,
+            None,
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let session_id = response.headers()["Mcp-Session-Id"]
+        .to_str()
+        .expect("session id")
+        .to_owned();
+    let response = service
+        .handle(post(
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            Some(&session_id),
+            Some(version),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    session_id
+}
+
+fn ping(id: u32) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": "ping" })
+}
+
+#[tokio::test]
+async fn test_header_is_validated_against_the_session() {
+    let service = service();
+    let session_id = initialize(&service, "2025-06-18").await;
+
+    for version in ["1999-01-01", "2025-03-26"] {
+        let response = service
+            .handle(post(ping(1), Some(&session_id), Some(version)))
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{version}");
+    }
+
+    let response = service
+        .handle(post(ping(2), Some(&session_id), Some("2025-06-18")))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_header_may_be_omitted() {
+    let service = service();
+    let session_id = initialize(&service, "2025-06-18").await;
+
+    // the server falls back to the negotiated version
+    let response = service.handle(post(ping(1), Some(&session_id), None)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_legacy_sessions_may_omit_header() {
+    let service = service();
+    let session_id = initialize(&service, "2025-03-26").await;
+
+    let response = service.handle(post(ping(1), Some(&session_id), None)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = service
+        .handle(post(ping(2), Some(&session_id), Some("2025-06-18")))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_client_sends_negotiated_version() -> anyhow::Result<()> {
+    let router = axum::Router::new().nest_service("/mcp", service());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    let ct = CancellationToken::new();
+    tokio::spawn({
+        let ct = ct.clone();
+        async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(ct.cancelled_owned())
+                .await;
+        }
+    });
+
+    let transport = StreamableHttpClientTransport::from_uri(format!("http://{address}/mcp"));
+    let client = ().serve(transport).await?;
+    assert_eq!(
+        client.peer().protocol_version(),
+        Some(&ProtocolVersion::LATEST)
+    );
+    // rejected with 400 if the header doesn't match the session
+    client.list_all_tools().await?;
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ServerHandler, ServiceExt,
+    handler::server::tool::{CancelOutcome, Cancellation, ToolRouter},
+    tool, tool_handler, tool_router,
+};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+struct CancellableServer {
+    tool_router: ToolRouter<Self>,
+    saw_cancellation: Arc<AtomicBool>,
+    cleaned_up: Arc<AtomicBool>,
+}
+
+#[tool_router]
+impl CancellableServer {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+            saw_cancellation: Default::default(),
+            cleaned_up: Default::default(),
+        }
+    }
+
+    #[tool(description = "Work until cancelled, then return anyway")]
+    async fn cooperative(&self, ct: CancellationToken) -> String {
+        ct.cancelled().await;
+        self.saw_cancellation.store(true, Ordering::SeqCst);
+        "stopped".to_string()
+    }
+
+    #[tool(description = "Never finish unless aborted")]
+    async fn abortable(&self, cancellation: Cancellation) -> CancelOutcome<String> {
+        let cleaned_up = self.cleaned_up.clone();
+        cancellation
+            .run_with_cleanup(std::future::pending::<String>(), || async move {
+                cleaned_up.store(true, Ordering::SeqCst);
+            })
+            .await
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for CancellableServer {}
+
+struct RawClient {
+    writer: tokio::io::WriteHalf<DuplexStream>,
+    lines: Lines<BufReader<tokio::io::ReadHalf<DuplexStream>>>,
+}
+
+impl RawClient {
+    async fn connect(server: CancellableServer) -> anyhow::Result<Self> {
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let service = server.serve(server_transport).await?;
+            service.waiting().await?;
+            anyhow::Ok(())
+        });
+        let (reader, writer) = tokio::io::split(client_transport);
+        let mut client = Self {
+            writer,
+            lines: BufReader::new(reader).lines(),
+        };
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "raw", "version": "0.0.0" }
+                }
+            }))
+            .await?;
+        client.receive().await?;
+        client
+            .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(client)
+    }
+
+    async fn send(&mut self, message: Value) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> anyhow::Result<Value> {
+        let line = self
+            .lines
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("connection closed"))?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// Call `tool`, cancel it, then ping and return every message received until the pong.
+    async fn call_and_cancel(&mut self, tool: &str) -> anyhow::Result<Vec<Value>> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": tool }
+        }))
+        .await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 1, "reason": "user cancelled" }
+        }))
+        .await?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" }))
+            .await?;
+        let mut received = Vec::new();
+        loop {
+            let message = self.receive().await?;
+            let is_pong = message["id"] == json!(2);
+            received.push(message);
+            if is_pong {
+                return Ok(received);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cooperative_cancellation_sends_no_response() -> anyhow::Result<()> {
+    let server = CancellableServer::new();
+    let mut client = RawClient::connect(server.clone()).await?;
+    let received = client.call_and_cancel("cooperative").await?;
+    assert!(server.saw_cancellation.load(Ordering::SeqCst));
+    assert!(
+        received.iter().all(|message| message["id"] != json!(1)),
+        "{received:?}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_abort_on_cancellation_runs_cleanup() -> anyhow::Result<()> {
+    let server = CancellableServer::new();
+    let mut client = RawClient::connect(server.clone()).await?;
+    let received = client.call_and_cancel("abortable").await?;
+    assert!(server.cleaned_up.load(Ordering::SeqCst));
+    assert!(
+        received.iter().all(|message| message["id"] != json!(1)),
+        "{received:?}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_outcome_completed() {
+    let cancellation = Cancellation::new(
+        CancellationToken::new(),
+        rmcp::model::NumberOrString::Number(1),
+    );
+    let outcome = cancellation.run(async { 42 }).await;
+    assert_eq!(outcome, CancelOutcome::Completed(42));
+
+    cancellation.token().cancel();
+    let outcome = cancellation.run(std::future::pending::<u32>()).await;
+    assert!(outcome.is_cancelled());
+}
@@ -0,0 +1,103 @@
+//cargo test --test test_tool_client --features "client server macros"
+use rmcp::{
+    ErrorData, Json, ServerHandler, ServiceExt,
+    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    schemars,
+    service::TypedToolCallError,
+    tool, tool_handler, tool_router,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SumRequest {
+    pub a: i32,
+    pub b: i32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SumResult {
+    pub sum: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calculator {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_handler]
+impl ServerHandler for Calculator {}
+
+#[tool_router(client = CalculatorClient, vis = "pub")]
+impl Calculator {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Add two numbers
+    #[tool]
+    async fn sum(
+        &self,
+        Parameters(SumRequest { a, b }): Parameters<SumRequest>,
+    ) -> Json<SumResult> {
+        Json(SumResult { sum: a + b })
+    }
+
+    /// Divide two numbers
+    #[tool(name = "divide")]
+    fn div(
+        &self,
+        #[arg(description = "The dividend")] a: i32,
+        #[arg(description = "The divisor")] b: i32,
+    ) -> Result<Json<i32>, ErrorData> {
+        a.checked_div(b)
+            .map(Json)
+            .ok_or_else(|| ErrorData::invalid_params("division by zero", None))
+    }
+
+    /// Say hello
+    #[tool]
+    async fn hello(&self) -> String {
+        "hello".to_string()
+    }
+}
+
+async fn client() -> anyhow::Result<CalculatorClient> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Calculator::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+    // the running service is kept alive by the peer of the client
+    let peer = client.peer().clone();
+    tokio::spawn(client.waiting());
+    Ok(CalculatorClient::new(peer))
+}
+
+#[tokio::test]
+async fn test_typed_client_calls() -> anyhow::Result<()> {
+    let client = client().await?;
+
+    let result = client.sum(SumRequest { a: 1, b: 2 }).await?;
+    assert_eq!(result, SumResult { sum: 3 });
+
+    assert_eq!(client.div(7, 2).await?, 3);
+
+    let result = client.hello().await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_typed_client_errors() -> anyhow::Result<()> {
+    let client = client().await?;
+    let error = client.div(1, 0).await.unwrap_err();
+    let TypedToolCallError::Service(rmcp::ServiceError::McpError(error)) = error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert_eq!(error.message, "division by zero");
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//cargo test --test test_tool_flattened_args --features "client server macros"
+mod common;
+
+use common::handlers::{TestClientHandler, serve_with_test_client};
+use rmcp::{
+    Peer, RoleServer, ServerHandler,
+    handler::server::{
+        router::tool::ToolRouter,
+        tool::{Extension, ToolName},
+    },
+    model::{CallToolRequestParam, RawContent},
+    service::RequestContext,
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Debug, Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Greet someone
+    #[tool]
+    async fn greet(
+        &self,
+        #[arg(description = "Who to greet")] name: String,
+        #[arg(description = "How to greet", default = "Hello")] greeting: String,
+        #[arg(default = 1)] times: u8,
+        #[arg] punctuation: Option<char>,
+    ) -> String {
+        let punctuation = punctuation.unwrap_or('!');
+        vec![format!("{greeting}, {name}{punctuation}"); times as usize].join(" ")
+    }
+
+    /// Describe the call
+    #[tool]
+    fn describe(
+        #[arg(description = "A label")] label: String,
+        tool_name: ToolName,
+        _peer: Peer<RoleServer>,
+        context: RequestContext<RoleServer>,
+    ) -> String {
+        format!("{label}: {} #{}", tool_name.0, context.id)
+    }
+
+    /// Read an extension
+    #[tool]
+    async fn extension(
+        &self,
+        Extension(prefix): Extension<String>,
+        #[arg] suffix: String,
+    ) -> String {
+        format!("{prefix}{suffix}")
+    }
+}
+
+#[test]
+fn test_flattened_args_schema() {
+    let tool = Server::greet_tool_attr();
+    let schema = serde_json::Value::Object((*tool.input_schema).clone());
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["properties"]["name"]["description"], "Who to greet");
+    assert_eq!(
+        schema["properties"]["greeting"]["description"],
+        "How to greet"
+    );
+    assert_eq!(schema["properties"]["greeting"]["default"], "Hello");
+    assert_eq!(schema["properties"]["times"]["default"], 1);
+    assert!(schema["properties"]["punctuation"].is_object());
+    assert_eq!(schema["required"], serde_json::json!(["name"]));
+
+    // the extractors aren't part of the schema
+    let tool = Server::describe_tool_attr();
+    let schema = serde_json::Value::Object((*tool.input_schema).clone());
+    let properties: Vec<_> = schema["properties"].as_object().unwrap().keys().collect();
+    assert_eq!(properties, ["label"]);
+}
+
+async fn call(
+    client: &rmcp::service::RunningService<rmcp::RoleClient, TestClientHandler>,
+    name: &'static str,
+    arguments: serde_json::Value,
+) -> Result<String, rmcp::ServiceError> {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: arguments.as_object().cloned(),
+        })
+        .await?;
+    match &result.content[0].raw {
+        RawContent::Text(text) => Ok(text.text.clone()),
+        content => panic!("unexpected content {content:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_flattened_args_call() -> anyhow::Result<()> {
+    let client = serve_with_test_client(Server::new()).await?;
+
+    let greeting = call(&client, "greet", serde_json::json!({ "name": "Ada" })).await?;
+    assert_eq!(greeting, "Hello, Ada!");
+    let greeting = call(
+        &client,
+        "greet",
+        serde_json::json!({ "name": "Ada", "greeting": "Hi", "times": 2, "punctuation": "." }),
+    )
+    .await?;
+    assert_eq!(greeting, "Hi, Ada. Hi, Ada.");
+
+    let description = call(&client, "describe", serde_json::json!({ "label": "call" })).await?;
+    assert!(description.starts_with("call: describe #"), "{description}");
+
+    let error = call(&client, "greet", serde_json::json!({ "times": 2 }))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    assert!(error.message.contains("name"), "{}", error.message);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//cargo test --test test_tool_output --features "client server macros"
+mod common;
+
+use common::handlers::serve_with_test_client;
+use futures::StreamExt;
+use rmcp::{
+    ServerHandler,
+    handler::server::{router::tool::ToolRouter, tool_output::ToolOutputSink},
+    model::{CallToolRequestParam, Content},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Debug, Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Build the project
+    #[tool]
+    async fn build(&self, output: ToolOutputSink) -> String {
+        for step in ["fetch", "compile", "link"] {
+            output.send(format!("{step} done")).await.unwrap();
+        }
+        format!("streamed: {}", output.is_streaming())
+    }
+}
+
+fn texts(contents: &[Content]) -> Vec<&str> {
+    contents
+        .iter()
+        .map(|content| content.as_text().unwrap().text.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_streamed_tool_output() -> anyhow::Result<()> {
+    let client = serve_with_test_client(Server::new()).await?;
+    let mut call = client
+        .call_tool_streaming(CallToolRequestParam {
+            name: "build".into(),
+            arguments: None,
+        })
+        .await?;
+    let chunk = call.next().await.unwrap();
+    assert_eq!(chunk.as_text().unwrap().text, "fetch done");
+    // the result has all the chunks, consumed or not
+    let result = call.result().await?;
+    assert_eq!(
+        texts(&result.content),
+        ["fetch done", "compile done", "link done", "streamed: true"]
+    );
+
+    // without asking for streaming, the chunks are in the result
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "build".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(
+        texts(&result.content),
+        ["fetch done", "compile done", "link done", "streamed: false"]
+    );
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,316 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, ErrorData, RoleClient, ServerHandler, ServiceError, ServiceExt,
+    handler::server::{
+        task::{InMemoryTaskStore, StoredTask, TaskManager, TaskManagerConfig, TaskStore},
+        tool::ToolRouter,
+    },
+    model::{CallToolRequestParam, RawContent, Task, TaskRequestParam, TaskStatus},
+    service::{NotificationContext, RunningService},
+    tool, tool_handler, tool_router,
+};
+
+#[derive(Clone)]
+struct Server {
+    tool_router: ToolRouter<Self>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[tool_router]
+impl Server {
+    fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+            cancelled: Default::default(),
+        }
+    }
+
+    #[tool(description = "Finish after a while")]
+    async fn slow(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "done".to_string()
+    }
+
+    #[tool(description = "Run until cancelled")]
+    async fn forever(&self) -> String {
+        // the call is dropped when the task is cancelled
+        let _flag = SetOnDrop(self.cancelled.clone());
+        std::future::pending().await
+    }
+
+    #[tool(description = "Always fail")]
+    async fn fail(&self) -> Result<String, ErrorData> {
+        Err(ErrorData::internal_error("broken", None))
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for Server {}
+
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Default)]
+struct Client {
+    statuses: Arc<Mutex<Vec<Task>>>,
+}
+
+impl ClientHandler for Client {
+    async fn on_task_status(&self, params: Task, _context: NotificationContext<RoleClient>) {
+        self.statuses.lock().unwrap().push(params);
+    }
+}
+
+async fn connect<T: TaskStore>(
+    server: Server,
+    tasks: TaskManager<T>,
+) -> anyhow::Result<(RunningService<RoleClient, Client>, Client)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = tasks.wrap(server).serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = Client::default();
+    let service = client.clone().serve(client_transport).await?;
+    Ok((service, client))
+}
+
+fn call(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+fn text(result: &rmcp::model::CallToolResult) -> &str {
+    match &result.content[0].raw {
+        RawContent::Text(text) => &text.text,
+        other => panic!("expected text content, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_task_completes_in_background() -> anyhow::Result<()> {
+    let (client, handler) = connect(Server::new(), TaskManager::default()).await?;
+    assert!(
+        client
+            .peer_info()
+            .and_then(|info| info.capabilities.experimental.as_ref())
+            .is_some_and(|experimental| experimental.contains_key("tasks"))
+    );
+
+    let mut task = client
+        .call_tool_as_task(call("slow"), Some(Duration::from_secs(60)))
+        .await?;
+    assert_eq!(task.task().status, TaskStatus::Working);
+    assert_eq!(task.task().ttl, Some(60_000));
+    assert_eq!(task.refresh().await?.status, TaskStatus::Working);
+
+    let result = task.poll_result().await?;
+    assert_eq!(text(&result), "done");
+    assert_eq!(task.task().status, TaskStatus::Completed);
+    // the result stays available
+    assert_eq!(text(&task.result().await?), "done");
+
+    let statuses = handler.statuses.lock().unwrap().clone();
+    assert_eq!(statuses.len(), 1, "{statuses:?}");
+    assert_eq!(statuses[0].task_id, task.id());
+    assert_eq!(statuses[0].status, TaskStatus::Completed);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_result_waits_and_reports_failures() -> anyhow::Result<()> {
+    let (client, _handler) = connect(Server::new(), TaskManager::default()).await?;
+    let slow = client.call_tool_as_task(call("slow"), None).await?;
+    let fail = client.call_tool_as_task(call("fail"), None).await?;
+
+    assert_eq!(text(&slow.result().await?), "done");
+    let error = fail.result().await.expect_err("the tool fails");
+    let ServiceError::McpError(error) = error else {
+        panic!("expected an mcp error, got {error:?}");
+    };
+    assert_eq!(error.message, "broken");
+
+    let tasks = client.list_all_tasks().await?;
+    let ids: Vec<_> = tasks.iter().map(|task| task.task_id.as_str()).collect();
+    assert_eq!(ids, [slow.id(), fail.id()]);
+    assert_eq!(tasks[1].status, TaskStatus::Failed);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_task() -> anyhow::Result<()> {
+    let server = Server::new();
+    let (client, _handler) = connect(server.clone(), TaskManager::default()).await?;
+    let mut task = client.call_tool_as_task(call("forever"), None).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(task.cancel().await?.status, TaskStatus::Cancelled);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(server.cancelled.load(Ordering::SeqCst));
+    assert!(task.result().await.is_err());
+    // finished tasks can't be cancelled
+    assert!(task.cancel().await.is_err());
+
+    client.cancel().await?;
+    Ok(())
+}
+
+/// A store whose updates wait to be released.
+struct GatedStore {
+    inner: InMemoryTaskStore,
+    updating: tokio::sync::Notify,
+    release: tokio::sync::Semaphore,
+}
+
+impl TaskStore for GatedStore {
+    type Error = std::convert::Infallible;
+
+    async fn insert(&self, task: StoredTask) -> Result<(), Self::Error> {
+        self.inner.insert(task).await
+    }
+
+    async fn get(&self, task_id: &str) -> Result<Option<StoredTask>, Self::Error> {
+        self.inner.get(task_id).await
+    }
+
+    async fn update(&self, task: StoredTask) -> Result<(), Self::Error> {
+        self.updating.notify_one();
+        self.release
+            .acquire()
+            .await
+            .expect("open semaphore")
+            .forget();
+        self.inner.update(task).await
+    }
+
+    async fn list(&self, owner: &str) -> Result<Vec<Task>, Self::Error> {
+        self.inner.list(owner).await
+    }
+
+    async fn remove_expired(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<String>, Self::Error> {
+        self.inner.remove_expired(now).await
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_while_the_result_is_saved() -> anyhow::Result<()> {
+    let tasks = TaskManager::new(
+        GatedStore {
+            inner: InMemoryTaskStore::default(),
+            updating: tokio::sync::Notify::new(),
+            release: tokio::sync::Semaphore::new(0),
+        },
+        TaskManagerConfig::default(),
+    );
+    let store = tasks.store();
+    let (client, handler) = connect(Server::new(), tasks.clone()).await?;
+    let mut task = client.call_tool_as_task(call("slow"), None).await?;
+
+    // the tool has finished, its result is being saved
+    tokio::time::timeout(Duration::from_secs(5), store.updating.notified()).await?;
+    let cancel = tokio::time::timeout(Duration::from_secs(5), task.cancel()).await?;
+    assert!(cancel.is_err());
+    store.release.add_permits(1);
+
+    assert_eq!(text(&task.result().await?), "done");
+    assert_eq!(task.refresh().await?.status, TaskStatus::Completed);
+    let statuses = handler.statuses.lock().unwrap().clone();
+    assert_eq!(statuses.len(), 1, "{statuses:?}");
+    assert_eq!(statuses[0].status, TaskStatus::Completed);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tasks_are_private_to_their_connection() -> anyhow::Result<()> {
+    let tasks = TaskManager::default();
+    let (owner, _handler) = connect(Server::new(), tasks.clone()).await?;
+    let (other, _handler) = connect(Server::new(), tasks).await?;
+    let task = owner.call_tool_as_task(call("forever"), None).await?;
+
+    let param = TaskRequestParam {
+        task_id: task.id().to_owned(),
+    };
+    assert!(other.get_task(param.clone()).await.is_err());
+    assert!(other.get_task_result(param.clone()).await.is_err());
+    assert!(other.cancel_task(param.clone()).await.is_err());
+    assert!(other.list_all_tasks().await?.is_empty());
+    assert_eq!(
+        owner.get_task(param).await?.task.status,
+        TaskStatus::Working
+    );
+    assert_eq!(owner.list_all_tasks().await?.len(), 1);
+
+    owner.cancel().await?;
+    other.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_tasks_are_removed() -> anyhow::Result<()> {
+    let tasks = TaskManager::new(
+        InMemoryTaskStore::default(),
+        TaskManagerConfig {
+            default_ttl: Some(Duration::from_millis(100)),
+            cleanup_interval: Duration::from_millis(50),
+            ..Default::default()
+        },
+    );
+    let (client, _handler) = connect(Server::new(), tasks.clone()).await?;
+    let task = client.call_tool_as_task(call("fail"), None).await?;
+    assert_eq!(task.task().ttl, Some(100));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(tasks.store().get(task.id()).await?.is_none());
+    let error = client
+        .get_task(TaskRequestParam {
+            task_id: task.id().to_owned(),
+        })
+        .await;
+    assert!(error.is_err());
+    assert!(client.list_all_tasks().await?.is_empty());
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_without_tasks() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = Server::new().serve(server_transport).await?;
+        service.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = Client::default().serve(client_transport).await?;
+
+    let error = client.call_tool_as_task(call("slow"), None).await;
+    assert!(matches!(error, Err(ServiceError::UnexpectedResponse)));
+    assert!(client.list_tasks(None).await.is_err());
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http::{Request, StatusCode};
+use http_body_util::{BodyExt, Full};
+use rmcp::{
+    ErrorData, RoleServer, ServerHandler, ServiceExt,
+    model::{ListToolsResult, PaginatedRequestParam, TraceContext},
+    service::{RequestContext, ServeConfig, propagation::TracePropagator},
+    transport::{
+        StreamableHttpServerConfig,
+        streamable_http_server::{
+            session::local::LocalSessionManager, tower::StreamableHttpService,
+        },
+    },
+};
+use serde_json::json;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+/// Records the trace contexts the handler receives.
+#[derive(Clone, Default)]
+struct Server {
+    received: Arc<Mutex<Vec<Option<TraceContext>>>>,
+}
+
+impl ServerHandler for Server {
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.received
+            .lock()
+            .unwrap()
+            .push(context.meta.get_trace_context());
+        Ok(ListToolsResult::default())
+    }
+}
+
+#[derive(Clone, Default)]
+struct FixedPropagator {
+    extracted: Arc<Mutex<Vec<TraceContext>>>,
+}
+
+impl TracePropagator for FixedPropagator {
+    fn inject(&self, _span: &tracing::Span) -> Option<TraceContext> {
+        Some(TraceContext::new(TRACEPARENT, Some("vendor=value".into())))
+    }
+
+    fn extract(&self, context: &TraceContext, _span: &tracing::Span) {
+        self.extracted.lock().unwrap().push(context.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_trace_context_is_propagated() -> anyhow::Result<()> {
+    let server = Server::default();
+    let server_propagator = FixedPropagator::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn({
+        let server = server.clone();
+        let config = ServeConfig::default().with_trace_propagator(server_propagator.clone());
+        async move {
+            let service = server
+                .serve_with_config(server_transport, config, Default::default())
+                .await?;
+            service.waiting().await?;
+            anyhow::Ok(())
+        }
+    });
+    let client = ()
+        .serve_with_config(
+            client_transport,
+            ServeConfig::default().with_trace_propagator(FixedPropagator::default()),
+            Default::default(),
+        )
+        .await?;
+    client.list_tools(None).await?;
+    client.cancel().await?;
+
+    let expected = TraceContext::new(TRACEPARENT, Some("vendor=value".into()));
+    assert_eq!(*server.received.lock().unwrap(), [Some(expected.clone())]);
+    assert_eq!(*server_propagator.extracted.lock().unwrap(), [expected]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_trace_context_is_optional() -> anyhow::Result<()> {
+    let server = Server::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            let service = server.serve(server_transport).await?;
+            service.waiting().await?;
+            anyhow::Ok(())
+        }
+    });
+    let client = ().serve(client_transport).await?;
+    client.list_tools(None).await?;
+    client.cancel().await?;
+
+    assert_eq!(*server.received.lock().unwrap(), [None]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_headers_carry_trace_context() -> anyhow::Result<()> {
+    let server = Server::default();
+    let service = StreamableHttpService::new(
+        {
+            let server = server.clone();
+            move || Ok(server.clone())
+        },
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig {
+            stateful_mode: false,
+            ..Default::default()
+        },
+    );
+    let request = Request::post("/mcp")
+        .header("Accept", "application/json, text/event-stream")
+        .header("Content-Type", "application/json")
+        .header("traceparent", TRACEPARENT)
+        .header("tracestate", "vendor=value")
+        .body(Full::new(Bytes::from(
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }).to_string(),
+        )))?;
+    let response = service.handle(request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // the handler has run once the response is streamed
+    response.into_body().frame().await;
+
+    assert_eq!(
+        *server.received.lock().unwrap(),
+        [Some(TraceContext::new(
+            TRACEPARENT,
+            Some("vendor=value".into())
+        ))]
+    );
+    Ok(())
+}
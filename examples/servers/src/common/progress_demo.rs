@@ -6,8 +6,10 @@ use std::{
 
 use futures::Stream;
 use rmcp::{
-    ErrorData as McpError, RoleServer, ServerHandler, handler::server::tool::ToolRouter, model::*,
-    service::RequestContext, tool, tool_handler, tool_router,
+    ErrorData as McpError, ServerHandler,
+    handler::server::{progress::Progress, tool::ToolRouter},
+    model::*,
+    tool, tool_handler, tool_router,
 };
 use serde_json::json;
 use tokio_stream::StreamExt;
@@ -67,13 +69,11 @@ impl ProgressDemo {
         }
     }
     #[tool(description = "Process data stream with progress updates")]
-    async fn stream_processor(
-        &self,
-        ctx: RequestContext<RoleServer>,
-    ) -> Result<CallToolResult, McpError> {
+    async fn stream_processor(&self, progress: Progress) -> Result<CallToolResult, McpError> {
         let mut counter = 0;
 
         let mut data_source = self.data_source.clone();
+        let total = data_source.data.len() as f64;
         loop {
             let chunk = data_source.next().await;
             if chunk.is_none() {
@@ -83,15 +83,11 @@ impl ProgressDemo {
             let chunk = chunk.unwrap().unwrap();
             let chunk_str = String::from_utf8_lossy(&chunk);
             counter += 1;
-            // create progress notification param
-            let progress_param = ProgressNotificationParam {
-                progress_token: ProgressToken(NumberOrString::Number(counter)),
-                progress: counter as f64,
-                total: None,
-                message: Some(chunk_str.to_string()),
-            };
 
-            match ctx.peer.notify_progress(progress_param).await {
+            match progress
+                .report(counter as f64, Some(total), Some(chunk_str.to_string()))
+                .await
+            {
                 Ok(_) => {
                     debug!("Processed record: {}", chunk_str);
                 }